@@ -6,18 +6,28 @@ using System.Threading;
 using Newtonsoft.Json;
 using System.Runtime.InteropServices;
 
-class HardwareData
+// Protocol version 2: the whole sensor tree, one JSON object per line.
+// The Rust side derives its fixed fields (CPU temperature, power, ...) from it.
+class SensorData
 {
+    public string HardwareId { get; set; }
+    public string HardwareName { get; set; }
+    public string HardwareType { get; set; }
+    public string Id { get; set; }
+    public string Type { get; set; }
+    public string Name { get; set; }
+    public float? Value { get; set; }
+    public float? Min { get; set; }
+    public float? Max { get; set; }
+}
+
+class SensorMessage
+{
+    public int Version { get; set; } = 2;
     public string MotherboardModel { get; set; }
-    public float CpuTemp { get; set; }
-    public List<float?> CcdTemperatures { get; set; } = new List<float?>();
-    public float? CpuVoltage { get; set; }
-    public float? CpuPower { get; set; }
-    public float? ChipsetTemp { get; set; }
-    public float MemoryUsage { get; set; }
-    public float? MemoryTemp { get; set; }
     public int TotalMemoryMB { get; set; }
     public int MemorySpeedMTS { get; set; }
+    public List<SensorData> Sensors { get; set; } = new List<SensorData>();
 }
 
 partial class Program
@@ -99,7 +109,8 @@ partial class Program
         {
             IsCpuEnabled = true,
             IsMemoryEnabled = true,
-            IsMotherboardEnabled = true
+            IsMotherboardEnabled = true,
+            IsControllerEnabled = true
         };
 
         try
@@ -145,82 +156,14 @@ partial class Program
             if (!IsProcessAlive(parentPid)) {
                 break;
             }
-            var data = new HardwareData();
+            var data = new SensorMessage();
             data.MotherboardModel = motherboardModel;
             data.TotalMemoryMB = totalMemoryMB;
             data.MemorySpeedMTS = maxMemorySpeed;
 
             foreach (IHardware hardware in computer.Hardware)
             {
-                hardware.Update();
-                if (hardware.HardwareType == HardwareType.Cpu)
-                {
-                    foreach (ISensor sensor in hardware.Sensors)
-                    {
-                        if (sensor.SensorType == SensorType.Temperature)
-                        {
-                            data.CpuTemp = sensor.Value ?? 0;
-                        }
-                        else if (sensor.SensorType == SensorType.Temperature && sensor.Name.StartsWith("CCD") && sensor.Name.EndsWith("(Tdie)"))
-                        {
-                            data.CcdTemperatures.Add(sensor.Value);
-                        }
-                        else if (sensor.SensorType == SensorType.Voltage && sensor.Name == "Core (SVI2 TFN)")
-                        {
-                            data.CpuVoltage = sensor.Value;
-                        }
-                        else if (sensor.SensorType == SensorType.Power && sensor.Name == "Package")
-                        {
-                            data.CpuPower = sensor.Value;
-                        }
-                    }
-                }
-                else if (hardware.HardwareType == HardwareType.Memory)
-                {
-                    foreach (ISensor sensor in hardware.Sensors)
-                    {
-                        if (sensor.SensorType == SensorType.Load)
-                        {
-                            data.MemoryUsage = sensor.Value ?? 0;
-                        }
-                        else if (sensor.SensorType == SensorType.Temperature)
-                        {
-                            data.MemoryTemp = sensor.Value;
-                        }
-                    }
-                    foreach (IHardware subHardware in hardware.SubHardware)
-                    {
-                        subHardware.Update();
-                        foreach (ISensor sensor in subHardware.Sensors)
-                        {
-                            if (sensor.SensorType == SensorType.Temperature && data.MemoryTemp == null)
-                            {
-                                data.MemoryTemp = sensor.Value;
-                            }
-                        }
-                    }
-                }
-                else if (hardware.HardwareType == HardwareType.Motherboard)
-                {
-                    foreach (ISensor sensor in hardware.Sensors)
-                    {
-                        if (sensor.SensorType == SensorType.Temperature && data.ChipsetTemp == null)
-                        {
-                            data.ChipsetTemp = sensor.Value;
-                        }
-                    }
-                    foreach (IHardware subHardware in hardware.SubHardware)
-                    {
-                        subHardware.Update();
-                        foreach (ISensor sensor in subHardware.Sensors)
-                        {
-                            if (sensor.SensorType == SensorType.Temperature && data.ChipsetTemp == null)
-                            {
-                                data.ChipsetTemp = sensor.Value;
-                            }
-                        }
-                    }
-                }
+                AddSensors(data, hardware);
             }
 
             try
@@ -237,4 +180,30 @@ partial class Program
 
         // computer.Close(); // never reached
     }
+
+    // Updates the hardware and appends its sensors, then those of its subhardware
+    // (e.g. the SuperIO chip below the motherboard, DIMMs below memory).
+    static void AddSensors(SensorMessage data, IHardware hardware)
+    {
+        hardware.Update();
+        foreach (ISensor sensor in hardware.Sensors)
+        {
+            data.Sensors.Add(new SensorData
+            {
+                HardwareId = hardware.Identifier.ToString(),
+                HardwareName = hardware.Name,
+                HardwareType = hardware.HardwareType.ToString(),
+                Id = sensor.Identifier.ToString(),
+                Type = sensor.SensorType.ToString(),
+                Name = sensor.Name,
+                Value = sensor.Value,
+                Min = sensor.Min,
+                Max = sensor.Max
+            });
+        }
+        foreach (IHardware subHardware in hardware.SubHardware)
+        {
+            AddSensors(data, subHardware);
+        }
+    }
 }
\ No newline at end of file
@@ -1,5 +1,10 @@
+use std::path::PathBuf;
+use std::process::Command;
+
 fn main() {
     if std::env::var("CARGO_CFG_TARGET_OS").unwrap() == "windows" {
+        build_temp_monitor();
+
         let mut res = winresource::WindowsResource::new();
         res.set("FileDescription", "CuteMonitor");
         res.set("ProductName", "CuteMonitor");
@@ -7,13 +12,15 @@ fn main() {
         res.set_version_info(winresource::VersionInfo::FILEVERSION, 0x0000000000030005);
         res.set_version_info(winresource::VersionInfo::PRODUCTVERSION, 0x0000000000030005);
         res.set_icon("cutemonitor.ico");
+        // asInvoker so --json and --replay run without a UAC prompt;
+        // the window relaunches itself elevated in main()
         res.set_manifest(
             r#"
 <assembly xmlns="urn:schemas-microsoft-com:asm.v1" manifestVersion="1.0">
 <trustInfo xmlns="urn:schemas-microsoft-com:asm.v3">
     <security>
         <requestedPrivileges>
-            <requestedExecutionLevel level="requireAdministrator" uiAccess="false" />
+            <requestedExecutionLevel level="asInvoker" uiAccess="false" />
         </requestedPrivileges>
     </security>
 </trustInfo>
@@ -23,3 +30,32 @@ fn main() {
         res.compile().unwrap();
     }
 }
+
+/// Builds TempMonitor.exe from TempMonitor.cs into `OUT_DIR/TempMonitor`, where
+/// hardware_checker.rs embeds it from. Without the .NET SDK (`dotnet`), or if the
+/// build fails (its NuGet restore needs the network), the checked-in
+/// TempMonitor.exe is embedded instead; it prints protocol version 1, so the
+/// sensor tree and the fan panel stay empty.
+fn build_temp_monitor() {
+    println!("cargo:rerun-if-changed=TempMonitor.cs");
+    println!("cargo:rerun-if-changed=TempMonitor.csproj");
+    println!("cargo:rerun-if-changed=TempMonitor.exe");
+    println!("cargo:rerun-if-changed=LibreHardwareMonitorLib.dll");
+
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap()).join("TempMonitor");
+    let status = Command::new("dotnet")
+        .args(["build", "TempMonitor.csproj", "--configuration", "Release", "--output"])
+        .arg(&out_dir)
+        .status();
+    let reason = match status {
+        Ok(status) if status.success() => return,
+        Ok(status) => format!("dotnet build TempMonitor.csproj failed ({})", status),
+        Err(e) => format!("the .NET SDK (dotnet) is not on the PATH ({})", e),
+    };
+    println!(
+        "cargo:warning=Embedding the checked-in TempMonitor.exe (protocol version 1) because {}",
+        reason
+    );
+    std::fs::create_dir_all(&out_dir).unwrap();
+    std::fs::copy("TempMonitor.exe", out_dir.join("TempMonitor.exe")).unwrap();
+}
@@ -0,0 +1,590 @@
+//! Alert rules for CuteMonitor.
+//!
+//! Rules are loaded from a JSON file passed with `--alerts <file>`:
+//!
+//! ```json
+//! {
+//!   "command": "msg * \"%CUTEMONITOR_ALERT%\"",
+//!   "log": "alerts.log",
+//!   "rules": [
+//!     { "metric": "cpu_temp", "above": 90, "for_secs": 10, "hysteresis": 5 },
+//!     { "metric": "gpu_memory_usage", "gpu": 0, "above": 95 },
+//!     { "metric": "download_mbps", "below": 1, "for_secs": 60, "hysteresis": 0.5 },
+//!     { "metric": "sensor", "sensor": "/lpc/nct6798d/fan/1", "below": 300 }
+//!   ]
+//! }
+//! ```
+//!
+//! A rule fires once its condition has held for `for_secs` and clears only
+//! after the value moved `hysteresis` back past the threshold, so a reading
+//! hovering around the limit doesn't flap. Firing rules are shown as a banner
+//! in the window; the optional command runs and the optional log gets a line
+//! whenever a rule fires or clears.
+
+use std::collections::BTreeSet;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+
+use crate::gpu_data::GpuData;
+use crate::hardware_checker::HardwareData;
+use crate::interface_stats::NetworkStats;
+use crate::sensors::SensorTree;
+
+/// Contents of the `--alerts` file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertConfig {
+    /// Shell command run whenever a rule fires or clears.
+    #[serde(default)]
+    pub command: Option<String>,
+    /// File that gets one line per fired or cleared rule.
+    #[serde(default)]
+    pub log: Option<PathBuf>,
+    pub rules: Vec<AlertRule>,
+}
+
+/// Value an alert rule watches.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    CpuTemp,
+    CpuPower,
+    ChipsetTemp,
+    MemoryUsage,
+    CpuUsage,
+    GpuTemp,
+    GpuUtilization,
+    GpuMemoryUsage,
+    DownloadMbps,
+    UploadMbps,
+    /// Any sensor of the LibreHardwareMonitor tree, picked by `sensor` id.
+    Sensor,
+}
+
+/// A single threshold rule.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertRule {
+    /// Name shown in the banner (defaults to a description of the condition).
+    #[serde(default)]
+    pub name: Option<String>,
+    pub metric: Metric,
+    /// Fire when the value rises above this.
+    #[serde(default)]
+    pub above: Option<f64>,
+    /// Fire when the value falls below this.
+    #[serde(default)]
+    pub below: Option<f64>,
+    /// How long the condition has to hold before the rule fires.
+    #[serde(default)]
+    pub for_secs: f64,
+    /// How far the value has to move back past the threshold to clear.
+    #[serde(default)]
+    pub hysteresis: f64,
+    /// GPU index for the `gpu_*` metrics (default 0).
+    #[serde(default)]
+    pub gpu: usize,
+    /// Interface for the network metrics (default: all non-loopback interfaces).
+    #[serde(default)]
+    pub interface: Option<String>,
+    /// Sensor id for the `sensor` metric, e.g. `/lpc/nct6798d/fan/1`.
+    #[serde(default)]
+    pub sensor: Option<String>,
+}
+
+impl AlertRule {
+    /// Checks that exactly one of `above`/`below` is set and the numbers make sense.
+    fn validate(&self) -> Result<(), String> {
+        match (self.above, self.below) {
+            (Some(_), None) | (None, Some(_)) => {}
+            _ => {
+                return Err(format!(
+                    "{}: set exactly one of \"above\" or \"below\"",
+                    self.label()
+                ))
+            }
+        }
+        if self.metric == Metric::Sensor && self.sensor.is_none() {
+            return Err(format!(
+                "{}: the \"sensor\" metric needs a \"sensor\" id",
+                self.label()
+            ));
+        }
+        if !(self.for_secs >= 0.0 && self.for_secs.is_finite()) {
+            return Err(format!(
+                "{}: \"for_secs\" must be zero or positive",
+                self.label()
+            ));
+        }
+        if !(self.hysteresis >= 0.0 && self.hysteresis.is_finite()) {
+            return Err(format!(
+                "{}: \"hysteresis\" must be zero or positive",
+                self.label()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Name shown in the banner, log and command environment.
+    pub fn label(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+
+        let metric = match self.metric {
+            Metric::CpuTemp => "CPU temperature".to_string(),
+            Metric::CpuPower => "CPU power".to_string(),
+            Metric::ChipsetTemp => "Chipset temperature".to_string(),
+            Metric::MemoryUsage => "Memory usage".to_string(),
+            Metric::CpuUsage => "CPU usage".to_string(),
+            Metric::GpuTemp => format!("GPU {} temperature", self.gpu),
+            Metric::GpuUtilization => format!("GPU {} utilization", self.gpu),
+            Metric::GpuMemoryUsage => format!("GPU {} memory usage", self.gpu),
+            Metric::DownloadMbps => format!("Download{}", self.interface_suffix()),
+            Metric::UploadMbps => format!("Upload{}", self.interface_suffix()),
+            Metric::Sensor => format!("Sensor {}", self.sensor.as_deref().unwrap_or_default()),
+        };
+        match (self.above, self.below) {
+            (Some(above), _) => format!("{} > {}", metric, above),
+            (_, Some(below)) => format!("{} < {}", metric, below),
+            _ => metric,
+        }
+    }
+
+    fn interface_suffix(&self) -> String {
+        self.interface
+            .as_ref()
+            .map_or(String::new(), |name| format!(" ({})", name))
+    }
+
+    /// True if the value is past the threshold.
+    fn triggered(&self, value: f64) -> bool {
+        match (self.above, self.below) {
+            (Some(above), _) => value > above,
+            (_, Some(below)) => value < below,
+            _ => false,
+        }
+    }
+
+    /// True if a firing rule may clear: the value moved back by `hysteresis`.
+    fn cleared(&self, value: f64) -> bool {
+        match (self.above, self.below) {
+            (Some(above), _) => value <= above - self.hysteresis,
+            (_, Some(below)) => value >= below + self.hysteresis,
+            _ => true,
+        }
+    }
+
+    /// Reads the watched value from a sample, or None if the sample doesn't carry it.
+    fn read(&self, sample: &Sample) -> Option<f64> {
+        match (self.metric, sample) {
+            (Metric::CpuTemp, Sample::Hardware(data)) => Some(data.cpu_temp as f64),
+            (Metric::CpuPower, Sample::Hardware(data)) => data.cpu_power.map(f64::from),
+            (Metric::ChipsetTemp, Sample::Hardware(data)) => data.chipset_temp.map(f64::from),
+            (Metric::MemoryUsage, Sample::Hardware(data)) => Some(data.memory_usage as f64),
+            (Metric::CpuUsage, Sample::Threads(usages)) if !usages.is_empty() => {
+                Some(usages.iter().sum::<f32>() as f64 / usages.len() as f64)
+            }
+            (Metric::GpuTemp, Sample::Gpus(gpus)) => gpus.get(self.gpu)?.temp.map(f64::from),
+            (Metric::GpuUtilization, Sample::Gpus(gpus)) => {
+                gpus.get(self.gpu)?.utilization.map(f64::from)
+            }
+            (Metric::GpuMemoryUsage, Sample::Gpus(gpus)) => {
+                gpus.get(self.gpu)?.memory_usage.map(f64::from)
+            }
+            (Metric::DownloadMbps | Metric::UploadMbps, Sample::Network(stats)) => {
+                let (upload_bps, download_bps) = match &self.interface {
+                    Some(name) => {
+                        let interface = stats.interfaces.iter().find(|i| &i.name == name)?;
+                        (interface.upload_bps, interface.download_bps)
+                    }
+                    None => stats.selected_rates(&BTreeSet::new()),
+                };
+                let bps = if self.metric == Metric::DownloadMbps {
+                    download_bps
+                } else {
+                    upload_bps
+                };
+                Some(bps * 8.0 / 1_000_000.0)
+            }
+            (Metric::Sensor, Sample::Sensors(tree)) => {
+                tree.get(self.sensor.as_deref()?)?.value.map(f64::from)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A reading from one of the collectors.
+pub enum Sample<'a> {
+    Hardware(&'a HardwareData),
+    Threads(&'a [f32]),
+    Gpus(&'a [GpuData]),
+    Network(&'a NetworkStats),
+    Sensors(&'a SensorTree),
+}
+
+/// Where a rule is in its lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RuleState {
+    /// Condition not met.
+    Idle,
+    /// Condition met since the given time, waiting for `for_secs`.
+    Pending(Instant),
+    /// Rule fired and hasn't cleared yet.
+    Firing,
+}
+
+/// Change reported by [`AlertEngine::observe`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertEvent {
+    Fired { rule: String, value: f64 },
+    Cleared { rule: String, value: f64 },
+}
+
+impl AlertEvent {
+    /// One-line description used for the log and the command.
+    pub fn describe(&self) -> String {
+        match self {
+            AlertEvent::Fired { rule, value } => format!("FIRED {} (value {:.1})", rule, value),
+            AlertEvent::Cleared { rule, value } => format!("CLEARED {} (value {:.1})", rule, value),
+        }
+    }
+}
+
+/// Tracks every rule's state and the latest value it saw.
+#[derive(Default)]
+pub struct AlertEngine {
+    rules: Vec<(AlertRule, RuleState, f64)>,
+}
+
+impl AlertEngine {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        Self {
+            rules: rules
+                .into_iter()
+                .map(|rule| (rule, RuleState::Idle, 0.0))
+                .collect(),
+        }
+    }
+
+    /// Creates an engine with the rules from `--alerts`, or none.
+    pub fn from_config() -> Self {
+        Self::new(
+            config()
+                .map(|config| config.rules.clone())
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Feeds a sample to every rule that watches it and returns what changed.
+    pub fn observe(&mut self, sample: &Sample, now: Instant) -> Vec<AlertEvent> {
+        let mut events = Vec::new();
+
+        for (rule, state, last_value) in &mut self.rules {
+            let Some(value) = rule.read(sample) else {
+                continue;
+            };
+            *last_value = value;
+
+            match *state {
+                RuleState::Idle | RuleState::Pending(_) if !rule.triggered(value) => {
+                    *state = RuleState::Idle;
+                }
+                RuleState::Idle => *state = RuleState::Pending(now),
+                RuleState::Pending(_) | RuleState::Firing => {}
+            }
+
+            if let RuleState::Pending(since) = *state {
+                if now.duration_since(since) >= Duration::from_secs_f64(rule.for_secs) {
+                    *state = RuleState::Firing;
+                    events.push(AlertEvent::Fired {
+                        rule: rule.label(),
+                        value,
+                    });
+                }
+            } else if *state == RuleState::Firing && rule.cleared(value) {
+                *state = RuleState::Idle;
+                events.push(AlertEvent::Cleared {
+                    rule: rule.label(),
+                    value,
+                });
+            }
+        }
+
+        events
+    }
+
+    /// Labels and latest values of the rules currently firing.
+    pub fn firing(&self) -> Vec<(String, f64)> {
+        self.rules
+            .iter()
+            .filter(|(_, state, _)| *state == RuleState::Firing)
+            .map(|(rule, _, value)| (rule.label(), *value))
+            .collect()
+    }
+}
+
+static CONFIG: OnceLock<AlertConfig> = OnceLock::new();
+
+/// Reads and validates an alert file.
+pub fn load_config(path: &Path) -> Result<AlertConfig, String> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let config: AlertConfig =
+        serde_json::from_str(&contents).map_err(|e| format!("{}: {}", path.display(), e))?;
+    for rule in &config.rules {
+        rule.validate()?;
+    }
+    Ok(config)
+}
+
+/// Installs the alert configuration used by [`AlertEngine::from_config`].
+pub fn enable(config: AlertConfig) {
+    let _ = CONFIG.set(config);
+}
+
+fn config() -> Option<&'static AlertConfig> {
+    CONFIG.get()
+}
+
+/// Appends the events to the alert log and runs the alert command, if configured.
+pub fn dispatch(events: &[AlertEvent]) {
+    let Some(config) = config() else {
+        return;
+    };
+
+    for event in events {
+        let description = event.describe();
+
+        if let Some(path) = &config.log {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs());
+            let appended = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| writeln!(file, "{} {}", timestamp, description));
+            if let Err(e) = appended {
+                eprintln!("Failed to append to alert log {}: {}", path.display(), e);
+            }
+        }
+
+        if let Some(command) = &config.command {
+            let (rule, state) = match event {
+                AlertEvent::Fired { rule, .. } => (rule, "fired"),
+                AlertEvent::Cleared { rule, .. } => (rule, "cleared"),
+            };
+            // Run detached; the monitor must not wait on a notification tool
+            let spawned = shell_command(command)
+                .env("CUTEMONITOR_ALERT", &description)
+                .env("CUTEMONITOR_ALERT_RULE", rule)
+                .env("CUTEMONITOR_ALERT_STATE", state)
+                .spawn();
+            match spawned {
+                // Reap the child on its own thread so it doesn't linger as a zombie
+                Ok(mut child) => {
+                    std::thread::spawn(move || child.wait());
+                }
+                Err(e) => eprintln!("Failed to run alert command: {}", e),
+            }
+        }
+    }
+}
+
+/// Builds a command that runs `command` through the platform shell.
+fn shell_command(command: &str) -> std::process::Command {
+    #[cfg(target_os = "windows")]
+    {
+        let mut shell = std::process::Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let mut shell = std::process::Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface_stats::InterfaceStats;
+
+    fn rule(json: &str) -> AlertRule {
+        let rule: AlertRule = serde_json::from_str(json).unwrap();
+        rule.validate().unwrap();
+        rule
+    }
+
+    fn hardware(cpu_temp: f32) -> HardwareData {
+        serde_json::from_value(serde_json::json!({
+            "MotherboardModel": "", "CpuTemp": cpu_temp, "CcdTemperatures": [],
+            "CpuVoltage": null, "CpuPower": null, "ChipsetTemp": null,
+            "MemoryUsage": 0.0, "TotalMemoryMB": 0, "MemorySpeedMTS": 0
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_fires_after_duration_and_clears_with_hysteresis() {
+        let mut engine = AlertEngine::new(vec![rule(
+            r#"{"metric": "cpu_temp", "above": 90, "for_secs": 10, "hysteresis": 5}"#,
+        )]);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert!(engine
+            .observe(&Sample::Hardware(&hardware(95.0)), at(0))
+            .is_empty());
+        assert!(engine
+            .observe(&Sample::Hardware(&hardware(95.0)), at(9))
+            .is_empty());
+        let events = engine.observe(&Sample::Hardware(&hardware(96.0)), at(10));
+        assert_eq!(
+            events,
+            vec![AlertEvent::Fired {
+                rule: "CPU temperature > 90".to_string(),
+                value: 96.0
+            }]
+        );
+        assert_eq!(
+            engine.firing(),
+            vec![("CPU temperature > 90".to_string(), 96.0)]
+        );
+
+        // Inside the hysteresis band the rule keeps firing
+        assert!(engine
+            .observe(&Sample::Hardware(&hardware(87.0)), at(11))
+            .is_empty());
+        assert_eq!(engine.firing().len(), 1);
+
+        let events = engine.observe(&Sample::Hardware(&hardware(85.0)), at(12));
+        assert!(matches!(events.as_slice(), [AlertEvent::Cleared { .. }]));
+        assert!(engine.firing().is_empty());
+    }
+
+    #[test]
+    fn test_pending_resets_when_condition_breaks() {
+        let mut engine = AlertEngine::new(vec![rule(
+            r#"{"metric": "cpu_temp", "above": 90, "for_secs": 10}"#,
+        )]);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        engine.observe(&Sample::Hardware(&hardware(95.0)), at(0));
+        engine.observe(&Sample::Hardware(&hardware(80.0)), at(5));
+        assert!(engine
+            .observe(&Sample::Hardware(&hardware(95.0)), at(10))
+            .is_empty());
+        assert_eq!(
+            engine
+                .observe(&Sample::Hardware(&hardware(95.0)), at(20))
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_gpu_and_network_rules() {
+        let mut engine = AlertEngine::new(vec![
+            rule(r#"{"metric": "gpu_memory_usage", "gpu": 1, "above": 95}"#),
+            rule(r#"{"metric": "download_mbps", "below": 1, "for_secs": 60}"#),
+        ]);
+        let now = Instant::now();
+
+        let gpus = vec![
+            GpuData {
+                memory_usage: Some(99.0),
+                ..Default::default()
+            },
+            GpuData {
+                memory_usage: Some(97.0),
+                ..Default::default()
+            },
+        ];
+        let events = engine.observe(&Sample::Gpus(&gpus), now);
+        assert_eq!(
+            events,
+            vec![AlertEvent::Fired {
+                rule: "GPU 1 memory usage > 95".to_string(),
+                value: 97.0
+            }]
+        );
+
+        let stats = NetworkStats {
+            interfaces: vec![InterfaceStats {
+                name: "eth0".to_string(),
+                upload_bps: 0.0,
+                download_bps: 50_000.0, // 0.4 Mbps
+                total_bytes_sent: 0,
+                total_bytes_received: 0,
+                packets_sent: 0,
+                packets_received: 0,
+                errors_sent: 0,
+                errors_received: 0,
+            }],
+            dropped_interfaces: Vec::new(),
+        };
+        assert!(engine.observe(&Sample::Network(&stats), now).is_empty());
+        let events = engine.observe(&Sample::Network(&stats), now + Duration::from_secs(60));
+        assert!(
+            matches!(events.as_slice(), [AlertEvent::Fired { value, .. }] if (*value - 0.4).abs() < 1e-9)
+        );
+    }
+
+    #[test]
+    fn test_sensor_rule() {
+        let mut engine = AlertEngine::new(vec![rule(
+            r#"{"metric": "sensor", "sensor": "/lpc/nct6798d/fan/1", "below": 300}"#,
+        )]);
+        let tree = |rpm: f32| SensorTree {
+            sensors: vec![crate::sensors::Sensor {
+                hardware_id: "/lpc/nct6798d".to_string(),
+                hardware_name: "Nuvoton NCT6798D".to_string(),
+                hardware_type: "SuperIO".to_string(),
+                id: "/lpc/nct6798d/fan/1".to_string(),
+                sensor_type: "Fan".to_string(),
+                name: "Pump".to_string(),
+                value: Some(rpm),
+                min: None,
+                max: None,
+            }],
+        };
+
+        assert!(engine.observe(&Sample::Sensors(&tree(2400.0)), Instant::now()).is_empty());
+        assert_eq!(
+            engine.observe(&Sample::Sensors(&tree(0.0)), Instant::now()),
+            vec![AlertEvent::Fired {
+                rule: "Sensor /lpc/nct6798d/fan/1 < 300".to_string(),
+                value: 0.0
+            }]
+        );
+        // A tree without the sensor leaves the rule as it is
+        assert!(engine.observe(&Sample::Sensors(&SensorTree::default()), Instant::now()).is_empty());
+        assert_eq!(engine.firing().len(), 1);
+    }
+
+    #[test]
+    fn test_rule_validation() {
+        let both: AlertRule =
+            serde_json::from_str(r#"{"metric": "cpu_temp", "above": 1, "below": 2}"#).unwrap();
+        assert!(both.validate().is_err());
+        let neither: AlertRule = serde_json::from_str(r#"{"metric": "cpu_temp"}"#).unwrap();
+        assert!(neither.validate().is_err());
+        let no_id: AlertRule =
+            serde_json::from_str(r#"{"metric": "sensor", "above": 1}"#).unwrap();
+        assert!(no_id.validate().is_err());
+        assert!(
+            serde_json::from_str::<AlertRule>(r#"{"metric": "fan_speed", "above": 1}"#).is_err()
+        );
+    }
+}
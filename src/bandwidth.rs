@@ -1,11 +1,12 @@
 use crate::interface_stats;
+use crate::settings;
+use iced::futures::stream;
 use iced::widget::canvas::{self, Path, Frame, LineCap, LineJoin, Geometry};
 use iced::{Color, Point, Theme, Rectangle, Element, Task, Size};
-use iced::widget::{container, column, row, text, Canvas};
+use iced::widget::{checkbox, container, column, row, scrollable, text, Canvas};
+use std::collections::BTreeSet;
 use std::time::Duration;
 
-const GRAPH_POINTS: usize = 300;
-
 // Layout constants
 const CANVAS_HEIGHT: f32 = 182.0;
 const CONTAINER_HEIGHT: f32 = 184.0;
@@ -16,8 +17,11 @@ const LINE_WIDTH: f32 = 2.0;
 
 #[derive(Debug, Clone)]
 pub enum Message {
+    /// Request a redraw without new data (e.g. after a window resize)
     Tick,
     StatsUpdated(Option<interface_stats::NetworkStats>),
+    /// Add (true) or remove (false) an interface from the plotted selection
+    ToggleInterface(String, bool),
 }
 
 #[derive(Default)]
@@ -25,6 +29,8 @@ pub struct BandwidthGraph {
     upload_points: Vec<f64>,
     download_points: Vec<f64>,
     current_stats: Option<interface_stats::NetworkStats>,
+    selected_interfaces: BTreeSet<String>, // Interfaces to plot, empty = all non-loopback
+    graph_points: usize,          // Number of points plotted (settings.json graph_points)
     bandwidth_graph_height: f64,  // Current display height
     target_height: f64,          // Desired final height
     scaling_step: u8,            // Current step in transition (0-10)
@@ -32,10 +38,13 @@ pub struct BandwidthGraph {
 
 impl BandwidthGraph {
     pub fn new() -> Self {
+        let graph_points = settings::current().graph_points;
         Self {
-            upload_points: vec![0.0; GRAPH_POINTS],
-            download_points: vec![0.0; GRAPH_POINTS],
+            upload_points: vec![0.0; graph_points],
+            download_points: vec![0.0; graph_points],
             current_stats: None,
+            selected_interfaces: BTreeSet::new(),
+            graph_points,
             bandwidth_graph_height: 1000.0, // Initial default value
             target_height: 1000.0,          // Initial target matches current
             scaling_step: 0,                 // No transition in progress
@@ -84,24 +93,31 @@ impl BandwidthGraph {
     }
 
     pub fn update_stats(&mut self, stats: interface_stats::NetworkStats) {
+        // A plotted interface reset, wrapped implausibly or vanished: skip this
+        // sample instead of plotting a dip or spike and rescaling to it
+        if stats.selection_dropped(&self.selected_interfaces) {
+            return;
+        }
+        
         // Convert bytes per second to megabits per second for graph
         const BYTES_TO_MEGABITS: f64 = 8.0 / 1_000_000.0;
-        let upload_mbps = stats.upload_bps * BYTES_TO_MEGABITS;
-        let download_mbps = stats.download_bps * BYTES_TO_MEGABITS;
+        let (upload_bps, download_bps) = stats.selected_rates(&self.selected_interfaces);
+        let upload_mbps = upload_bps * BYTES_TO_MEGABITS;
+        let download_mbps = download_bps * BYTES_TO_MEGABITS;
         
         self.current_stats = Some(stats);
         
         // Efficient circular buffer - avoid remove(0) which is O(n)
-        if self.upload_points.len() >= GRAPH_POINTS {
+        if self.upload_points.len() >= self.graph_points {
             self.upload_points.rotate_left(1);
-            self.upload_points[GRAPH_POINTS - 1] = upload_mbps;
+            self.upload_points[self.graph_points - 1] = upload_mbps;
         } else {
             self.upload_points.push(upload_mbps);
         }
         
-        if self.download_points.len() >= GRAPH_POINTS {
+        if self.download_points.len() >= self.graph_points {
             self.download_points.rotate_left(1);
-            self.download_points[GRAPH_POINTS - 1] = download_mbps;
+            self.download_points[self.graph_points - 1] = download_mbps;
         } else {
             self.download_points.push(download_mbps);
         }
@@ -113,11 +129,26 @@ impl BandwidthGraph {
         self.smooth_scale_update();
     }
 
+    /// Changes how many points are plotted, keeping the most recent ones
+    pub fn set_graph_points(&mut self, graph_points: usize) {
+        for points in [&mut self.upload_points, &mut self.download_points] {
+            if points.len() > graph_points {
+                points.drain(..points.len() - graph_points);
+            } else {
+                // Pad the old end with zeros so the newest point stays on the right
+                points.splice(0..0, std::iter::repeat_n(0.0, graph_points - points.len()));
+            }
+        }
+        self.graph_points = graph_points;
+        self.recalculate_graph_height();
+    }
+
     pub fn update(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::Tick => {
-                // Start async network stats collection using Iced's Task system
-                Task::perform(interface_stats::get_network_stats_async(), Message::StatsUpdated)
+                // Data arrives through the sampler subscription; processing the
+                // message is enough for iced to redraw the canvas
+                Task::none()
             }
             Message::StatsUpdated(stats) => {
                 // Handle async result when it completes
@@ -126,13 +157,25 @@ impl BandwidthGraph {
                 }
                 Task::none()
             }
+            Message::ToggleInterface(name, selected) => {
+                if selected {
+                    self.selected_interfaces.insert(name);
+                } else {
+                    self.selected_interfaces.remove(&name);
+                }
+                // Old points were summed over a different selection, start the graph over
+                self.upload_points = vec![0.0; self.graph_points];
+                self.download_points = vec![0.0; self.graph_points];
+                Task::none()
+            }
         }
     }
 
     pub fn view(&self) -> Element<'_, Message> {
         let bandwidth_data = if let Some(stats) = &self.current_stats {
-            let upload_str = interface_stats::format_rate(stats.upload_bps);
-            let download_str = interface_stats::format_rate(stats.download_bps);
+            let (upload_bps, download_bps) = stats.selected_rates(&self.selected_interfaces);
+            let upload_str = interface_stats::format_rate(upload_bps);
+            let download_str = interface_stats::format_rate(download_bps);
             
             column![
                 text(format!("↑ {}", upload_str))
@@ -145,6 +188,7 @@ impl BandwidthGraph {
                     .style(|_theme| iced::widget::text::Style { 
                         color: Color::from_rgb(0.0, 1.0, 0.5).into() // Green for download
                     }),
+                self.interface_selector(stats),
             ]
             .spacing(4)
         } else {
@@ -246,8 +290,37 @@ impl BandwidthGraph {
             .into()
     }
 
+    /// One checkbox per interface; when none are ticked every non-loopback
+    /// interface is plotted. Ticked interfaces that vanished (VPN down, USB
+    /// adapter unplugged) stay listed as absent so they can be unticked
+    fn interface_selector<'a>(&'a self, stats: &'a interface_stats::NetworkStats) -> Element<'a, Message> {
+        let entries = stats.selectable_interfaces(&self.selected_interfaces);
+        let checkboxes = entries.into_iter().map(|(name, present)| {
+            let label = if present { name.to_string() } else { format!("{} (absent)", name) };
+            let name = name.to_string();
+            checkbox(label, self.selected_interfaces.contains(&name))
+                .on_toggle(move |selected| Message::ToggleInterface(name.clone(), selected))
+                .size(10)
+                .text_size(10)
+                .into()
+        });
+
+        scrollable(column(checkboxes).spacing(2))
+            .height(iced::Length::Fill)
+            .into()
+    }
+
+    /// Streams network statistics from one long-lived sampler every 200ms by default
+    /// 
+    /// The next sample is only taken after the previous one has finished, so
+    /// slow PDH/procfs reads can never pile up on the blocking thread pool.
     pub fn subscription(&self) -> iced::Subscription<Message> {
-        iced::time::every(Duration::from_millis(200)).map(|_| Message::Tick)
+        let stream = stream::unfold(interface_stats::NetworkSampler::new(), |sampler| async move {
+            tokio::time::sleep(Duration::from_millis(settings::current().intervals_ms.bandwidth)).await;
+            let (sampler, stats) = interface_stats::sample_network_stats_async(sampler).await;
+            Some((Message::StatsUpdated(stats), sampler))
+        });
+        iced::Subscription::run_with_id("bandwidth", stream)
     }
 }
 
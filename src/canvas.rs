@@ -74,6 +74,57 @@ impl<Message> canvas::Program<Message> for BarChartProgram {
     }
 }
 
+/// A stacked variant of `BarChartProgram`: every bar is split into parts
+/// (e.g. user, system and iowait time) drawn bottom-up in their own colors
+#[derive(Debug)]
+pub struct StackedBarChartProgram {
+    /// Parts of each past measurement in percent, newest first
+    pub history: Vec<Vec<f32>>,
+    /// Color of each part, in the same order as the parts
+    pub colors: Vec<iced::Color>,
+}
+
+impl<Message> canvas::Program<Message> for StackedBarChartProgram {
+    type State = (); // No state needed for this simple drawing
+
+    // Same bar layout as BarChartProgram, one rectangle per part
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &iced::Renderer,
+        _theme: &iced::Theme,
+        bounds: iced::Rectangle,
+        _cursor: iced::mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+
+        let bar_width = 0.4;
+        let spacing = 0.5;
+        let total_width_needed = self.history.len() as f32 * spacing;
+        let scale_x = bounds.width / total_width_needed;
+
+        for (i, parts) in self.history.iter().enumerate() {
+            let x = i as f32 * spacing * scale_x;
+            let mut y = bounds.height;
+            for (&part, &color) in parts.iter().zip(&self.colors) {
+                // Never draw past the top, even if rounding adds up to over 100%
+                let part_height = (part / 100.0 * bounds.height).min(y);
+                if part_height <= 0.0 {
+                    continue;
+                }
+                y -= part_height;
+                frame.fill_rectangle(
+                    iced::Point::new(x, y),
+                    iced::Size::new(bar_width * scale_x, part_height),
+                    color,
+                );
+            }
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
+
 /// A program that draws overlaid bars showing current, previous, and oldest CPU usage
 /// The bars are stacked vertically with different colors and transparency
 #[derive(Debug)]
@@ -144,4 +195,59 @@ impl<Message> canvas::Program<Message> for OverlayBarProgram {
         // Return the completed drawing
         vec![frame.into_geometry()]
     }
-}
\ No newline at end of file
+}
+
+/// A program that draws a small line chart of recent values
+/// Used for the fan speed history next to each fan
+#[derive(Debug)]
+pub struct SparklineProgram {
+    /// Recent values, newest first (drawn right to left)
+    pub history: Vec<f32>,
+    /// Line color
+    pub color: iced::Color,
+}
+
+/// Implementation of the Canvas Program trait for drawing the sparkline
+impl<Message> canvas::Program<Message> for SparklineProgram {
+    type State = (); // No internal state needed
+
+    /// Draw function that scales the history to the canvas and connects the points
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &iced::Renderer,
+        _theme: &iced::Theme,
+        bounds: iced::Rectangle,
+        _cursor: iced::mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+
+        if self.history.len() >= 2 {
+            // Scale to the highest value shown, so small changes stay visible
+            let max = self.history.iter().cloned().fold(1.0, f32::max);
+            let step = bounds.width / (self.history.len() - 1) as f32;
+            let path = canvas::Path::new(|builder| {
+                for (i, &value) in self.history.iter().enumerate() {
+                    let point = iced::Point::new(
+                        bounds.width - i as f32 * step,
+                        bounds.height - 1.0 - (value / max) * (bounds.height - 2.0),
+                    );
+                    if i == 0 {
+                        builder.move_to(point);
+                    } else {
+                        builder.line_to(point);
+                    }
+                }
+            });
+            frame.stroke(
+                &path,
+                canvas::Stroke::default()
+                    .with_color(self.color)
+                    .with_width(1.0),
+            );
+        }
+
+        // Return the completed drawing
+        vec![frame.into_geometry()]
+    }
+}
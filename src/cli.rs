@@ -0,0 +1,213 @@
+//! Command-line options for CuteMonitor.
+//!
+//! Without arguments the iced window starts as before. `--headless` (alias
+//! `--json`) runs the collectors without a window and prints one JSON
+//! snapshot per interval to stdout instead. `--metrics` additionally serves
+//! the readings to Prometheus in either mode. `--record` logs every reading
+//! to a file that `--replay` plays back in the window later. `--alerts` loads
+//! threshold rules (see `alerts.rs`) and `--config` picks another settings
+//! file (see `settings.rs`). `--diagnose` checks the hardware collector and
+//! prints its parse-error counters (see `diagnostics.rs`).
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+
+/// Usage text printed for `--help` and after argument errors.
+pub const USAGE: &str = "\
+Usage: cutemonitor [OPTIONS]
+
+Options:
+  --headless, --json    Print JSON snapshots to stdout instead of opening a window
+  --interval <ms>       Milliseconds between JSON snapshots (default 1000)
+  --count <n>           Exit after printing <n> snapshots
+  --metrics <addr>      Serve Prometheus metrics on <addr>/metrics (a bare port binds 127.0.0.1)
+  --record <file>       Append every reading to <file> for later replay
+  --replay <file>       Play back a recording instead of reading the sensors
+  --speed <factor>      Replay speed relative to the recording (default 1)
+  --alerts <file>       Load alert rules from a JSON file
+  --config <file>       Use <file> instead of the default settings.json
+  --diagnose            Watch the hardware collector for 10s and print its health
+  -h, --help            Print this help";
+
+/// Default interval between headless JSON snapshots in milliseconds.
+pub const DEFAULT_INTERVAL_MS: u64 = 1000;
+
+/// Options parsed from the command line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliOptions {
+    /// Run without a window and print JSON snapshots to stdout.
+    pub headless: bool,
+    /// Milliseconds between JSON snapshots in headless mode.
+    pub interval_ms: u64,
+    /// Stop after this many snapshots (None = run until killed).
+    pub count: Option<u64>,
+    /// Address of the Prometheus `/metrics` endpoint (None = disabled).
+    pub metrics_address: Option<SocketAddr>,
+    /// Append every reading to this history file.
+    pub record: Option<PathBuf>,
+    /// Play back this history file instead of starting the collectors.
+    pub replay: Option<PathBuf>,
+    /// Replay speed relative to the recording.
+    pub speed: f64,
+    /// JSON file with alert rules.
+    pub alerts: Option<PathBuf>,
+    /// Settings file to use instead of the one in the config directory.
+    pub config: Option<PathBuf>,
+    /// Print the hardware collector's health and exit.
+    pub diagnose: bool,
+    /// Print usage and exit.
+    pub help: bool,
+}
+
+impl Default for CliOptions {
+    fn default() -> Self {
+        Self {
+            headless: false,
+            interval_ms: DEFAULT_INTERVAL_MS,
+            count: None,
+            metrics_address: None,
+            record: None,
+            replay: None,
+            speed: 1.0,
+            alerts: None,
+            config: None,
+            diagnose: false,
+            help: false,
+        }
+    }
+}
+
+/// Parses command-line arguments (without the program name).
+/// Returns a message describing the first invalid argument on error.
+pub fn parse_args<I>(args: I) -> Result<CliOptions, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut options = CliOptions::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--headless" | "--json" => options.headless = true,
+            "--interval" => {
+                options.interval_ms = parse_number(&arg, args.next())?;
+                if options.interval_ms == 0 {
+                    return Err("--interval must be greater than 0".to_string());
+                }
+            }
+            "--count" => options.count = Some(parse_number(&arg, args.next())?),
+            "--metrics" => options.metrics_address = Some(parse_address(&arg, args.next())?),
+            "--record" => options.record = Some(parse_path(&arg, args.next())?),
+            "--replay" => options.replay = Some(parse_path(&arg, args.next())?),
+            "--speed" => {
+                let value = args.next().ok_or_else(|| format!("{} requires a value", arg))?;
+                options.speed = value
+                    .parse()
+                    .ok()
+                    .filter(|speed: &f64| speed.is_finite() && *speed > 0.0)
+                    .ok_or_else(|| format!("Invalid value for {}: {}", arg, value))?;
+            }
+            "--alerts" => options.alerts = Some(parse_path(&arg, args.next())?),
+            "--config" => options.config = Some(parse_path(&arg, args.next())?),
+            "--diagnose" => options.diagnose = true,
+            "-h" | "--help" => options.help = true,
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+
+    if options.replay.is_some() && options.headless {
+        return Err("--replay cannot be combined with --headless".to_string());
+    }
+    if options.replay.is_some() && options.record.is_some() {
+        return Err("--replay cannot be combined with --record".to_string());
+    }
+    if options.diagnose && (options.headless || options.replay.is_some()) {
+        return Err("--diagnose cannot be combined with --headless or --replay".to_string());
+    }
+
+    Ok(options)
+}
+
+/// Returns the value following a file option such as `--record`.
+fn parse_path(option: &str, value: Option<String>) -> Result<PathBuf, String> {
+    value
+        .map(PathBuf::from)
+        .ok_or_else(|| format!("{} requires a value", option))
+}
+
+/// Parses the value following a numeric option such as `--interval`.
+fn parse_number(option: &str, value: Option<String>) -> Result<u64, String> {
+    let value = value.ok_or_else(|| format!("{} requires a value", option))?;
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for {}: {}", option, value))
+}
+
+/// Parses a listen address, accepting either `host:port` or a bare port.
+fn parse_address(option: &str, value: Option<String>) -> Result<SocketAddr, String> {
+    let value = value.ok_or_else(|| format!("{} requires a value", option))?;
+    if let Ok(port) = value.parse::<u16>() {
+        return Ok(SocketAddr::from((Ipv4Addr::LOCALHOST, port)));
+    }
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for {}: {}", option, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliOptions, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_no_arguments_starts_gui() {
+        assert_eq!(parse(&[]).unwrap(), CliOptions::default());
+    }
+
+    #[test]
+    fn test_headless_options() {
+        let options = parse(&["--json", "--interval", "250", "--count", "3"]).unwrap();
+        assert!(options.headless);
+        assert_eq!(options.interval_ms, 250);
+        assert_eq!(options.count, Some(3));
+        assert!(parse(&["--headless"]).unwrap().headless);
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        assert!(parse(&["--interval"]).is_err());
+        assert!(parse(&["--interval", "fast"]).is_err());
+        assert!(parse(&["--interval", "0"]).is_err());
+        assert!(parse(&["--verbose"]).is_err());
+        assert!(parse(&["--metrics", "localhost"]).is_err());
+        assert!(parse(&["--speed", "0"]).is_err());
+        assert!(parse(&["--replay", "a.log", "--json"]).is_err());
+        assert!(parse(&["--replay", "a.log", "--record", "b.log"]).is_err());
+        assert!(parse(&["--diagnose", "--json"]).is_err());
+    }
+
+    #[test]
+    fn test_history_options() {
+        let options = parse(&["--replay", "night.log", "--speed", "60"]).unwrap();
+        assert_eq!(options.replay, Some(PathBuf::from("night.log")));
+        assert_eq!(options.speed, 60.0);
+        let options = parse(&["--json", "--record", "night.log"]).unwrap();
+        assert_eq!(options.record, Some(PathBuf::from("night.log")));
+        let options = parse(&["--alerts", "alerts.json"]).unwrap();
+        assert_eq!(options.alerts, Some(PathBuf::from("alerts.json")));
+        let options = parse(&["--config", "portable.json"]).unwrap();
+        assert_eq!(options.config, Some(PathBuf::from("portable.json")));
+        assert!(parse(&["--diagnose"]).unwrap().diagnose);
+    }
+
+    #[test]
+    fn test_metrics_address() {
+        let options = parse(&["--metrics", "9184"]).unwrap();
+        assert_eq!(options.metrics_address, Some("127.0.0.1:9184".parse().unwrap()));
+        let options = parse(&["--metrics", "0.0.0.0:9100"]).unwrap();
+        assert_eq!(options.metrics_address, Some("0.0.0.0:9100".parse().unwrap()));
+    }
+}
@@ -0,0 +1,247 @@
+//! Latest-value channels between the collectors and their consumers.
+//!
+//! A collector publishes into a `Publisher` and the consumer reads a stream
+//! of `Stamped` values. Unlike a buffered channel, a value that hasn't been
+//! read yet is replaced by the next one with the same key, so a slow UI always
+//! renders the freshest reading and memory stays bounded by the number of keys.
+//!
+//! Most collectors use a single key. Collectors that send different kinds of
+//! events key them by kind (see `std::mem::discriminant`), so e.g. a helper
+//! state change isn't overwritten by the reading that follows it. A replaced
+//! value moves behind the other keys, so the consumer still sees the kinds in
+//! the order they were last published.
+
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use iced_futures::futures::{future, stream, Future, FutureExt, Stream, StreamExt};
+use serde::Serialize;
+
+/// When and in which order a value was published.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Stamp {
+    /// Starts at 1 and grows with every publish, including values that were replaced.
+    pub seq: u64,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+}
+
+/// A published value with its stamp.
+#[derive(Debug, Clone)]
+pub struct Stamped<T> {
+    pub stamp: Stamp,
+    pub value: T,
+}
+
+struct Shared<T, K> {
+    /// Values not read yet, at most one per key, in publish order.
+    pending: Vec<(K, Stamped<T>)>,
+    seq: u64,
+    waker: Option<Waker>,
+    publisher_gone: bool,
+    receiver_gone: bool,
+}
+
+/// Sending half; owned by the collector.
+pub struct Publisher<T, K = ()> {
+    shared: Arc<Mutex<Shared<T, K>>>,
+    key: fn(&T) -> K,
+}
+
+impl<T, K: PartialEq> Publisher<T, K> {
+    /// Publishes `value`, replacing any unread value with the same key and
+    /// queueing it behind the values of the other keys.
+    pub fn publish(&self, value: T) {
+        let key = (self.key)(&value);
+        let mut shared = self.shared.lock().unwrap();
+        shared.seq += 1;
+        let stamped = Stamped {
+            stamp: Stamp {
+                seq: shared.seq,
+                timestamp_ms: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |elapsed| elapsed.as_millis() as u64),
+            },
+            value,
+        };
+        if let Some(position) = shared
+            .pending
+            .iter()
+            .position(|(pending, _)| *pending == key)
+        {
+            shared.pending.remove(position);
+        }
+        shared.pending.push((key, stamped));
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+    }
+
+    /// True once the consumer dropped its stream; the collector should stop.
+    pub fn is_closed(&self) -> bool {
+        self.shared.lock().unwrap().receiver_gone
+    }
+}
+
+impl<T, K> Drop for Publisher<T, K> {
+    fn drop(&mut self) {
+        let mut shared = self.shared.lock().unwrap();
+        shared.publisher_gone = true;
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Receiving half; a stream that ends once the publisher is gone.
+pub struct Receiver<T, K = ()> {
+    shared: Arc<Mutex<Shared<T, K>>>,
+}
+
+impl<T, K> Stream for Receiver<T, K> {
+    type Item = Stamped<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut shared = self.shared.lock().unwrap();
+        if !shared.pending.is_empty() {
+            return Poll::Ready(Some(shared.pending.remove(0).1));
+        }
+        if shared.publisher_gone {
+            return Poll::Ready(None);
+        }
+        shared.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl<T, K> Drop for Receiver<T, K> {
+    fn drop(&mut self) {
+        self.shared.lock().unwrap().receiver_gone = true;
+    }
+}
+
+/// Creates a latest-value channel; values with equal `key`s replace each other.
+pub fn channel<T, K: PartialEq>(key: fn(&T) -> K) -> (Publisher<T, K>, Receiver<T, K>) {
+    let shared = Arc::new(Mutex::new(Shared {
+        pending: Vec::new(),
+        seq: 0,
+        waker: None,
+        publisher_gone: false,
+        receiver_gone: false,
+    }));
+    (
+        Publisher {
+            shared: Arc::clone(&shared),
+            key,
+        },
+        Receiver { shared },
+    )
+}
+
+/// Runs the collector `produce` once the stream is first polled and yields
+/// the latest values it publishes. The collector future is dropped with the
+/// stream; collectors on their own thread should stop when
+/// `Publisher::is_closed` turns true.
+pub fn latest<T, K, F, Fut>(key: fn(&T) -> K, produce: F) -> impl Stream<Item = Stamped<T>>
+where
+    T: Send + 'static,
+    K: PartialEq + Send + 'static,
+    F: FnOnce(Publisher<T, K>) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let (publisher, receiver) = channel(key);
+    let collector = produce(publisher)
+        .into_stream()
+        .filter_map(|()| future::ready(None));
+    stream::select(collector, receiver)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Event {
+        Reading(u32),
+        State(&'static str),
+    }
+
+    fn values<T: Clone, K>(receiver: &mut Receiver<T, K>) -> Vec<(u64, T)> {
+        let mut values = Vec::new();
+        while let Some(Some(stamped)) = receiver.next().now_or_never() {
+            values.push((stamped.stamp.seq, stamped.value));
+        }
+        values
+    }
+
+    #[test]
+    fn test_unread_values_are_replaced() {
+        let (publisher, mut receiver) = channel(|_: &u32| ());
+        for reading in 1..=1000 {
+            publisher.publish(reading);
+        }
+        assert_eq!(values(&mut receiver), vec![(1000, 1000)]);
+
+        publisher.publish(1001);
+        drop(publisher);
+        assert_eq!(values(&mut receiver), vec![(1001, 1001)]);
+        assert!(matches!(receiver.next().now_or_never(), Some(None)));
+    }
+
+    #[test]
+    fn test_one_slot_per_key() {
+        let (publisher, mut receiver) = channel(std::mem::discriminant::<Event>);
+        publisher.publish(Event::State("Starting"));
+        publisher.publish(Event::Reading(1));
+        publisher.publish(Event::State("Running"));
+        publisher.publish(Event::Reading(2));
+
+        assert_eq!(
+            values(&mut receiver),
+            vec![(3, Event::State("Running")), (4, Event::Reading(2))]
+        );
+    }
+
+    #[test]
+    fn test_replaced_value_moves_behind_other_keys() {
+        let (publisher, mut receiver) = channel(std::mem::discriminant::<Event>);
+        publisher.publish(Event::Reading(1));
+        publisher.publish(Event::State("Restarting"));
+        publisher.publish(Event::Reading(2));
+
+        assert_eq!(
+            values(&mut receiver),
+            vec![(2, Event::State("Restarting")), (3, Event::Reading(2))]
+        );
+    }
+
+    #[test]
+    fn test_collector_stops_with_stream() {
+        let (done_tx, done_rx) = std::sync::mpsc::channel();
+        let stream = latest(
+            |_: &u32| (),
+            |publisher| async move {
+                std::thread::spawn(move || {
+                    let mut reading = 0;
+                    while !publisher.is_closed() {
+                        reading += 1;
+                        publisher.publish(reading);
+                        std::thread::sleep(std::time::Duration::from_millis(1));
+                    }
+                    let _ = done_tx.send(reading);
+                });
+                future::pending::<()>().await
+            },
+        );
+
+        let first = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(stream.take(2).collect::<Vec<_>>());
+        assert!(first[0].stamp.seq < first[1].stamp.seq);
+        assert!(done_rx
+            .recv_timeout(std::time::Duration::from_secs(5))
+            .is_ok());
+    }
+}
@@ -0,0 +1,226 @@
+//! CPU clocks: the current frequency of every logical CPU, the reference
+//! clocks of the model and a throttling flag.
+//!
+//! Current frequencies come from the shared sampler (sysinfo reads
+//! `cpufreq/scaling_cur_freq` on Linux). The reference is read once: minimum,
+//! maximum and base clock from `/sys/devices/system/cpu/cpu0/cpufreq` on
+//! Linux, the base clock (`~MHz`) from the registry on Windows.
+//!
+//! The CPU counts as throttling while it stays busy, its mean clock is well
+//! below the peak of the last few busy samples and the temperature is close to
+//! the red band of `temperature_color`. Clocks falling because the load went
+//! away are not throttling.
+
+use std::sync::OnceLock;
+
+use crate::data_colouring::HOT_TEMPERATURE;
+
+/// Busy samples the throttling check looks back for the peak clock.
+const THROTTLE_WINDOW: usize = 20;
+
+/// Mean thread usage (%) from which a sample counts as busy.
+const THROTTLE_USAGE: f32 = 80.0;
+
+/// Clocks below this share of the recent peak count as dropped.
+const THROTTLE_DROP: f32 = 0.85;
+
+/// How close to `HOT_TEMPERATURE` (°C) the CPU has to be.
+const THROTTLE_MARGIN: f32 = 5.0;
+
+/// Reference clocks of the CPU in MHz, where the platform reports them.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FrequencyReference {
+    pub min_mhz: Option<u64>,
+    pub base_mhz: Option<u64>,
+    pub max_mhz: Option<u64>,
+}
+
+impl FrequencyReference {
+    /// E.g. "min 0.55 / base 3.70 / max 4.65 GHz"; None if nothing is known.
+    pub fn describe(&self) -> Option<String> {
+        let parts: Vec<String> = [
+            ("min", self.min_mhz),
+            ("base", self.base_mhz),
+            ("max", self.max_mhz),
+        ]
+        .into_iter()
+        .filter_map(|(name, mhz)| mhz.map(|mhz| format!("{} {:.2}", name, mhz as f32 / 1000.0)))
+        .collect();
+        if parts.is_empty() {
+            None
+        } else {
+            Some(format!("{} GHz", parts.join(" / ")))
+        }
+    }
+}
+
+/// Reference clocks of the running system, read once.
+pub fn reference() -> FrequencyReference {
+    static REFERENCE: OnceLock<FrequencyReference> = OnceLock::new();
+    *REFERENCE.get_or_init(read_reference)
+}
+
+#[cfg(target_os = "linux")]
+fn read_reference() -> FrequencyReference {
+    read_cpufreq_reference(std::path::Path::new("/sys/devices/system/cpu/cpu0/cpufreq"))
+}
+
+/// Reads the reference clocks from a cpufreq directory (values in kHz).
+/// `base_frequency` is only reported by `intel_pstate`.
+#[cfg(target_os = "linux")]
+fn read_cpufreq_reference(dir: &std::path::Path) -> FrequencyReference {
+    let read_mhz = |file: &str| -> Option<u64> {
+        let khz: u64 = std::fs::read_to_string(dir.join(file))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()?;
+        Some(khz / 1000)
+    };
+    FrequencyReference {
+        min_mhz: read_mhz("cpuinfo_min_freq"),
+        base_mhz: read_mhz("base_frequency"),
+        max_mhz: read_mhz("cpuinfo_max_freq"),
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn read_reference() -> FrequencyReference {
+    use windows::Win32::System::Registry::{RegGetValueW, HKEY_LOCAL_MACHINE, RRF_RT_REG_DWORD};
+
+    let mut mhz = 0u32;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    let found = unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            windows::core::w!("HARDWARE\\DESCRIPTION\\System\\CentralProcessor\\0"),
+            windows::core::w!("~MHz"),
+            RRF_RT_REG_DWORD,
+            None,
+            Some(&mut mhz as *mut u32 as *mut std::ffi::c_void),
+            Some(&mut size),
+        )
+        .is_ok()
+    };
+    FrequencyReference {
+        base_mhz: (found && mhz > 0).then_some(mhz as u64),
+        ..Default::default()
+    }
+}
+
+/// Mean clock of the CPUs that report one, in MHz.
+fn mean_mhz(frequencies_mhz: &[u64]) -> Option<f32> {
+    let reported: Vec<u64> = frequencies_mhz
+        .iter()
+        .copied()
+        .filter(|&mhz| mhz > 0)
+        .collect();
+    if reported.is_empty() {
+        None
+    } else {
+        Some(reported.iter().sum::<u64>() as f32 / reported.len() as f32)
+    }
+}
+
+/// Flags clock drops that happen while the CPU is hot and busy.
+#[derive(Debug, Clone, Default)]
+pub struct ThrottleDetector {
+    /// Mean clock of the recent busy samples, newest first.
+    recent_mhz: Vec<f32>,
+    throttling: bool,
+}
+
+impl ThrottleDetector {
+    /// Adds a sample and returns whether the CPU is throttling now.
+    /// `usage_percent` is the mean thread usage of the same sampler refresh.
+    pub fn update(&mut self, frequencies_mhz: &[u64], usage_percent: f32, cpu_temp: f32) -> bool {
+        let mean = match mean_mhz(frequencies_mhz) {
+            Some(mean) if usage_percent >= THROTTLE_USAGE => mean,
+            _ => {
+                self.throttling = false;
+                return false;
+            }
+        };
+        let peak = self.recent_mhz.iter().copied().fold(0.0, f32::max);
+        let hot = cpu_temp >= HOT_TEMPERATURE - THROTTLE_MARGIN;
+        self.throttling = hot && mean < peak * THROTTLE_DROP;
+
+        self.recent_mhz.insert(0, mean);
+        self.recent_mhz.truncate(THROTTLE_WINDOW);
+        self.throttling
+    }
+
+    pub fn is_throttling(&self) -> bool {
+        self.throttling
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttling_needs_heat_and_a_drop() {
+        let mut detector = ThrottleDetector::default();
+        assert!(!detector.update(&[4600, 4500], 100.0, 70.0));
+        // Cool CPU clocking down under load
+        assert!(!detector.update(&[3600, 3500], 100.0, 40.0));
+        // Hot and well below the recent peak
+        assert!(detector.update(&[3600, 3500], 100.0, 70.0));
+        assert!(detector.is_throttling());
+        // Hot but back at full speed
+        assert!(!detector.update(&[4550, 4500], 100.0, 70.0));
+        // No frequencies reported at all
+        assert!(!detector.update(&[0, 0], 100.0, 90.0));
+    }
+
+    #[test]
+    fn test_throttling_needs_sustained_load() {
+        let mut detector = ThrottleDetector::default();
+        assert!(!detector.update(&[4600, 4500], 100.0, 80.0));
+        // Still hot from the load, but the load is gone and the clocks follow
+        assert!(!detector.update(&[1800, 1700], 5.0, 80.0));
+        assert!(!detector.update(&[1800, 1700], 5.0, 78.0));
+        // Idle samples don't lower the peak the next busy stretch is compared to
+        assert!(detector.update(&[3600, 3500], 95.0, 80.0));
+    }
+
+    #[test]
+    fn test_describe_reference() {
+        let reference = FrequencyReference {
+            min_mhz: Some(550),
+            base_mhz: None,
+            max_mhz: Some(4650),
+        };
+        assert_eq!(
+            reference.describe().as_deref(),
+            Some("min 0.55 / max 4.65 GHz")
+        );
+        assert_eq!(FrequencyReference::default().describe(), None);
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod linux_tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_cpufreq_reference() {
+        let dir = std::env::temp_dir().join(format!("cutemonitor_cpufreq_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("cpuinfo_min_freq"), "400000\n").unwrap();
+        std::fs::write(dir.join("cpuinfo_max_freq"), "5300000\n").unwrap();
+        std::fs::write(dir.join("base_frequency"), "2100000\n").unwrap();
+
+        let reference = read_cpufreq_reference(&dir);
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(
+            reference,
+            FrequencyReference {
+                min_mhz: Some(400),
+                base_mhz: Some(2100),
+                max_mhz: Some(5300),
+            }
+        );
+    }
+}
@@ -0,0 +1,339 @@
+//! Where the CPU time goes: user, nice, system, iowait, irq, softirq and steal.
+//!
+//! On Linux the cumulative tick counters come from `/proc/stat`. On Windows
+//! the `\Processor(*)` PDH counters give user, privileged, interrupt, DPC and
+//! idle time; privileged time without interrupts and DPCs counts as system,
+//! DPCs as softirq, and nice, iowait and steal stay at zero. Each sample is the
+//! share of every state between two readings, per logical CPU and in total.
+
+use serde::{Deserialize, Serialize};
+
+/// Names of the states, in the order of `CpuBreakdown::parts`.
+pub const STATES: [&str; 7] = [
+    "user", "nice", "system", "iowait", "irq", "softirq", "steal",
+];
+
+/// Cumulative time counters of one CPU, in ticks (Linux) or 100 ns (Windows).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CpuTimes {
+    pub user: u64,
+    pub nice: u64,
+    pub system: u64,
+    pub idle: u64,
+    pub iowait: u64,
+    pub irq: u64,
+    pub softirq: u64,
+    pub steal: u64,
+}
+
+impl CpuTimes {
+    fn total(&self) -> u64 {
+        self.user
+            + self.nice
+            + self.system
+            + self.idle
+            + self.iowait
+            + self.irq
+            + self.softirq
+            + self.steal
+    }
+
+    /// Share of each state in percent since `previous`.
+    pub fn breakdown_since(&self, previous: &CpuTimes) -> CpuBreakdown {
+        let elapsed = self.total().saturating_sub(previous.total());
+        if elapsed == 0 {
+            return CpuBreakdown::default();
+        }
+        let percent =
+            |now: u64, before: u64| now.saturating_sub(before) as f32 * 100.0 / elapsed as f32;
+        CpuBreakdown {
+            user: percent(self.user, previous.user),
+            nice: percent(self.nice, previous.nice),
+            system: percent(self.system, previous.system),
+            iowait: percent(self.iowait, previous.iowait),
+            irq: percent(self.irq, previous.irq),
+            softirq: percent(self.softirq, previous.softirq),
+            steal: percent(self.steal, previous.steal),
+        }
+    }
+}
+
+/// Share of each busy state in percent; the rest is idle.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CpuBreakdown {
+    pub user: f32,
+    pub nice: f32,
+    pub system: f32,
+    pub iowait: f32,
+    pub irq: f32,
+    pub softirq: f32,
+    pub steal: f32,
+}
+
+impl CpuBreakdown {
+    /// The shares in the order of `STATES`.
+    pub fn parts(&self) -> [f32; 7] {
+        [
+            self.user,
+            self.nice,
+            self.system,
+            self.iowait,
+            self.irq,
+            self.softirq,
+            self.steal,
+        ]
+    }
+}
+
+/// One breakdown for the whole machine and one per logical CPU.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CpuTimesSample {
+    pub total: CpuBreakdown,
+    pub per_cpu: Vec<CpuBreakdown>,
+}
+
+/// Counters of the whole machine and of every logical CPU at one moment.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct CpuTimesReading {
+    total: CpuTimes,
+    per_cpu: Vec<CpuTimes>,
+}
+
+impl CpuTimesReading {
+    fn sample_since(&self, previous: &CpuTimesReading) -> CpuTimesSample {
+        CpuTimesSample {
+            total: self.total.breakdown_since(&previous.total),
+            per_cpu: self
+                .per_cpu
+                .iter()
+                .zip(&previous.per_cpu)
+                .map(|(now, before)| now.breakdown_since(before))
+                .collect(),
+        }
+    }
+}
+
+/// Parses `/proc/stat`: the `cpu` line and one `cpuN` line per logical CPU.
+/// Guest time is already part of user and nice, so it isn't counted again.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_proc_stat(contents: &str) -> Option<CpuTimesReading> {
+    let mut total = None;
+    let mut per_cpu = Vec::new();
+    for line in contents.lines() {
+        let mut fields = line.split_whitespace();
+        let Some(name) = fields.next().filter(|name| name.starts_with("cpu")) else {
+            continue;
+        };
+        let values: Vec<u64> = fields.map_while(|field| field.parse().ok()).collect();
+        let value = |index: usize| values.get(index).copied().unwrap_or(0);
+        let times = CpuTimes {
+            user: value(0),
+            nice: value(1),
+            system: value(2),
+            idle: value(3),
+            iowait: value(4),
+            irq: value(5),
+            softirq: value(6),
+            steal: value(7),
+        };
+        if name == "cpu" {
+            total = Some(times);
+        } else {
+            per_cpu.push(times);
+        }
+    }
+    Some(CpuTimesReading {
+        total: total?,
+        per_cpu,
+    })
+}
+
+/// Turns successive counter readings into breakdowns.
+pub struct CpuTimesSampler {
+    previous: Option<CpuTimesReading>,
+    #[cfg(target_os = "windows")]
+    counters: Option<PdhCpuCounters>,
+}
+
+impl CpuTimesSampler {
+    pub fn new() -> Self {
+        Self {
+            previous: None,
+            #[cfg(target_os = "windows")]
+            counters: PdhCpuCounters::open(),
+        }
+    }
+
+    /// Reads the counters; None on the first call and if they can't be read.
+    pub fn sample(&mut self) -> Option<CpuTimesSample> {
+        let reading = self.read()?;
+        let sample = self
+            .previous
+            .as_ref()
+            .map(|previous| reading.sample_since(previous));
+        self.previous = Some(reading);
+        sample
+    }
+
+    #[cfg(target_os = "linux")]
+    fn read(&mut self) -> Option<CpuTimesReading> {
+        parse_proc_stat(&std::fs::read_to_string("/proc/stat").ok()?)
+    }
+
+    #[cfg(target_os = "windows")]
+    fn read(&mut self) -> Option<CpuTimesReading> {
+        self.counters.as_ref()?.collect()
+    }
+}
+
+/// Open PDH query with the wildcard `\Processor(*)` time counters
+#[cfg(target_os = "windows")]
+struct PdhCpuCounters {
+    query: isize,
+    user: isize,
+    privileged: isize,
+    interrupt: isize,
+    dpc: isize,
+    idle: isize,
+}
+
+#[cfg(target_os = "windows")]
+impl PdhCpuCounters {
+    fn open() -> Option<Self> {
+        use windows::core::HSTRING;
+        use windows::Win32::Foundation::ERROR_SUCCESS;
+        use windows::Win32::System::Performance::{PdhAddCounterW, PdhOpenQueryW};
+
+        unsafe {
+            let mut query: isize = 0;
+            if PdhOpenQueryW(None, 0, &mut query) != ERROR_SUCCESS.0 {
+                return None;
+            }
+            let mut counters = PdhCpuCounters {
+                query,
+                user: 0,
+                privileged: 0,
+                interrupt: 0,
+                dpc: 0,
+                idle: 0,
+            };
+            let paths = [
+                ("\\Processor(*)\\% User Time", &mut counters.user),
+                (
+                    "\\Processor(*)\\% Privileged Time",
+                    &mut counters.privileged,
+                ),
+                ("\\Processor(*)\\% Interrupt Time", &mut counters.interrupt),
+                ("\\Processor(*)\\% DPC Time", &mut counters.dpc),
+                ("\\Processor(*)\\% Idle Time", &mut counters.idle),
+            ];
+            for (path, counter) in paths {
+                if PdhAddCounterW(query, &HSTRING::from(path), 0, counter) != ERROR_SUCCESS.0 {
+                    return None;
+                }
+            }
+            Some(counters)
+        }
+    }
+
+    /// Raw values are cumulative 100 ns counts per instance ("0", "1", ..., "_Total").
+    fn collect(&self) -> Option<CpuTimesReading> {
+        use crate::interface_stats::raw_counter_array;
+        use windows::Win32::Foundation::ERROR_SUCCESS;
+        use windows::Win32::System::Performance::PdhCollectQueryData;
+
+        unsafe {
+            if PdhCollectQueryData(self.query) != ERROR_SUCCESS.0 {
+                return None;
+            }
+        }
+        let user = raw_counter_array(self.user)?;
+        let privileged = raw_counter_array(self.privileged)?;
+        let interrupt = raw_counter_array(self.interrupt).unwrap_or_default();
+        let dpc = raw_counter_array(self.dpc).unwrap_or_default();
+        let idle = raw_counter_array(self.idle)?;
+
+        let lookup = |values: &[(String, u64)], name: &str| {
+            values
+                .iter()
+                .find(|(instance, _)| instance == name)
+                .map_or(0, |(_, value)| *value)
+        };
+        let times = |name: &str| {
+            let interrupt = lookup(&interrupt, name);
+            let dpc = lookup(&dpc, name);
+            CpuTimes {
+                user: lookup(&user, name),
+                system: lookup(&privileged, name).saturating_sub(interrupt + dpc),
+                idle: lookup(&idle, name),
+                irq: interrupt,
+                softirq: dpc,
+                ..Default::default()
+            }
+        };
+
+        let mut cpus: Vec<usize> = user
+            .iter()
+            .filter_map(|(name, _)| name.parse().ok())
+            .collect();
+        cpus.sort_unstable();
+        Some(CpuTimesReading {
+            total: times("_Total"),
+            per_cpu: cpus.iter().map(|cpu| times(&cpu.to_string())).collect(),
+        })
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for PdhCpuCounters {
+    fn drop(&mut self) {
+        unsafe {
+            windows::Win32::System::Performance::PdhCloseQuery(self.query);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BEFORE: &str = "cpu  1000 100 500 8000 200 20 30 0 0 0\n\
+                          cpu0 500 50 250 4000 100 10 15 0 0 0\n\
+                          cpu1 500 50 250 4000 100 10 15 0 0 0\n\
+                          intr 12345 0 0\n\
+                          ctxt 67890\n";
+
+    const AFTER: &str = "cpu  1300 100 600 8450 300 40 40 20 0 0\n\
+                         cpu0 800 50 300 4100 100 30 25 20 0 0\n\
+                         cpu1 500 50 300 4350 200 10 15 0 0 0\n";
+
+    #[test]
+    fn test_breakdown_from_proc_stat() {
+        let before = parse_proc_stat(BEFORE).unwrap();
+        let after = parse_proc_stat(AFTER).unwrap();
+        assert_eq!(before.per_cpu.len(), 2);
+
+        // 1000 ticks passed in total, 500 on each CPU
+        let sample = after.sample_since(&before);
+        assert_eq!(sample.total.user, 30.0);
+        assert_eq!(sample.total.system, 10.0);
+        assert_eq!(sample.total.iowait, 10.0);
+        assert_eq!(sample.total.irq, 2.0);
+        assert_eq!(sample.total.softirq, 1.0);
+        assert_eq!(sample.total.steal, 2.0);
+        assert_eq!(sample.per_cpu[0].user, 60.0);
+        assert_eq!(sample.per_cpu[0].steal, 4.0);
+        assert_eq!(sample.per_cpu[1].iowait, 20.0);
+        assert_eq!(sample.per_cpu[1].parts().iter().sum::<f32>(), 30.0);
+    }
+
+    #[test]
+    fn test_no_time_passed() {
+        let reading = parse_proc_stat(BEFORE).unwrap();
+        assert_eq!(
+            reading.sample_since(&reading).total,
+            CpuBreakdown::default()
+        );
+        assert!(parse_proc_stat("intr 1 2 3\n").is_none());
+    }
+}
@@ -0,0 +1,410 @@
+//! CPU topology: packages, CCDs, cores and their SMT sibling threads.
+//!
+//! Logical CPUs are numbered the way `sysinfo` lists them, which is not
+//! "all first threads, then all siblings" on every system: Windows puts the
+//! two threads of a core next to each other, Linux usually numbers every
+//! core's first thread before the siblings. The real layout is read from
+//! `/sys/devices/system/cpu/cpu*/topology` on Linux and from
+//! `GetLogicalProcessorInformationEx` on Windows.
+//!
+//! Cores are grouped by the L3 cache they share (or the `die_id` where no L3
+//! is reported). On Zen 3 and later that group is a CCD; on Zen 2 it is a
+//! CCX, half a CCD, so [`CpuTopology::ccds`] pairs the groups up against the
+//! number of CCD temperatures. Where the topology can't be read, threads are
+//! assumed to be split evenly over the cores in order.
+
+use std::sync::OnceLock;
+
+/// One physical core.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Core {
+    pub package: usize,
+    /// Index of the core's L3 group, counted from 0 across all packages.
+    pub l3: usize,
+    /// Logical CPU indexes of the core's threads (SMT siblings), ascending.
+    pub threads: Vec<usize>,
+}
+
+/// Where one logical CPU sits, as reported by the OS.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LogicalCpu {
+    package: usize,
+    /// Any id that is equal for the threads sharing an L3 in the same package.
+    l3: usize,
+    /// Any id that is equal for the threads of one core in the same L3 group.
+    core: usize,
+}
+
+/// Every core of the system, ordered by package, L3 group and first thread.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CpuTopology {
+    pub cores: Vec<Core>,
+}
+
+impl CpuTopology {
+    /// Assumes `threads` logical CPUs split evenly over `cores` cores in order,
+    /// all sharing one L3. Threads left over go to the last core.
+    pub fn uniform(cores: usize, threads: usize) -> Self {
+        let cores = cores.clamp(1, threads.max(1));
+        let per_core = (threads / cores).max(1);
+        Self {
+            cores: (0..cores)
+                .map(|core| {
+                    let end = if core + 1 == cores {
+                        threads
+                    } else {
+                        (core + 1) * per_core
+                    };
+                    Core {
+                        package: 0,
+                        l3: 0,
+                        threads: (core * per_core..end).collect(),
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// Groups logical CPUs (indexed by position) into cores and numbers the L3 groups.
+    fn from_logical_cpus(cpus: &[LogicalCpu]) -> Self {
+        let mut cores: Vec<(LogicalCpu, Vec<usize>)> = Vec::new();
+        for (index, cpu) in cpus.iter().enumerate() {
+            match cores.iter_mut().find(|(known, _)| known == cpu) {
+                Some((_, threads)) => threads.push(index),
+                None => cores.push((*cpu, vec![index])),
+            }
+        }
+        cores.sort_by_key(|(cpu, threads)| (cpu.package, cpu.l3, threads[0]));
+
+        let mut l3_groups: Vec<(usize, usize)> = Vec::new();
+        let cores = cores
+            .into_iter()
+            .map(|(cpu, threads)| {
+                let key = (cpu.package, cpu.l3);
+                let l3 = match l3_groups.iter().position(|known| *known == key) {
+                    Some(l3) => l3,
+                    None => {
+                        l3_groups.push(key);
+                        l3_groups.len() - 1
+                    }
+                };
+                Core {
+                    package: cpu.package,
+                    l3,
+                    threads,
+                }
+            })
+            .collect();
+        Self { cores }
+    }
+
+    /// Number of logical CPUs.
+    pub fn thread_count(&self) -> usize {
+        self.cores.iter().map(|core| core.threads.len()).sum()
+    }
+
+    /// The cores of each L3 group, in order.
+    pub fn l3_groups(&self) -> Vec<&[Core]> {
+        self.cores.chunk_by(|a, b| a.l3 == b.l3).collect()
+    }
+
+    /// The cores of each of `ccd_count` CCDs (as counted from the Tccd sensors),
+    /// made of consecutive L3 groups: one per CCD, or two Zen 2 CCXs of up to
+    /// four cores each. None if the L3 groups don't pair up that way or a CCD
+    /// would span packages.
+    pub fn ccds(&self, ccd_count: usize) -> Option<Vec<&[Core]>> {
+        let l3_groups = self.l3_groups();
+        if ccd_count == 0 || !l3_groups.len().is_multiple_of(ccd_count) {
+            return None;
+        }
+        let per_ccd = l3_groups.len() / ccd_count;
+        if per_ccd > 2 || (per_ccd == 2 && l3_groups.iter().any(|group| group.len() > 4)) {
+            return None;
+        }
+
+        let mut ccds = Vec::with_capacity(ccd_count);
+        let mut start = 0;
+        for groups in l3_groups.chunks(per_ccd) {
+            let end = start + groups.iter().map(|group| group.len()).sum::<usize>();
+            let cores = &self.cores[start..end];
+            if cores.iter().any(|core| core.package != cores[0].package) {
+                return None;
+            }
+            ccds.push(cores);
+            start = end;
+        }
+        Some(ccds)
+    }
+
+    /// Usage of every core: the mean usage of its threads.
+    pub fn core_usages(&self, thread_usages: &[f32]) -> Vec<f32> {
+        self.cores
+            .iter()
+            .map(|core| {
+                let usages: Vec<f32> = core
+                    .threads
+                    .iter()
+                    .filter_map(|&thread| thread_usages.get(thread).copied())
+                    .collect();
+                if usages.is_empty() {
+                    0.0
+                } else {
+                    usages.iter().sum::<f32>() / usages.len() as f32
+                }
+            })
+            .collect()
+    }
+}
+
+/// Topology of the running system, read once.
+pub fn current() -> &'static CpuTopology {
+    static TOPOLOGY: OnceLock<CpuTopology> = OnceLock::new();
+    TOPOLOGY.get_or_init(|| {
+        let info = crate::what_cpu_check::get_cpu_info();
+        read_topology()
+            .filter(|topology| topology.thread_count() == info.threads)
+            .unwrap_or_else(|| CpuTopology::uniform(info.cores, info.threads))
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn read_topology() -> Option<CpuTopology> {
+    read_sysfs_topology(std::path::Path::new("/sys/devices/system/cpu"))
+}
+
+/// Reads the topology of every online CPU below `dir` (`/sys/devices/system/cpu`).
+#[cfg(target_os = "linux")]
+fn read_sysfs_topology(dir: &std::path::Path) -> Option<CpuTopology> {
+    let read = |cpu: &std::path::Path, file: &str| -> Option<usize> {
+        std::fs::read_to_string(cpu.join(file))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    };
+
+    let mut numbered: Vec<(usize, std::path::PathBuf)> = std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let number = name.strip_prefix("cpu")?.parse().ok()?;
+            Some((number, entry.path()))
+        })
+        // Offline CPUs have no topology and aren't listed by sysinfo either
+        .filter(|(_, path)| path.join("topology/core_id").exists())
+        .collect();
+    numbered.sort_by_key(|(number, _)| *number);
+
+    let cpus = numbered
+        .iter()
+        .map(|(_, cpu)| {
+            Some(LogicalCpu {
+                package: read(cpu, "topology/physical_package_id")?,
+                l3: read(cpu, "cache/index3/id").or_else(|| read(cpu, "topology/die_id"))?,
+                core: read(cpu, "topology/core_id")?,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    if cpus.is_empty() {
+        return None;
+    }
+    Some(CpuTopology::from_logical_cpus(&cpus))
+}
+
+#[cfg(target_os = "windows")]
+fn read_topology() -> Option<CpuTopology> {
+    use windows::Win32::System::SystemInformation::{
+        GetLogicalProcessorInformationEx, RelationAll, RelationCache, RelationProcessorCore,
+        RelationProcessorPackage, GROUP_AFFINITY, SYSTEM_LOGICAL_PROCESSOR_INFORMATION_EX,
+    };
+
+    // Logical CPU indexes in a processor group mask (64 per group).
+    fn mask_cpus(mask: &GROUP_AFFINITY) -> impl Iterator<Item = usize> + '_ {
+        (0..usize::BITS as usize)
+            .filter(move |bit| mask.Mask & (1 << bit) != 0)
+            .map(move |bit| mask.Group as usize * usize::BITS as usize + bit)
+    }
+
+    let mut length = 0u32;
+    unsafe {
+        // The first call only reports the buffer size
+        let _ = GetLogicalProcessorInformationEx(RelationAll, None, &mut length);
+    }
+    // u64 words keep the records aligned
+    let mut buffer = vec![0u64; (length as usize).div_ceil(8)];
+    unsafe {
+        GetLogicalProcessorInformationEx(
+            RelationAll,
+            Some(buffer.as_mut_ptr() as *mut SYSTEM_LOGICAL_PROCESSOR_INFORMATION_EX),
+            &mut length,
+        )
+        .ok()?;
+    }
+
+    // (package, L3, core) of every logical CPU seen so far
+    let mut cpus: Vec<[Option<usize>; 3]> = Vec::new();
+    let mut counts = [0usize; 3];
+    let base = buffer.as_ptr() as *const u8;
+    let mut offset = 0usize;
+    while offset < length as usize {
+        let info =
+            unsafe { &*(base.add(offset) as *const SYSTEM_LOGICAL_PROCESSOR_INFORMATION_EX) };
+        let slot = if info.Relationship == RelationProcessorPackage {
+            Some(0)
+        } else if info.Relationship == RelationCache && unsafe { info.Anonymous.Cache.Level } == 3 {
+            Some(1)
+        } else if info.Relationship == RelationProcessorCore {
+            Some(2)
+        } else {
+            None
+        };
+        if let Some(slot) = slot {
+            let mask = unsafe {
+                if slot == 1 {
+                    info.Anonymous.Cache.Anonymous.GroupMask
+                } else {
+                    info.Anonymous.Processor.GroupMask[0]
+                }
+            };
+            for cpu in mask_cpus(&mask) {
+                if cpus.len() <= cpu {
+                    cpus.resize(cpu + 1, [None; 3]);
+                }
+                cpus[cpu][slot] = Some(counts[slot]);
+            }
+            counts[slot] += 1;
+        }
+        offset += info.Size as usize;
+    }
+
+    let cpus = cpus
+        .into_iter()
+        .map(|[package, l3, core]| {
+            Some(LogicalCpu {
+                package: package?,
+                l3: l3.unwrap_or(0),
+                core: core?,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    if cpus.is_empty() {
+        return None;
+    }
+    Some(CpuTopology::from_logical_cpus(&cpus))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn threads(topology: &CpuTopology) -> Vec<Vec<usize>> {
+        topology
+            .cores
+            .iter()
+            .map(|core| core.threads.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_siblings_are_grouped() {
+        // Linux numbering: cpu0-3 are the first threads, cpu4-7 their siblings;
+        // two L3 groups of two cores each
+        let cpus: Vec<LogicalCpu> = (0..8)
+            .map(|cpu| LogicalCpu {
+                package: 0,
+                l3: (cpu % 4) / 2 * 8,
+                core: cpu % 4,
+            })
+            .collect();
+        let topology = CpuTopology::from_logical_cpus(&cpus);
+
+        assert_eq!(
+            threads(&topology),
+            vec![vec![0, 4], vec![1, 5], vec![2, 6], vec![3, 7]]
+        );
+        assert_eq!(
+            topology
+                .l3_groups()
+                .iter()
+                .map(|ccd| ccd.len())
+                .collect::<Vec<_>>(),
+            vec![2, 2]
+        );
+        assert_eq!(topology.thread_count(), 8);
+
+        let usages = [10.0, 20.0, 30.0, 40.0, 30.0, 40.0, 50.0, 60.0];
+        assert_eq!(topology.core_usages(&usages), vec![20.0, 30.0, 40.0, 50.0]);
+    }
+
+    #[test]
+    fn test_ccds_from_l3_groups() {
+        // `cores_per_l3` cores with one thread each in every L3 group
+        let topology = |l3_groups: usize, cores_per_l3: usize| {
+            let cpus: Vec<LogicalCpu> = (0..l3_groups * cores_per_l3)
+                .map(|cpu| LogicalCpu {
+                    package: 0,
+                    l3: cpu / cores_per_l3,
+                    core: cpu,
+                })
+                .collect();
+            CpuTopology::from_logical_cpus(&cpus)
+        };
+        let sizes = |ccds: Vec<&[Core]>| ccds.iter().map(|ccd| ccd.len()).collect::<Vec<_>>();
+
+        // Zen 2 (3900X): four CCXs of three cores are two CCDs
+        assert_eq!(topology(4, 3).ccds(2).map(sizes), Some(vec![6, 6]));
+        // Zen 3 and later: one L3 per CCD
+        assert_eq!(topology(2, 8).ccds(2).map(sizes), Some(vec![8, 8]));
+        // A missing Tccd doesn't merge two eight-core CCDs
+        assert_eq!(topology(2, 8).ccds(1), None);
+        assert_eq!(topology(3, 4).ccds(2), None);
+        assert_eq!(topology(1, 8).ccds(0), None);
+    }
+
+    #[test]
+    fn test_uniform_fallback() {
+        let topology = CpuTopology::uniform(4, 8);
+        assert_eq!(
+            threads(&topology),
+            vec![vec![0, 1], vec![2, 3], vec![4, 5], vec![6, 7]]
+        );
+        assert_eq!(topology.l3_groups().len(), 1);
+
+        // Hybrid CPUs don't split evenly; every thread still belongs to some core
+        assert_eq!(CpuTopology::uniform(3, 8).thread_count(), 8);
+        assert_eq!(CpuTopology::uniform(0, 2).thread_count(), 2);
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod linux_tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_reads_sysfs() {
+        let dir = std::env::temp_dir().join(format!("cutemonitor_topology_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let write = |path: String, contents: usize| {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, format!("{}\n", contents)).unwrap();
+        };
+        // Two cores with two threads each, cpu4 offline
+        for (cpu, core) in [(0, 0), (1, 1), (2, 0), (3, 1)] {
+            write(format!("cpu{}/topology/physical_package_id", cpu), 0);
+            write(format!("cpu{}/topology/die_id", cpu), 0);
+            write(format!("cpu{}/topology/core_id", cpu), core);
+            write(format!("cpu{}/cache/index3/id", cpu), 0);
+        }
+        fs::create_dir_all(dir.join("cpu4")).unwrap();
+        fs::create_dir_all(dir.join("cpufreq")).unwrap();
+
+        let topology = read_sysfs_topology(&dir).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(topology.cores.len(), 2);
+        assert_eq!(topology.cores[0].threads, vec![0, 2]);
+        assert_eq!(topology.cores[1].threads, vec![1, 3]);
+    }
+}
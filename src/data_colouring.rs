@@ -1,5 +1,8 @@
 use iced::Color;
 
+/// Where `temperature_color` turns red-orange; used by the throttling check in `cpu_frequency.rs`
+pub const HOT_TEMPERATURE: f32 = 66.0;
+
 /// Color-coding for temperature values (10°C to 80°C range)
 /// Maps temperature to intuitive gradient from cool blues to hot reds
 pub fn temperature_color(temp: f32) -> Color {
@@ -8,7 +11,7 @@ pub fn temperature_color(temp: f32) -> Color {
         (24.0, 255, 255, 0),     // Cool - cyan  
         (38.0, 255, 191, 0),     // Warm - yellow
         (52.0, 255, 128, 0),     // Hot - orange
-        (66.0, 255, 64, 0),      // Very hot - red-orange
+        (HOT_TEMPERATURE, 255, 64, 0), // Very hot - red-orange
         (80.0, 255, 0, 0),       // Extremely hot - red
     ];
 
@@ -109,4 +112,31 @@ pub fn memory_color(memory_usage: f32) -> Color {
     
     // Use voltage color mapping
     voltage_color(voltage_equiv)
-}
\ No newline at end of file
+}
+
+/// Maps fan speed to temperature-equivalent colors
+/// Range: 0 RPM to 3000 RPM, mapped to 10°C to 80°C temperature colors
+pub fn fan_speed_color(rpm: f32) -> Color {
+    let clamped_rpm = rpm.clamp(0.0, 3000.0);
+
+    // Map RPM range to temperature range (10°C to 80°C)
+    // Formula: temp_equiv = 10.0 + rpm * 70.0 / 3000.0
+    let temp_equiv = 10.0 + clamped_rpm * 70.0 / 3000.0;
+
+    // Use temperature color mapping
+    temperature_color(temp_equiv)
+}
+
+/// Colors of the CPU time states, in the order of `cpu_times::STATES`
+/// (user, nice, system, iowait, irq, softirq, steal)
+pub fn cpu_time_colors() -> [Color; 7] {
+    [
+        Color::from_rgb8(123, 104, 238), // user - same purple as the usage bars
+        Color::from_rgb8(72, 61, 139),   // nice - darker purple
+        Color::from_rgb8(255, 128, 0),   // system - orange
+        Color::from_rgb8(255, 255, 0),   // iowait - yellow
+        Color::from_rgb8(255, 64, 160),  // irq - pink
+        Color::from_rgb8(255, 160, 200), // softirq - light pink
+        Color::from_rgb8(255, 0, 0),     // steal - red
+    ]
+}
@@ -0,0 +1,218 @@
+//! Health of the hardware data collector.
+//!
+//! Every line TempMonitor.exe prints is either parsed or counted here by why it
+//! was rejected, and the last rejected line is kept with its error. When the
+//! helper's output changes shape, the window shows a diagnostics panel instead
+//! of silently freezing the temperatures, and `--diagnose` prints the same
+//! report from the command line.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use iced::futures::StreamExt;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::hardware_checker::{self, HardwareEvent};
+use crate::sensors::{ParseError, ParseErrorKind};
+
+/// How long `--diagnose` watches the collector.
+const DIAGNOSE_DURATION: Duration = Duration::from_secs(10);
+
+/// Rejected lines are kept up to this many characters.
+const MAX_LINE_CHARS: usize = 500;
+
+/// The most recent line that failed to parse.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BadLine {
+    /// Milliseconds since the Unix epoch when the line arrived.
+    pub timestamp_ms: u64,
+    pub kind: ParseErrorKind,
+    pub error: String,
+    /// The line itself, shortened to `MAX_LINE_CHARS`.
+    pub line: String,
+}
+
+/// Line counters of the hardware collector.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CollectorHealth {
+    pub parsed: u64,
+    /// Lines that weren't JSON.
+    pub malformed: u64,
+    /// JSON of the wrong shape or protocol version.
+    pub schema_mismatch: u64,
+    /// JSON missing a required field.
+    pub missing_field: u64,
+    pub last_bad_line: Option<BadLine>,
+}
+
+impl CollectorHealth {
+    /// Total number of rejected lines.
+    pub fn errors(&self) -> u64 {
+        self.malformed + self.schema_mismatch + self.missing_field
+    }
+
+    pub fn record_parsed(&mut self) {
+        self.parsed += 1;
+    }
+
+    pub fn record_error(&mut self, line: &str, error: &ParseError) {
+        match error.kind {
+            ParseErrorKind::Malformed => self.malformed += 1,
+            ParseErrorKind::SchemaMismatch => self.schema_mismatch += 1,
+            ParseErrorKind::MissingField => self.missing_field += 1,
+        }
+        let mut kept: String = line.chars().take(MAX_LINE_CHARS).collect();
+        if kept.len() < line.len() {
+            kept.push('…');
+        }
+        self.last_bad_line = Some(BadLine {
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_millis() as u64),
+            kind: error.kind,
+            error: error.message.clone(),
+            line: kept,
+        });
+    }
+
+    /// Human-readable report, as printed by `--diagnose`.
+    pub fn report(&self) -> String {
+        let mut lines = vec![
+            format!("Lines parsed:     {}", self.parsed),
+            format!("Malformed JSON:   {}", self.malformed),
+            format!("Schema mismatch:  {}", self.schema_mismatch),
+            format!("Missing field:    {}", self.missing_field),
+        ];
+        match &self.last_bad_line {
+            Some(bad) => {
+                lines.push(format!("Last error:       {}", bad.error));
+                lines.push(format!("Last bad line:    {}", bad.line));
+            }
+            None => lines.push("Last error:       none".to_string()),
+        }
+        lines.join("\n")
+    }
+}
+
+lazy_static! {
+    static ref HEALTH: Mutex<CollectorHealth> = Mutex::new(CollectorHealth::default());
+}
+
+// Only the TempMonitor.exe line reader uses these so far
+/// Counts a successfully parsed line.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub fn record_parsed() {
+    HEALTH.lock().unwrap().record_parsed();
+}
+
+/// Counts a rejected line and returns the updated health.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub fn record_error(line: &str, error: &ParseError) -> CollectorHealth {
+    let mut health = HEALTH.lock().unwrap();
+    health.record_error(line, error);
+    health.clone()
+}
+
+/// Returns a copy of the collector health so far.
+pub fn health() -> CollectorHealth {
+    HEALTH.lock().unwrap().clone()
+}
+
+/// Runs the hardware collector for a few seconds and prints its health.
+/// Returns the process exit code: 0 if readings arrived and every line parsed.
+pub fn run() -> i32 {
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Failed to start the runtime: {}", e);
+            return 1;
+        }
+    };
+
+    println!(
+        "Watching the hardware collector for {}s...",
+        DIAGNOSE_DURATION.as_secs()
+    );
+    let readings = runtime.block_on(async {
+        let deadline = Instant::now() + DIAGNOSE_DURATION;
+        let mut events = std::pin::pin!(hardware_checker::hardware_data_channel());
+        let mut readings = 0u64;
+        while let Ok(Some(event)) = tokio::time::timeout_at(deadline.into(), events.next()).await {
+            match event.value {
+                HardwareEvent::Data(_) => readings += 1,
+                HardwareEvent::Monitor(state) => println!("Sensor helper: {}", state),
+                HardwareEvent::Sensors(_) | HardwareEvent::Health(_) => {}
+            }
+        }
+        readings
+    });
+    // The collector thread blocks on the helper's output, don't wait for it
+    runtime.shutdown_background();
+
+    let health = health();
+    println!("Readings received: {}", readings);
+    println!("{}", health.report());
+
+    if readings > 0 && health.errors() == 0 {
+        0
+    } else {
+        1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(kind: ParseErrorKind, message: &str) -> ParseError {
+        ParseError {
+            kind,
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_counts_errors_by_kind() {
+        let mut health = CollectorHealth::default();
+        health.record_parsed();
+        health.record_error(
+            "garbage",
+            &error(ParseErrorKind::Malformed, "expected value"),
+        );
+        health.record_error(
+            "{}",
+            &error(ParseErrorKind::MissingField, "missing field `CpuTemp`"),
+        );
+        health.record_error(
+            "{}",
+            &error(ParseErrorKind::MissingField, "missing field `CpuTemp`"),
+        );
+
+        assert_eq!(health.parsed, 1);
+        assert_eq!(health.malformed, 1);
+        assert_eq!(health.schema_mismatch, 0);
+        assert_eq!(health.missing_field, 2);
+        assert_eq!(health.errors(), 3);
+
+        let bad = health.last_bad_line.as_ref().unwrap();
+        assert_eq!(bad.kind, ParseErrorKind::MissingField);
+        assert_eq!(bad.line, "{}");
+        assert!(health
+            .report()
+            .contains("Last error:       missing field `CpuTemp`"));
+    }
+
+    #[test]
+    fn test_long_lines_are_shortened() {
+        let mut health = CollectorHealth::default();
+        let line = "x".repeat(MAX_LINE_CHARS * 2);
+        health.record_error(
+            &line,
+            &error(ParseErrorKind::Malformed, "trailing characters"),
+        );
+        let kept = &health.last_bad_line.unwrap().line;
+        assert_eq!(kept.chars().count(), MAX_LINE_CHARS + 1);
+        assert!(kept.ends_with('…'));
+    }
+}
@@ -0,0 +1,383 @@
+//! Sensors read from user-configured commands.
+//!
+//! Each entry in `external_sensors` in `settings.json` names a command (a
+//! script, an Arduino bridge, an IPMI tool, ...) that prints one JSON sample
+//! per line:
+//!
+//! ```json
+//! {"id": "room", "label": "Room temperature", "unit": "°C", "value": 21.5}
+//! ```
+//!
+//! `label` defaults to the id and `unit` to nothing. The command runs under the
+//! same supervisor as TempMonitor.exe, so it is restarted when it exits or goes
+//! quiet for longer than its `stall_timeout_ms` (10 seconds by default). The latest value of every sensor is shown in the window and goes to
+//! every export path (headless JSON, Prometheus, recordings). The window also
+//! shows what each command is doing and the last line it got wrong; readings of
+//! a command that isn't running are dropped rather than shown as current.
+
+use std::time::Duration;
+
+use iced_futures::futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::sensors::{ParseError, ParseErrorKind};
+use crate::supervisor::{line_channel, ChildState, SupervisorConfig};
+
+/// One command from `settings.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExternalCommand {
+    /// Name shown with the readings; must be unique.
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Restart the command if it prints nothing for this long; commands that
+    /// report every minute need more than the default 10 seconds.
+    #[serde(default)]
+    pub stall_timeout_ms: Option<u64>,
+}
+
+impl ExternalCommand {
+    /// How the supervisor runs this command.
+    fn supervisor_config(&self) -> SupervisorConfig {
+        let config = SupervisorConfig::new(&self.command).args(&self.args);
+        match self.stall_timeout_ms {
+            Some(ms) => config.stall_timeout(Duration::from_millis(ms)),
+            None => config,
+        }
+    }
+}
+
+/// A sample as printed by the command.
+#[derive(Debug, Deserialize)]
+struct Sample {
+    id: String,
+    label: Option<String>,
+    #[serde(default)]
+    unit: String,
+    value: f64,
+}
+
+/// Latest value of one external sensor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExternalReading {
+    /// Name of the command that reported it.
+    pub source: String,
+    pub id: String,
+    pub label: String,
+    pub unit: String,
+    pub value: f64,
+}
+
+/// Output of one external command.
+#[derive(Debug, Clone)]
+pub enum ExternalEvent {
+    Reading(ExternalReading),
+    State { source: String, state: ChildState },
+    Rejected { source: String, error: ParseError },
+}
+
+/// Parses one line printed by the command `source`.
+pub fn parse_sample(source: &str, line: &str) -> Result<ExternalReading, ParseError> {
+    let sample: Sample = serde_json::from_str(line)?;
+    if sample.id.is_empty() || !sample.value.is_finite() {
+        return Err(ParseError {
+            kind: ParseErrorKind::SchemaMismatch,
+            message: "id must not be empty and value must be a finite number".to_string(),
+        });
+    }
+    Ok(ExternalReading {
+        source: source.to_string(),
+        label: sample.label.unwrap_or_else(|| sample.id.clone()),
+        id: sample.id,
+        unit: sample.unit,
+        value: sample.value,
+    })
+}
+
+/// Sensors of one command keep separate slots in the latest-value channel.
+fn slot(event: &ExternalEvent) -> (std::mem::Discriminant<ExternalEvent>, Option<String>) {
+    let id = match event {
+        ExternalEvent::Reading(reading) => Some(reading.id.clone()),
+        _ => None,
+    };
+    (std::mem::discriminant(event), id)
+}
+
+/// Runs `command` and streams its samples until the stream is dropped.
+/// Unread samples of a sensor are replaced by newer ones.
+pub fn channel(command: ExternalCommand) -> impl Stream<Item = ExternalEvent> {
+    let source = command.name.clone();
+    let state_source = command.name.clone();
+    line_channel(
+        slot,
+        move || command.supervisor_config(),
+        move |line, emit| {
+            let line = line.trim();
+            if line.is_empty() {
+                return;
+            }
+            emit(match parse_sample(&source, line) {
+                Ok(reading) => ExternalEvent::Reading(reading),
+                Err(error) => ExternalEvent::Rejected {
+                    source: source.clone(),
+                    error,
+                },
+            });
+        },
+        move |state| ExternalEvent::State {
+            source: state_source.clone(),
+            state,
+        },
+    )
+    .map(|stamped| stamped.value)
+}
+
+/// Creates one iced subscription per configured command. Editing the list in
+/// `settings.json` starts and stops commands as needed.
+pub fn subscription() -> iced::Subscription<ExternalEvent> {
+    let commands = crate::settings::current().external_sensors;
+    iced::Subscription::batch(commands.into_iter().map(|command| {
+        iced::Subscription::run_with_id(("external_sensor", command.clone()), channel(command))
+    }))
+}
+
+/// What one command is doing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceStatus {
+    pub source: String,
+    pub state: ChildState,
+    /// Why the last rejected line was rejected.
+    pub last_error: Option<String>,
+}
+
+/// Latest reading of every external sensor, in the order they first appeared,
+/// and the state of every command.
+#[derive(Debug, Clone, Default)]
+pub struct ExternalSensors {
+    readings: Vec<ExternalReading>,
+    sources: Vec<SourceStatus>,
+}
+
+impl ExternalSensors {
+    pub fn update(&mut self, reading: ExternalReading) {
+        match self
+            .readings
+            .iter_mut()
+            .find(|known| known.source == reading.source && known.id == reading.id)
+        {
+            Some(known) => *known = reading,
+            None => self.readings.push(reading),
+        }
+    }
+
+    /// Records a state change of `source`. Once the command stops running its
+    /// readings are dropped, so a crashed command leaves no stale values behind.
+    pub fn set_state(&mut self, source: &str, state: ChildState) {
+        if !matches!(state, ChildState::Running { .. }) {
+            self.readings.retain(|reading| reading.source != source);
+        }
+        self.status_mut(source).state = state;
+    }
+
+    /// Records a line of `source` that wasn't a valid sample.
+    pub fn reject(&mut self, source: &str, error: &ParseError) {
+        self.status_mut(source).last_error = Some(error.message.clone());
+    }
+
+    fn status_mut(&mut self, source: &str) -> &mut SourceStatus {
+        let index = match self
+            .sources
+            .iter()
+            .position(|status| status.source == source)
+        {
+            Some(index) => index,
+            None => {
+                self.sources.push(SourceStatus {
+                    source: source.to_string(),
+                    state: ChildState::Starting,
+                    last_error: None,
+                });
+                self.sources.len() - 1
+            }
+        };
+        &mut self.sources[index]
+    }
+
+    /// Drops the readings and states of commands that are no longer configured.
+    pub fn retain_sources(&mut self, commands: &[ExternalCommand]) {
+        let configured = |source: &str| commands.iter().any(|command| command.name == source);
+        self.readings.retain(|reading| configured(&reading.source));
+        self.sources.retain(|status| configured(&status.source));
+    }
+
+    pub fn readings(&self) -> &[ExternalReading] {
+        &self.readings
+    }
+
+    pub fn sources(&self) -> &[SourceStatus] {
+        &self.sources
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sample() {
+        let reading = parse_sample(
+            "arduino",
+            r#"{"id": "room", "label": "Room temperature", "unit": "°C", "value": 21.5}"#,
+        )
+        .unwrap();
+        assert_eq!(reading.source, "arduino");
+        assert_eq!(reading.label, "Room temperature");
+        assert_eq!(reading.unit, "°C");
+        assert_eq!(reading.value, 21.5);
+
+        let reading = parse_sample("ipmi", r#"{"id": "psu_watts", "value": 212}"#).unwrap();
+        assert_eq!(reading.label, "psu_watts");
+        assert_eq!(reading.unit, "");
+
+        let kind = |line: &str| parse_sample("x", line).unwrap_err().kind;
+        assert_eq!(kind("21.5"), ParseErrorKind::SchemaMismatch);
+        assert_eq!(kind(r#"{"id": "room"}"#), ParseErrorKind::MissingField);
+        assert_eq!(
+            kind(r#"{"id": "", "value": 1}"#),
+            ParseErrorKind::SchemaMismatch
+        );
+        assert_eq!(kind("room=21.5"), ParseErrorKind::Malformed);
+    }
+
+    #[test]
+    fn test_stall_timeout_from_settings() {
+        let mut command: ExternalCommand =
+            serde_json::from_str(r#"{"name": "ipmi", "command": "ipmitool"}"#).unwrap();
+        assert_eq!(
+            command.supervisor_config().stall_timeout,
+            Duration::from_secs(10)
+        );
+
+        command.stall_timeout_ms = Some(90_000);
+        assert_eq!(
+            command.supervisor_config().stall_timeout,
+            Duration::from_secs(90)
+        );
+    }
+
+    #[test]
+    fn test_latest_reading_per_sensor() {
+        let mut sensors = ExternalSensors::default();
+        let reading = |source: &str, id: &str, value| ExternalReading {
+            source: source.to_string(),
+            id: id.to_string(),
+            label: id.to_string(),
+            unit: String::new(),
+            value,
+        };
+        sensors.update(reading("a", "one", 1.0));
+        sensors.update(reading("a", "two", 2.0));
+        sensors.update(reading("b", "one", 3.0));
+        sensors.update(reading("a", "one", 4.0));
+        let values: Vec<f64> = sensors.readings().iter().map(|r| r.value).collect();
+        assert_eq!(values, vec![4.0, 2.0, 3.0]);
+
+        let remaining = ExternalCommand {
+            name: "b".to_string(),
+            command: "b.sh".to_string(),
+            args: Vec::new(),
+            stall_timeout_ms: None,
+        };
+        sensors.retain_sources(&[remaining]);
+        assert_eq!(sensors.readings().len(), 1);
+    }
+
+    #[test]
+    fn test_stopped_command_drops_its_readings() {
+        let mut sensors = ExternalSensors::default();
+        let reading = |source: &str| ExternalReading {
+            source: source.to_string(),
+            id: "temp".to_string(),
+            label: "temp".to_string(),
+            unit: "°C".to_string(),
+            value: 40.0,
+        };
+        sensors.set_state("a", ChildState::Running { pid: 10 });
+        sensors.update(reading("a"));
+        sensors.update(reading("b"));
+        sensors.reject("a", &parse_sample("a", "oops").unwrap_err());
+
+        sensors.set_state(
+            "a",
+            ChildState::Restarting {
+                attempt: 1,
+                delay: std::time::Duration::from_secs(1),
+                reason: "Exited (exit status: 1)".to_string(),
+            },
+        );
+        let sources: Vec<&str> = sensors
+            .readings()
+            .iter()
+            .map(|r| r.source.as_str())
+            .collect();
+        assert_eq!(sources, vec!["b"]);
+        let status = &sensors.sources()[0];
+        assert!(matches!(
+            status.state,
+            ChildState::Restarting { attempt: 1, .. }
+        ));
+        assert!(status.last_error.is_some());
+
+        sensors.retain_sources(&[]);
+        assert!(sensors.readings().is_empty());
+        assert!(sensors.sources().is_empty());
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod linux_tests {
+    use super::*;
+    use iced_futures::futures::StreamExt;
+
+    #[test]
+    fn test_reads_samples_from_command() {
+        let command = ExternalCommand {
+            name: "script".to_string(),
+            command: "sh".to_string(),
+            args: vec![
+                "-c".to_string(),
+                r#"echo '{"id":"fan","unit":"RPM","value":900}'; echo oops; sleep 5"#.to_string(),
+            ],
+            stall_timeout_ms: None,
+        };
+        // State changes replace each other until read, so `Starting` may never
+        // be seen; wait for the reading and the rejected line instead
+        let events: Vec<ExternalEvent> = tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let mut stream = std::pin::pin!(channel(command));
+            let mut events = Vec::new();
+            let rejected = |event: &ExternalEvent| matches!(event, ExternalEvent::Rejected { .. });
+            while !events.iter().any(rejected) {
+                let next = tokio::time::timeout(std::time::Duration::from_secs(5), stream.next());
+                events.push(next.await.unwrap().unwrap());
+            }
+            events
+        });
+
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, ExternalEvent::State { .. })));
+        let samples: Vec<&ExternalEvent> = events
+            .iter()
+            .filter(|event| !matches!(event, ExternalEvent::State { .. }))
+            .collect();
+        assert_eq!(samples.len(), 2, "{:?}", events);
+        assert!(
+            matches!(samples[0], ExternalEvent::Reading(reading) if reading.id == "fan" && reading.value == 900.0)
+        );
+        assert!(
+            matches!(samples[1], ExternalEvent::Rejected { error, .. } if error.kind == ParseErrorKind::Malformed)
+        );
+    }
+}
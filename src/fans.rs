@@ -0,0 +1,144 @@
+//! Fan and pump readings.
+//!
+//! On Windows they are taken from the LibreHardwareMonitor sensor tree that
+//! TempMonitor.exe streams (`Fan` sensors of the Super I/O chip and of fan
+//! controllers, with the `Control` sensor of the same header as the PWM duty); on
+//! Linux `hwmon.rs` reads `fan*_input` and `pwm*`. Either way they arrive with
+//! the rest of `HardwareData`, and `FanHistory` keeps the recent RPMs for the
+//! sparklines in the fan panel.
+
+use serde::{Deserialize, Serialize};
+
+use crate::sensors::SensorTree;
+
+/// One fan or pump header.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct FanReading {
+    /// Stable identifier, e.g. `/lpc/nct6798d/fan/1` or `nct6798-nct6775.656/fan2`.
+    pub id: String,
+    pub name: String,
+    /// Current speed (None if the tachometer can't be read).
+    pub rpm: Option<f32>,
+    /// PWM duty cycle in percent, where the controller exposes it.
+    pub duty_percent: Option<f32>,
+}
+
+/// Trailing index of a LibreHardwareMonitor sensor id (`/lpc/nct6798d/fan/1` -> `1`).
+fn sensor_index(id: &str) -> Option<&str> {
+    id.rsplit('/').next()
+}
+
+/// Every `Fan` sensor in the tree, paired with the `Control` sensor of the same header.
+pub fn from_sensor_tree(tree: &SensorTree) -> Vec<FanReading> {
+    tree.sensors
+        .iter()
+        .filter(|sensor| sensor.sensor_type == "Fan")
+        .map(|fan| {
+            let duty_percent = tree
+                .sensors
+                .iter()
+                .find(|control| {
+                    control.sensor_type == "Control"
+                        && control.hardware_id == fan.hardware_id
+                        && sensor_index(&control.id) == sensor_index(&fan.id)
+                })
+                .and_then(|control| control.value);
+            FanReading {
+                id: fan.id.clone(),
+                name: fan.name.clone(),
+                rpm: fan.value,
+                duty_percent,
+            }
+        })
+        .collect()
+}
+
+/// Recent RPMs of every fan, newest first, for the sparklines.
+#[derive(Debug, Clone, Default)]
+pub struct FanHistory {
+    histories: Vec<(String, Vec<f32>)>,
+}
+
+impl FanHistory {
+    /// Adds the latest readings; fans that disappeared are dropped.
+    pub fn update(&mut self, fans: &[FanReading], history_size: usize) {
+        let mut previous = std::mem::take(&mut self.histories);
+        for fan in fans {
+            let mut history = previous
+                .iter()
+                .position(|(id, _)| *id == fan.id)
+                .map(|index| previous.swap_remove(index).1)
+                .unwrap_or_default();
+            history.insert(0, fan.rpm.unwrap_or(0.0));
+            history.truncate(history_size);
+            self.histories.push((fan.id.clone(), history));
+        }
+    }
+
+    /// Recent RPMs of one fan, newest first.
+    pub fn get(&self, id: &str) -> &[f32] {
+        self.histories
+            .iter()
+            .find(|(known, _)| known == id)
+            .map_or(&[], |(_, history)| history.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::Sensor;
+
+    fn sensor(id: &str, sensor_type: &str, name: &str, value: f32) -> Sensor {
+        Sensor {
+            hardware_id: "/lpc/nct6798d".to_string(),
+            hardware_name: "Nuvoton NCT6798D".to_string(),
+            hardware_type: "SuperIO".to_string(),
+            id: id.to_string(),
+            sensor_type: sensor_type.to_string(),
+            name: name.to_string(),
+            value: Some(value),
+            min: None,
+            max: None,
+        }
+    }
+
+    #[test]
+    fn test_fans_from_sensor_tree() {
+        let tree = SensorTree {
+            sensors: vec![
+                sensor("/lpc/nct6798d/fan/0", "Fan", "Fan #1", 850.0),
+                sensor("/lpc/nct6798d/fan/1", "Fan", "Pump", 2400.0),
+                sensor("/lpc/nct6798d/control/1", "Control", "Pump", 100.0),
+                sensor("/lpc/nct6798d/control/2", "Control", "Fan #3", 30.0),
+                sensor("/lpc/nct6798d/temperature/0", "Temperature", "CPU", 50.0),
+            ],
+        };
+        let fans = from_sensor_tree(&tree);
+
+        assert_eq!(fans.len(), 2);
+        assert_eq!(fans[0].name, "Fan #1");
+        assert_eq!(fans[0].rpm, Some(850.0));
+        assert_eq!(fans[0].duty_percent, None);
+        assert_eq!(fans[1].name, "Pump");
+        assert_eq!(fans[1].duty_percent, Some(100.0));
+    }
+
+    #[test]
+    fn test_history_follows_fans() {
+        let fan = |id: &str, rpm| FanReading {
+            id: id.to_string(),
+            name: id.to_string(),
+            rpm: Some(rpm),
+            duty_percent: None,
+        };
+        let mut history = FanHistory::default();
+        history.update(&[fan("a", 800.0), fan("b", 1200.0)], 2);
+        history.update(&[fan("a", 900.0), fan("b", 1300.0)], 2);
+        history.update(&[fan("a", 1000.0)], 2);
+
+        assert_eq!(history.get("a"), &[1000.0, 900.0]);
+        assert!(history.get("b").is_empty());
+    }
+}
@@ -1,6 +1,9 @@
 // Import serde for serialization/deserialization - allows converting data to/from JSON
 use serde::{Deserialize, Serialize};
 
+/// Model name of the placeholder entry shown when no GPU is found
+pub const NO_GPU_MODEL: &str = "No GPU detected";
+
 /// GPU data structure for real-time monitoring (legacy single GPU)
 /// 
 /// This struct represents the core data collected for a single GPU.
@@ -51,7 +54,7 @@ pub struct GpuData {
 impl Default for GpuData {
     fn default() -> Self {
         Self {
-            model: "No GPU detected".to_string(),  // Default message when no GPU is found
+            model: NO_GPU_MODEL.to_string(),       // Default message when no GPU is found
             vram_mb: 0,                            // No VRAM by default
             temp: None,                            // Temperature not available
             utilization: None,                     // Utilization not available
@@ -135,7 +138,7 @@ impl From<GpuInfo> for GpuData {
 impl Default for GpuInfo {
     fn default() -> Self {
         Self {
-            name: "No GPU detected".to_string(),    // Default placeholder name
+            name: NO_GPU_MODEL.to_string(),         // Default placeholder name
             adapter_ram: 0,                         // No memory by default
             driver_version: "Unknown".to_string(),  // Unknown driver
             pnp_device_id: "Unknown".to_string(),   // Unknown device ID
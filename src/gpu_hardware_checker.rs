@@ -1,5 +1,6 @@
 // Import required modules
-use iced_futures::stream;                              // Stream utilities for Iced framework
+use iced_futures::futures::{Stream, StreamExt};       // Stream traits for the raw data channel
+use crate::collector::{self, Stamped};                 // Latest-value channel to the GUI
 use crate::gpu_data::GpuData;                          // GPU data structure for GUI
 use crate::launch_gpu_detect::LaunchGpuDetector;       // GPU detection functionality
 use crate::gpu_monitor_manager::GpuMonitorManager;     // GPU monitoring management
@@ -21,9 +22,25 @@ use log::{debug, error, warn};                          // Logging utilities
 pub fn multi_gpu_data_stream() -> iced::Subscription<Vec<GpuData>> {
     debug!("Creating multi-GPU data stream subscription");
     
-    // Create a stream channel with buffer size of 100,000 messages
-    // This buffer prevents message loss if the GUI can't keep up
-    let stream = stream::channel(100000, |mut sender| async move {
+    // Create and return the Iced subscription with a unique ID
+    // The GUI only needs the readings, not their stamps
+    iced::Subscription::run_with_id(
+        "multi_gpu",
+        multi_gpu_data_channel().map(|stamped| stamped.value),
+    )
+}
+
+/// Creates the raw multi-GPU data stream behind `multi_gpu_data_stream()`
+/// 
+/// Kept separate from the subscription so the same detection and monitoring
+/// loop can run without an Iced application (e.g. in headless JSON mode).
+/// 
+/// Returns: A stream that emits the latest stamped Vec<GpuData> every second
+pub fn multi_gpu_data_channel() -> impl Stream<Item = Stamped<Vec<GpuData>>> {
+    // Create a latest-value channel (see collector.rs)
+    // If the GUI can't keep up, an unread update is replaced by the newer one,
+    // so memory stays bounded and the GUI never renders stale readings
+    collector::latest(|_| (), |publisher| async move {
         debug!("Stream channel created, initializing GPU detector");
         
         // === STEP 1: Initialize GPU Detector ===
@@ -104,17 +121,15 @@ pub fn multi_gpu_data_stream() -> iced::Subscription<Vec<GpuData>> {
                 .collect();        // Collect into vector
 
             // === STEP 8: Send Data to GUI ===
-            // Send the updated data through the channel to the GUI
-            // try_send() is non-blocking - if the channel is full, we skip this update
-            let _ = sender.try_send(gpu_data_list);
+            // Publish the updated data through the channel to the GUI
+            // publish() is non-blocking - it replaces any update the GUI hasn't read yet
+            publisher.publish(gpu_data_list);
 
             // === STEP 9: Wait for Next Update ===
-            // Sleep for 1 second to achieve ~1Hz update rate
+            // Sleep for 1 second by default to achieve ~1Hz update rate
             // This provides responsive monitoring without overwhelming the system
-            tokio::time::sleep(std::time::Duration::from_millis(1000)).await;
+            let interval = crate::settings::current().intervals_ms.gpu;
+            tokio::time::sleep(std::time::Duration::from_millis(interval)).await;
         }
-    });
-    
-    // Create and return the Iced subscription with a unique ID
-    iced::Subscription::run_with_id("multi_gpu", stream)
+    })
 }
\ No newline at end of file
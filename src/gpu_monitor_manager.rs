@@ -77,7 +77,7 @@ impl GpuMonitorManager {
     pub async fn initialize_amd_monitor(&mut self, has_amd_discrete: bool) -> Result<()> {
         // Only initialize if AMD discrete GPUs are detected
         if !has_amd_discrete {
-            println!("No AMD discrete GPUs detected - skipping GPUPerfAPI initialization");
+            eprintln!("No AMD discrete GPUs detected - skipping GPUPerfAPI initialization");
             return Ok(());
         }
 
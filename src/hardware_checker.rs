@@ -1,42 +1,43 @@
 //! Hardware monitoring module for Cutemonitor.
 //!
 //! This module handles collecting hardware data from the system, including CPU temperatures,
-//! voltages, and memory usage. It uses LibreHardwareMonitor (via a C# executable)
-//! for detailed CPU and motherboard data.
+//! voltages, and memory usage. On Windows it uses LibreHardwareMonitor (via a C#
+//! executable) for detailed CPU and motherboard data; on Linux the native
+//! hwmon/powercap provider in `hwmon.rs` fills the same `HardwareData`.
 
+#[cfg(target_os = "linux")]
 use iced_futures::futures::future;
-use iced_futures::stream;
-use serde::Deserialize;
-use std::io::BufRead;
+use iced_futures::futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+#[cfg(target_os = "windows")]
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+#[cfg(target_os = "windows")]
+use crate::supervisor::{line_channel, SupervisorConfig};
+#[cfg(target_os = "linux")]
+use crate::collector;
+use crate::collector::Stamped;
+use crate::diagnostics::CollectorHealth;
+use crate::fans::FanReading;
+use crate::sensors::SensorTree;
+use crate::supervisor::ChildState;
 
 // Embedded binaries for LibreHardwareMonitor library and dependencies.
 // These are included at compile time and extracted at runtime.
+#[cfg(target_os = "windows")]
 static LIBRE_HARDWARE_MONITOR_LIB: &[u8] = include_bytes!("../LibreHardwareMonitorLib.dll");
+#[cfg(target_os = "windows")]
 static NEWTONSOFT_JSON: &[u8] = include_bytes!("../Newtonsoft.Json.dll");
-static TEMP_MONITOR_EXE: &[u8] = include_bytes!("../TempMonitor.exe");
-
-/// RAII guard to ensure TempMonitor.exe and related dotnet processes are terminated
-/// when the subscription ends or the program exits.
-struct ProcessGuard;
-
-impl Drop for ProcessGuard {
-    fn drop(&mut self) {
-        // Forcefully terminate any remaining TempMonitor.exe and dotnet.exe processes
-        // to prevent them from running indefinitely.
-        let _ = std::process::Command::new("taskkill")
-            .args(["/f", "/t", "/im", "TempMonitor.exe"])
-            .output();
-        let _ = std::process::Command::new("taskkill")
-            .args(["/f", "/t", "/im", "dotnet.exe"])
-            .output();
-    }
-}
+// Built from TempMonitor.cs by build.rs (version 2), or the checked-in
+// TempMonitor.exe (version 1) when the .NET SDK isn't available.
+#[cfg(target_os = "windows")]
+static TEMP_MONITOR_EXE: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/TempMonitor/TempMonitor.exe"));
 
 /// Hardware data collected from LibreHardwareMonitor.
-/// This struct represents the JSON output from the TempMonitor.exe C# application,
-/// which uses LibreHardwareMonitor to gather system information.
-#[derive(Deserialize, Debug, Clone)]
+/// This struct is the version 1 JSON output of older TempMonitor.exe builds;
+/// with version 2 it is derived from the sensor tree (see `sensors.rs`).
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct HardwareData {
     /// The model name of the motherboard.
     #[serde(rename = "MotherboardModel")]
@@ -59,60 +60,131 @@ pub struct HardwareData {
     /// Memory usage as a percentage (0-100).
     #[serde(rename = "MemoryUsage")]
     pub memory_usage: f32,
+    /// Memory (DIMM) temperature in Celsius (if available).
+    #[serde(rename = "MemoryTemp", default)]
+    pub memory_temp: Option<f32>,
     /// Total system memory in megabytes.
     #[serde(rename = "TotalMemoryMB")]
     pub total_memory_mb: i32,
     /// Memory speed in MT/s (MegaTransfers per second).
     #[serde(rename = "MemorySpeedMTS")]
     pub memory_speed_mts: i32,
+    /// Every fan and pump header (not part of protocol version 1).
+    #[serde(rename = "Fans", default)]
+    pub fans: Vec<FanReading>,
+}
+
+/// How often TempMonitor.exe prints a line; it takes no arguments.
+const HELPER_PERIOD: Duration = Duration::from_millis(500);
+
+/// Keeps one parsed helper line per `intervals_ms.hardware` and drops the rest,
+/// so the setting applies without sleeping in the line callback (the supervisor
+/// would stop reading the helper's output meanwhile).
+#[derive(Debug, Default)]
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+struct LinePacer {
+    last_kept: Option<Instant>,
+}
+
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+impl LinePacer {
+    /// True if a line arriving at `now` should be used. Lines come in steps of
+    /// `HELPER_PERIOD`, so one arriving up to half a step early still counts.
+    fn keep(&mut self, now: Instant, interval: Duration) -> bool {
+        let due = self.last_kept.is_none_or(|last| {
+            now.saturating_duration_since(last) + HELPER_PERIOD / 2 >= interval
+        });
+        if due {
+            self.last_kept = Some(now);
+        }
+        due
+    }
+}
+
+/// Output of the hardware collector: readings, plus the full sensor tree and
+/// the state of the TempMonitor.exe helper on Windows.
+#[derive(Debug, Clone)]
+pub enum HardwareEvent {
+    Data(HardwareData),
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    Sensors(SensorTree),
+    /// A line was rejected; carries the updated counters (see `diagnostics.rs`).
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    Health(CollectorHealth),
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    Monitor(ChildState),
 }
 
 /// Creates an iced subscription that streams hardware data from LibreHardwareMonitor.
-/// This function spawns a background thread that runs TempMonitor.exe, reads its JSON output,
-/// and sends parsed HardwareData to the iced application every 500ms.
-pub fn hardware_data_stream() -> iced::Subscription<HardwareData> {
-    let stream = stream::channel(100000, |mut sender| async move {
-        std::thread::spawn(move || {
-            // Extract embedded binaries to a temporary directory.
-            let temp_dir = extract_resources();
-            let exe_path = temp_dir.join("TempMonitor.exe");
-            // Spawn the C# executable with piped stdout for reading output.
-            #[allow(clippy::zombie_processes)]
-            let mut cmd = std::process::Command::new(&exe_path)
-                .stdout(std::process::Stdio::piped())
-                .spawn()
-                .expect("Failed to spawn TempMonitor.exe. Ensure the C# project is built.");
-            let stdout = cmd.stdout.take().unwrap();
-            // ProcessGuard ensures processes are killed when this scope ends.
-            let _guard = ProcessGuard;
-            let mut reader = std::io::BufReader::new(stdout);
-            loop {
-                let mut line = String::new();
-                match reader.read_line(&mut line) {
-                    Ok(0) => break, // EOF reached
-                    Ok(_) => {
-                        let line = line.trim();
-                        // Parse JSON line into HardwareData and send it.
-                        if let Ok(data) = serde_json::from_str::<HardwareData>(line) {
-                            let _ = sender.try_send(data);
-                            // Throttle updates to every 500ms.
-                            std::thread::sleep(std::time::Duration::from_millis(500));
-                        }
+/// This function spawns a background thread that supervises TempMonitor.exe, reads its JSON output,
+/// and sends the latest parsed HardwareData to the iced application (every `intervals_ms.hardware`,
+/// but no faster than the helper's own 500ms).
+pub fn hardware_data_stream() -> iced::Subscription<HardwareEvent> {
+    iced::Subscription::run_with_id(
+        "hardware",
+        hardware_data_channel().map(|stamped| stamped.value),
+    )
+}
+
+/// The raw stream behind `hardware_data_stream()`, with the stamp of every event.
+/// `headless::run` consumes it directly, without an iced application.
+/// Events of the same kind replace each other until read (see `collector.rs`).
+#[cfg(target_os = "windows")]
+pub fn hardware_data_channel() -> impl Stream<Item = Stamped<HardwareEvent>> {
+    // The supervisor restarts TempMonitor.exe if it crashes or goes quiet,
+    // and kills only its own process tree when the stream is dropped.
+    let mut pacer = LinePacer::default();
+    line_channel(
+        std::mem::discriminant,
+        // Extract embedded binaries to a temporary directory.
+        || SupervisorConfig::new(extract_resources().join("TempMonitor.exe")),
+        move |line, emit| {
+            // Parse and count every JSON line (either protocol version), so the
+            // diagnostics see all of them; only the readings are paced.
+            match crate::sensors::parse_line(line.trim()) {
+                Ok((data, tree)) => {
+                    crate::diagnostics::record_parsed();
+                    let interval = crate::settings::current().intervals_ms.hardware;
+                    if !pacer.keep(Instant::now(), Duration::from_millis(interval)) {
+                        return;
+                    }
+                    if let Some(tree) = tree {
+                        emit(HardwareEvent::Sensors(tree));
                     }
-                    Err(_) => break, // Error reading line
+                    emit(HardwareEvent::Data(data));
+                }
+                Err(error) => {
+                    let health = crate::diagnostics::record_error(line, &error);
+                    emit(HardwareEvent::Health(health));
                 }
             }
+        },
+        HardwareEvent::Monitor,
+    )
+}
+
+/// Streams readings from the native sysfs provider on Linux.
+#[cfg(target_os = "linux")]
+pub fn hardware_data_channel() -> impl Stream<Item = Stamped<HardwareEvent>> {
+    collector::latest(std::mem::discriminant, |publisher| async move {
+        std::thread::spawn(move || {
+            let mut provider = crate::hwmon::HwmonProvider::new();
+            while !publisher.is_closed() {
+                publisher.publish(HardwareEvent::Data(provider.sample()));
+                let interval = crate::settings::current().intervals_ms.hardware;
+                std::thread::sleep(std::time::Duration::from_millis(interval));
+            }
         });
         // Keep the async task alive indefinitely.
         future::pending::<()>().await
-    });
-    iced::Subscription::run_with_id("hardware", stream)
+    })
 }
 
 /// Extracts embedded binaries to a temporary directory.
 /// This function writes the LibreHardwareMonitor DLL, Newtonsoft.Json DLL, and TempMonitor.exe
 /// to the system's temp directory so they can be executed.
 /// Returns the path to the temporary directory.
+#[cfg(target_os = "windows")]
 fn extract_resources() -> PathBuf {
     let temp_dir = std::env::temp_dir().join("libre_hardware_temp");
     std::fs::create_dir_all(&temp_dir).unwrap();
@@ -128,4 +200,25 @@ fn extract_resources() -> PathBuf {
     temp_dir
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
 
+    #[test]
+    fn test_line_pacer() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let interval = Duration::from_millis(2000);
+        let mut pacer = LinePacer::default();
+        let kept: Vec<u64> = (0..10)
+            .map(|tick| tick * 500 + tick % 3)
+            .filter(|&ms| pacer.keep(at(ms), interval))
+            .collect();
+        // Every fourth line, even with the helper drifting a little
+        assert_eq!(kept, vec![0, 2001, 4002]);
+
+        // Intervals below the helper's period keep every line
+        let mut pacer = LinePacer::default();
+        assert!((0..4).all(|tick| pacer.keep(at(tick * 500), Duration::from_millis(200))));
+    }
+}
@@ -0,0 +1,265 @@
+//! Headless mode for CuteMonitor.
+//!
+//! Starts the same collectors as the iced application (hardware data, GPUs,
+//! CPU thread usage and network bandwidth) without opening a window, and
+//! prints one JSON document per interval to stdout so the data can be scripted.
+//! Does not require administrator privileges; collectors that need them
+//! simply report less data.
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use iced::futures::{Stream, StreamExt};
+use serde::Serialize;
+
+use crate::alerts::{self, AlertEngine, Sample};
+use crate::cli::CliOptions;
+use crate::collector::{Stamp, Stamped};
+use crate::cpu_times::{CpuTimesSample, CpuTimesSampler};
+use crate::external_sensors::{self, ExternalEvent, ExternalReading, ExternalSensors};
+use crate::gpu_data::GpuData;
+use crate::hardware_checker::{self, HardwareData, HardwareEvent};
+use crate::history::{self, Record};
+use crate::interface_stats::{self, NetworkStats};
+use crate::metrics_exporter;
+use crate::sensors::{Sensor, SensorTree};
+use crate::what_cpu_check;
+
+/// One JSON document printed per interval.
+#[derive(Serialize)]
+struct Snapshot<'a> {
+    /// Milliseconds since the Unix epoch when the snapshot was taken.
+    timestamp_ms: u64,
+    /// Latest reading from TempMonitor.exe (None until the first line arrives).
+    hardware: Option<&'a HardwareData>,
+    /// Every sensor TempMonitor.exe reports (empty with protocol version 1 and on Linux).
+    sensors: &'a [Sensor],
+    /// Latest metrics for every detected GPU.
+    gpus: &'a [GpuData],
+    /// Current usage percentage of every logical CPU.
+    thread_usages: &'a [f32],
+    /// Current clock of every logical CPU in MHz (0 where unknown).
+    thread_frequencies_mhz: &'a [u64],
+    /// Share of user, system, iowait, irq and steal time since the previous snapshot.
+    cpu_times: Option<&'a CpuTimesSample>,
+    /// Per-interface network rates since the previous snapshot.
+    network: Option<&'a NetworkStats>,
+    /// Latest sample of every external sensor command.
+    external: &'a [ExternalReading],
+    /// When the hardware and GPU readings above were published.
+    freshness: Freshness,
+}
+
+/// Stamps of the readings in a snapshot, so consumers can tell stale data
+/// (the same `seq` twice) from fresh data.
+#[derive(Serialize)]
+struct Freshness {
+    hardware: Option<Stamp>,
+    gpus: Option<Stamp>,
+}
+
+/// Runs headless mode until stdout is closed or `--count` snapshots were printed.
+pub fn run(options: &CliOptions) -> anyhow::Result<()> {
+    // GPU monitoring needs the embedded DLLs, but stdout is reserved for JSON
+    if let Err(e) = crate::extract_embedded_dlls() {
+        eprintln!("Failed to extract embedded DLLs: {}", e);
+    }
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    let result = runtime.block_on(print_snapshots(options));
+    // The collector threads block on I/O, don't wait for them
+    runtime.shutdown_background();
+    result
+}
+
+/// Collects and prints snapshots on a fixed interval.
+async fn print_snapshots(options: &CliOptions) -> anyhow::Result<()> {
+    // Helper restarts go to stderr, readings and the sensor tree into the snapshot
+    let sensors: Arc<Mutex<Option<SensorTree>>> = Arc::new(Mutex::new(None));
+    let sensors_slot = Arc::clone(&sensors);
+    let hardware = keep_latest(hardware_checker::hardware_data_channel().filter_map(move |event| {
+        let sensors_slot = Arc::clone(&sensors_slot);
+        async move {
+            match event.value {
+                HardwareEvent::Data(data) => Some(Stamped {
+                    stamp: event.stamp,
+                    value: data,
+                }),
+                HardwareEvent::Sensors(tree) => {
+                    *sensors_slot.lock().unwrap() = Some(tree);
+                    None
+                }
+                HardwareEvent::Monitor(state) => {
+                    eprintln!("Sensor helper: {}", state);
+                    None
+                }
+                HardwareEvent::Health(health) => {
+                    if let Some(bad) = &health.last_bad_line {
+                        eprintln!("Rejected hardware data line: {}", bad.error);
+                    }
+                    None
+                }
+            }
+        }
+    }));
+    let gpus = keep_latest(crate::gpu_hardware_checker::multi_gpu_data_channel());
+    let external = collect_external();
+    if let Some(address) = metrics_exporter::listen_address() {
+        tokio::spawn(metrics_exporter::serve(address));
+    }
+
+    // Take the baseline reading now so the first snapshot already has rates
+    let (mut sampler, _) = interface_stats::sample_network_stats_async(interface_stats::NetworkSampler::new()).await;
+    let mut cpu_times_sampler = CpuTimesSampler::new();
+    cpu_times_sampler.sample();
+
+    let mut alert_engine = AlertEngine::from_config();
+    let mut interval = tokio::time::interval(Duration::from_millis(options.interval_ms));
+    let mut printed = 0;
+
+    loop {
+        interval.tick().await;
+
+        let thread_usages = what_cpu_check::get_thread_usages().await;
+        let thread_frequencies = what_cpu_check::get_thread_frequencies().await;
+        let cpu_times = cpu_times_sampler.sample();
+        let (next_sampler, network) = interface_stats::sample_network_stats_async(sampler).await;
+        sampler = next_sampler;
+
+        let (hardware_stamp, hardware) = unstamp(hardware.lock().unwrap().clone());
+        let sensors = sensors.lock().unwrap().clone().unwrap_or_default();
+        let (gpus_stamp, gpus) = unstamp(gpus.lock().unwrap().clone());
+        let gpus = gpus.unwrap_or_default();
+
+        if let Some(hardware) = &hardware {
+            metrics_exporter::record_hardware(hardware);
+            history::record(|| Record::Hardware(hardware.clone()));
+        }
+        if !sensors.sensors.is_empty() {
+            metrics_exporter::record_sensors(&sensors);
+            history::record(|| Record::Sensors(sensors.clone()));
+        }
+        metrics_exporter::record_gpus(&gpus);
+        history::record(|| Record::Gpus(gpus.clone()));
+        metrics_exporter::record_thread_usages(&thread_usages);
+        history::record(|| Record::Threads(thread_usages.clone()));
+        metrics_exporter::record_frequencies(&thread_frequencies);
+        history::record(|| Record::Frequencies(thread_frequencies.clone()));
+        if let Some(cpu_times) = &cpu_times {
+            metrics_exporter::record_cpu_times(cpu_times);
+            history::record(|| Record::CpuTimes(cpu_times.clone()));
+        }
+        if let Some(network) = &network {
+            metrics_exporter::record_network(network);
+            history::record(|| Record::Network(network.clone()));
+        }
+        let external = external.lock().unwrap().readings().to_vec();
+        metrics_exporter::record_external(&external);
+        for reading in &external {
+            history::record(|| Record::External(reading.clone()));
+        }
+
+        let snapshot = Snapshot {
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_millis() as u64),
+            hardware: hardware.as_ref(),
+            sensors: &sensors.sensors,
+            gpus: &gpus,
+            thread_usages: &thread_usages,
+            thread_frequencies_mhz: &thread_frequencies,
+            cpu_times: cpu_times.as_ref(),
+            network: network.as_ref(),
+            external: &external,
+            freshness: Freshness {
+                hardware: hardware_stamp,
+                gpus: gpus_stamp,
+            },
+        };
+        check_alerts(&mut alert_engine, &snapshot, &sensors);
+        let line = serde_json::to_string(&snapshot)?;
+
+        let mut stdout = std::io::stdout().lock();
+        if writeln!(stdout, "{}", line).and_then(|_| stdout.flush()).is_err() {
+            // Whoever was reading stdout went away (e.g. `| head`)
+            break;
+        }
+
+        printed += 1;
+        if options.count.is_some_and(|count| printed >= count) {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Evaluates the alert rules against a snapshot; alerts go to stderr and the hooks.
+fn check_alerts(engine: &mut AlertEngine, snapshot: &Snapshot, sensors: &SensorTree) {
+    let now = std::time::Instant::now();
+    let mut events = Vec::new();
+    if let Some(hardware) = snapshot.hardware {
+        events.extend(engine.observe(&Sample::Hardware(hardware), now));
+    }
+    events.extend(engine.observe(&Sample::Threads(snapshot.thread_usages), now));
+    events.extend(engine.observe(&Sample::Gpus(snapshot.gpus), now));
+    if let Some(network) = snapshot.network {
+        events.extend(engine.observe(&Sample::Network(network), now));
+    }
+    events.extend(engine.observe(&Sample::Sensors(sensors), now));
+
+    for event in &events {
+        eprintln!("Alert: {}", event.describe());
+    }
+    alerts::dispatch(&events);
+}
+
+/// Runs every command from `external_sensors` and keeps the latest sample of each sensor.
+fn collect_external() -> Arc<Mutex<ExternalSensors>> {
+    let sensors = Arc::new(Mutex::new(ExternalSensors::default()));
+    for command in crate::settings::current().external_sensors {
+        let sensors = Arc::clone(&sensors);
+        tokio::spawn(async move {
+            let mut events = std::pin::pin!(external_sensors::channel(command));
+            while let Some(event) = events.next().await {
+                match event {
+                    ExternalEvent::Reading(reading) => sensors.lock().unwrap().update(reading),
+                    ExternalEvent::State { source, state } => {
+                        eprintln!("External sensor {}: {}", source, state);
+                        sensors.lock().unwrap().set_state(&source, state);
+                    }
+                    ExternalEvent::Rejected { source, error } => {
+                        eprintln!("External sensor {} printed an invalid sample: {}", source, error)
+                    }
+                }
+            }
+        });
+    }
+    sensors
+}
+
+/// Splits an optional stamped value into its stamp and value.
+fn unstamp<T>(stamped: Option<Stamped<T>>) -> (Option<Stamp>, Option<T>) {
+    match stamped {
+        Some(stamped) => (Some(stamped.stamp), Some(stamped.value)),
+        None => (None, None),
+    }
+}
+
+/// Drives a collector stream on the runtime and keeps only its latest value.
+fn keep_latest<T, S>(stream: S) -> Arc<Mutex<Option<T>>>
+where
+    T: Send + 'static,
+    S: Stream<Item = T> + Send + 'static,
+{
+    let latest = Arc::new(Mutex::new(None));
+    let slot = Arc::clone(&latest);
+    tokio::spawn(async move {
+        let mut stream = std::pin::pin!(stream);
+        while let Some(value) = stream.next().await {
+            *slot.lock().unwrap() = Some(value);
+        }
+    });
+    latest
+}
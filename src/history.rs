@@ -0,0 +1,259 @@
+//! History recording and replay.
+//!
+//! With `--record <file>` every input to `State::update` (hardware data, CPU
+//! core and thread usage, clocks and time breakdown, GPU data and network statistics) is appended to a
+//! log with one JSON object per line, stamped with the Unix time in
+//! milliseconds:
+//!
+//! ```text
+//! {"t":1700000000000,"threads":[12.5,3.0]}
+//! {"t":1700000000200,"network":{"interfaces":[...],"dropped_interfaces":[]}}
+//! ```
+//!
+//! `--replay <file>` feeds such a log back through the same `Message`
+//! variants instead of starting the live collectors, at the recorded pace
+//! divided by `--speed`.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use iced::futures::stream as futures_stream;
+use iced::futures::{SinkExt, Stream, StreamExt};
+use iced_futures::stream;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use crate::bandwidth;
+use crate::cpu_times::CpuTimesSample;
+use crate::gpu_data::GpuData;
+use crate::hardware_checker::HardwareData;
+use crate::external_sensors::{ExternalEvent, ExternalReading};
+use crate::interface_stats::NetworkStats;
+use crate::sensors::SensorTree;
+use crate::state::Message;
+use crate::what_cpu_check::CpuInfo;
+
+/// One recorded input, named after the collector that produced it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Record {
+    /// CPU model and topology of the recording machine, written once at start.
+    Cpu(CpuInfo),
+    Hardware(HardwareData),
+    Sensors(SensorTree),
+    Cores(Vec<f32>),
+    Threads(Vec<f32>),
+    /// Clock of every logical CPU in MHz.
+    Frequencies(Vec<u64>),
+    /// User/system/iowait/irq/steal shares, in total and per logical CPU.
+    CpuTimes(CpuTimesSample),
+    Gpus(Vec<GpuData>),
+    Network(NetworkStats),
+    External(ExternalReading),
+}
+
+impl Record {
+    /// Converts the record into the message the live collector would have sent.
+    pub fn into_message(self) -> Message {
+        match self {
+            Record::Cpu(info) => Message::UpdateCpuInfo(info),
+            Record::Hardware(data) => Message::UpdateData(data),
+            Record::Sensors(tree) => Message::UpdateSensors(tree),
+            Record::Cores(usages) => Message::UpdateCores(usages),
+            Record::Threads(usages) => Message::UpdateThreads(usages),
+            Record::Frequencies(frequencies) => Message::UpdateFrequencies(frequencies, None),
+            Record::CpuTimes(sample) => Message::UpdateCpuTimes(sample),
+            Record::Gpus(gpus) => Message::UpdateMultipleGpus(gpus),
+            Record::Network(stats) => {
+                Message::BandwidthMessage(bandwidth::Message::StatsUpdated(Some(stats)))
+            }
+            Record::External(reading) => Message::ExternalSensor(ExternalEvent::Reading(reading)),
+        }
+    }
+}
+
+/// A line of the history log.
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    /// Milliseconds since the Unix epoch when the record was taken.
+    t: u64,
+    #[serde(flatten)]
+    record: Record,
+}
+
+/// Replay settings from the command line.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub path: PathBuf,
+    /// Playback speed relative to the recording (2.0 = twice as fast).
+    pub speed: f64,
+}
+
+static RECORDER: OnceLock<Mutex<BufWriter<File>>> = OnceLock::new();
+static REPLAY: OnceLock<Replay> = OnceLock::new();
+
+/// Starts appending every recorded input to `path`.
+pub fn start_recording(path: &Path) -> std::io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let _ = RECORDER.set(Mutex::new(BufWriter::new(file)));
+    Ok(())
+}
+
+/// Appends a record to the log. `make` is only called while recording,
+/// so callers don't pay for cloning their data otherwise.
+pub fn record(make: impl FnOnce() -> Record) {
+    let Some(recorder) = RECORDER.get() else {
+        return;
+    };
+
+    let entry = Entry {
+        t: unix_millis(),
+        record: make(),
+    };
+    let mut writer = recorder.lock().unwrap();
+    let written = serde_json::to_writer(&mut *writer, &entry)
+        .map_err(std::io::Error::from)
+        .and_then(|_| writeln!(writer))
+        // Flush every line so an overnight run survives being killed
+        .and_then(|_| writer.flush());
+    if let Err(e) = written {
+        eprintln!("Failed to write history record: {}", e);
+    }
+}
+
+/// Milliseconds since the Unix epoch.
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+/// Switches the application to replay mode.
+pub fn enable_replay(replay: Replay) {
+    let _ = REPLAY.set(replay);
+}
+
+/// Returns the replay settings if `--replay` was given.
+pub fn replay() -> Option<&'static Replay> {
+    REPLAY.get()
+}
+
+/// Creates an iced subscription that plays back the recording.
+pub fn replay_subscription(replay: &Replay) -> iced::Subscription<Message> {
+    // Stay pending at the end so the last values remain on screen
+    let stream = replay_stream(replay.path.clone(), replay.speed).chain(futures_stream::pending());
+    iced::Subscription::run_with_id("history_replay", stream)
+}
+
+/// Reads a recording and yields its messages, sleeping between them to
+/// reproduce the recorded timing divided by `speed`.
+pub fn replay_stream(path: PathBuf, speed: f64) -> impl Stream<Item = Message> {
+    stream::channel(100, move |mut sender| async move {
+        let file = match tokio::fs::File::open(&path).await {
+            Ok(file) => file,
+            Err(e) => {
+                eprintln!("Failed to open recording {}: {}", path.display(), e);
+                return;
+            }
+        };
+
+        let mut lines = BufReader::new(file).lines();
+        let mut previous_t = None;
+        let mut skipped = 0;
+
+        while let Ok(Some(line)) = lines.next_line().await {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: Entry = match serde_json::from_str(&line) {
+                Ok(entry) => entry,
+                Err(_) => {
+                    skipped += 1;
+                    continue;
+                }
+            };
+
+            if let Some(previous_t) = previous_t {
+                let delay = replay_delay(previous_t, entry.t, speed);
+                if !delay.is_zero() {
+                    tokio::time::sleep(delay).await;
+                }
+            }
+            previous_t = Some(entry.t);
+
+            if sender.send(entry.record.into_message()).await.is_err() {
+                return;
+            }
+        }
+
+        if skipped > 0 {
+            eprintln!("Skipped {} unreadable lines in {}", skipped, path.display());
+        }
+    })
+}
+
+/// Time to wait between two records taken at `previous` and `current` ms.
+/// Clock jumps backwards (e.g. two appended recordings) replay immediately.
+fn replay_delay(previous: u64, current: u64, speed: f64) -> Duration {
+    let elapsed = current.saturating_sub(previous) as f64;
+    Duration::from_secs_f64(elapsed / speed / 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_round_trip() {
+        let entry = Entry {
+            t: 1_700_000_000_000,
+            record: Record::Threads(vec![12.5, 3.0]),
+        };
+        let line = serde_json::to_string(&entry).unwrap();
+        assert_eq!(line, r#"{"t":1700000000000,"threads":[12.5,3.0]}"#);
+
+        let parsed: Entry = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed.t, entry.t);
+        assert!(matches!(parsed.record, Record::Threads(usages) if usages == vec![12.5, 3.0]));
+    }
+
+    #[test]
+    fn test_replay_delay() {
+        assert_eq!(replay_delay(1000, 1500, 1.0), Duration::from_millis(500));
+        assert_eq!(replay_delay(1000, 1500, 10.0), Duration::from_millis(50));
+        assert_eq!(replay_delay(2000, 1000, 1.0), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_replay_stream() {
+        let path =
+            std::env::temp_dir().join(format!("cutemonitor_history_{}.log", std::process::id()));
+        std::fs::write(
+            &path,
+            concat!(
+                "{\"t\":1000,\"cores\":[50.0]}\n",
+                "not json\n",
+                "{\"t\":1100,\"network\":{\"interfaces\":[],\"dropped_interfaces\":[]}}\n",
+                "{\"t\":1200,\"gpus\":[]}\n",
+            ),
+        )
+        .unwrap();
+
+        let started = std::time::Instant::now();
+        let messages: Vec<Message> = replay_stream(path.clone(), 100.0).collect().await;
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(messages.len(), 3);
+        assert!(matches!(&messages[0], Message::UpdateCores(usages) if usages == &vec![50.0]));
+        assert!(matches!(
+            &messages[1],
+            Message::BandwidthMessage(bandwidth::Message::StatsUpdated(Some(_)))
+        ));
+        assert!(matches!(&messages[2], Message::UpdateMultipleGpus(gpus) if gpus.is_empty()));
+        // 200 ms of recording at 100x speed
+        assert!(started.elapsed() < Duration::from_millis(500));
+    }
+}
@@ -0,0 +1,608 @@
+//! Native Linux hardware provider.
+//!
+//! Fills `HardwareData` from sysfs and procfs instead of TempMonitor.exe:
+//! - CPU temperature from the `k10temp`/`zenpower` (Tdie, else Tctl) or
+//!   `coretemp` (Package id) hwmon drivers, per-CCD `Tccd*` into `ccd_temperatures`
+//! - Core voltage where the driver labels it (`SVI2_Core`, `Vcore`)
+//! - Chipset temperature from a `pch_*` hwmon device or a sensor labelled PCH/chipset
+//! - Memory temperature from the `jc42`/`spd5118` DIMM sensors, if loaded
+//! - Fan speeds from every `fan*_input`, with the PWM duty from `pwm*` where present
+//! - Package power from the RAPL energy counters in `/sys/class/powercap`
+//! - Memory usage from `/proc/meminfo`, board name from `/sys/class/dmi/id`
+//!
+//! All paths are resolved below a configurable root so tests can point the
+//! provider at a fake directory tree.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::fans::FanReading;
+use crate::hardware_checker::HardwareData;
+
+/// hwmon drivers of the temperature sensors on DDR4 (`jc42`) and DDR5 (`spd5118`) DIMMs.
+const DIMM_DRIVERS: &[&str] = &["jc42", "spd5118"];
+
+/// hwmon drivers that report AMD Zen temperatures with Tctl/Tdie/Tccd labels.
+const AMD_DRIVERS: &[&str] = &["k10temp", "zenpower"];
+
+/// Reads hardware data from sysfs below `root`.
+pub struct HwmonProvider {
+    root: PathBuf,
+    /// Previous RAPL reading (time, package zones) to derive power.
+    previous_energy: Option<(Instant, Vec<RaplZone>)>,
+}
+
+impl HwmonProvider {
+    /// Creates a provider for the running system.
+    pub fn new() -> Self {
+        Self::with_root("/")
+    }
+
+    /// Creates a provider that resolves `/sys` and `/proc` below `root`.
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            previous_energy: None,
+        }
+    }
+
+    /// Takes one reading. Package power is None on the first call, as it
+    /// needs two energy readings.
+    pub fn sample(&mut self) -> HardwareData {
+        self.sample_at(Instant::now())
+    }
+
+    fn sample_at(&mut self, now: Instant) -> HardwareData {
+        let chips = read_hwmon_chips(&self.root.join("sys/class/hwmon"));
+        let (memory_usage, total_memory_mb) =
+            read_meminfo(&self.root.join("proc/meminfo")).unwrap_or((0.0, 0));
+
+        HardwareData {
+            motherboard_model: read_board_name(&self.root.join("sys/class/dmi/id")),
+            cpu_temp: cpu_temperature(&chips).unwrap_or(0.0),
+            ccd_temperatures: ccd_temperatures(&chips),
+            cpu_voltage: cpu_voltage(&chips),
+            cpu_power: self.package_power(now),
+            chipset_temp: chipset_temperature(&chips),
+            memory_usage,
+            memory_temp: memory_temperature(&chips),
+            total_memory_mb,
+            // Not exposed by sysfs without root-only DMI tables
+            memory_speed_mts: 0,
+            fans: chips.iter().flat_map(|chip| chip.fans.iter().cloned()).collect(),
+        }
+    }
+
+    /// Average package power since the previous call, from RAPL energy counters.
+    fn package_power(&mut self, now: Instant) -> Option<f32> {
+        let zones = read_rapl_packages(&self.root.join("sys/class/powercap"));
+        if zones.is_empty() {
+            return None;
+        }
+
+        let delta = self
+            .previous_energy
+            .as_ref()
+            .and_then(|(_, before)| energy_delta(before, &zones));
+        let (then, _) = self.previous_energy.replace((now, zones))?;
+        let seconds = now.duration_since(then).as_secs_f64();
+        if seconds <= 0.0 {
+            return None;
+        }
+        Some((delta? as f64 / 1_000_000.0 / seconds) as f32)
+    }
+}
+
+impl Default for HwmonProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// One `/sys/class/hwmon/hwmonN` device.
+#[derive(Debug, Default)]
+struct HwmonChip {
+    name: String,
+    /// Unique among the chips, unlike the driver name (see `chip_id`).
+    id: String,
+    /// (label, °C) for every temp*_input; unlabelled inputs are named `tempN`.
+    temperatures: Vec<(String, f32)>,
+    /// (label, V) for every labelled in*_input.
+    voltages: Vec<(String, f32)>,
+    /// Every fan*_input, with the duty of the matching pwm* output.
+    fans: Vec<FanReading>,
+}
+
+/// Reads every hwmon device, sorted by directory name for a stable order.
+fn read_hwmon_chips(dir: &Path) -> Vec<HwmonChip> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    paths.sort();
+
+    paths.iter().map(|path| read_hwmon_chip(path)).collect()
+}
+
+fn read_hwmon_chip(dir: &Path) -> HwmonChip {
+    let name = read_trimmed(&dir.join("name")).unwrap_or_default();
+    let mut chip = HwmonChip {
+        id: chip_id(dir, &name),
+        name,
+        ..Default::default()
+    };
+
+    let Ok(entries) = fs::read_dir(dir) else {
+        return chip;
+    };
+    let mut inputs: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|file| file.ends_with("_input"))
+        .collect();
+    // temp10 after temp9
+    inputs.sort_by_key(|file| {
+        (
+            file.chars()
+                .take_while(|c| !c.is_ascii_digit())
+                .collect::<String>(),
+            sensor_index(file),
+        )
+    });
+
+    for input in inputs {
+        let sensor = input.trim_end_matches("_input");
+        let Some(raw) = read_trimmed(&dir.join(&input)).and_then(|value| value.parse::<f64>().ok())
+        else {
+            continue;
+        };
+        let label = read_trimmed(&dir.join(format!("{}_label", sensor)));
+
+        if sensor.starts_with("temp") {
+            // Millidegrees Celsius
+            let label = label.unwrap_or_else(|| sensor.to_string());
+            chip.temperatures.push((label, (raw / 1000.0) as f32));
+        } else if sensor.starts_with("in") {
+            // Millivolts; unlabelled voltages can't be identified
+            if let Some(label) = label {
+                chip.voltages.push((label, (raw / 1000.0) as f32));
+            }
+        } else if sensor.starts_with("fan") {
+            // RPM; pwmN (0-255) drives fanN on the common Super I/O chips
+            let index = sensor_index(sensor);
+            let duty_percent = read_trimmed(&dir.join(format!("pwm{}", index)))
+                .and_then(|value| value.parse::<f32>().ok())
+                .map(|pwm| pwm / 255.0 * 100.0);
+            chip.fans.push(FanReading {
+                id: format!("{}/{}", chip.id, sensor),
+                name: label.unwrap_or_else(|| format!("{} fan {}", chip.name, index)),
+                rpm: Some(raw as f32),
+                duty_percent,
+            });
+        }
+    }
+
+    chip
+}
+
+/// Driver name plus the device the chip belongs to, e.g. `amdgpu-0000:03:00.0`,
+/// so two cards with the same driver get different ids. The device stays the
+/// same across reboots while hwmonN numbers may not; chips without a `device`
+/// link fall back to their hwmon directory.
+fn chip_id(dir: &Path, name: &str) -> String {
+    let location = fs::read_link(dir.join("device"))
+        .ok()
+        .as_deref()
+        .or(Some(dir))
+        .and_then(Path::file_name)
+        .map(|location| location.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    format!("{}-{}", name, location)
+}
+
+/// Number in a sysfs sensor file name such as `temp3_input`.
+fn sensor_index(file: &str) -> u32 {
+    file.chars()
+        .skip_while(|c| !c.is_ascii_digit())
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<String>()
+        .parse()
+        .unwrap_or(0)
+}
+
+fn find_temperature(chip: &HwmonChip, label: &str) -> Option<f32> {
+    chip.temperatures
+        .iter()
+        .find(|(name, _)| name == label)
+        .map(|(_, value)| *value)
+}
+
+/// CPU temperature: Tdie (else Tctl) on AMD, Package id 0 on Intel.
+fn cpu_temperature(chips: &[HwmonChip]) -> Option<f32> {
+    chips.iter().find_map(|chip| {
+        if AMD_DRIVERS.contains(&chip.name.as_str()) {
+            find_temperature(chip, "Tdie").or_else(|| find_temperature(chip, "Tctl"))
+        } else if chip.name == "coretemp" {
+            chip.temperatures
+                .iter()
+                .find(|(label, _)| label.starts_with("Package id"))
+                .map(|(_, value)| *value)
+        } else {
+            None
+        }
+    })
+}
+
+/// Per-CCD temperatures from the `Tccd1`..`TccdN` sensors, in CCD order.
+fn ccd_temperatures(chips: &[HwmonChip]) -> Vec<Option<f32>> {
+    let Some(chip) = chips
+        .iter()
+        .find(|chip| AMD_DRIVERS.contains(&chip.name.as_str()))
+    else {
+        return Vec::new();
+    };
+
+    let mut ccds: Vec<(u32, f32)> = chip
+        .temperatures
+        .iter()
+        .filter_map(|(label, value)| Some((label.strip_prefix("Tccd")?.parse().ok()?, *value)))
+        .collect();
+    ccds.sort_by_key(|(index, _)| *index);
+
+    // Keep gaps (e.g. Tccd1 and Tccd3 only) as None so indices match the CCD numbers
+    let count = ccds.last().map_or(0, |(index, _)| *index as usize);
+    let mut temperatures = vec![None; count];
+    for (index, value) in ccds {
+        if index >= 1 {
+            temperatures[index as usize - 1] = Some(value);
+        }
+    }
+    temperatures
+}
+
+/// Core voltage from drivers that label it (zenpower `SVI2_Core`, Super I/O `Vcore`).
+fn cpu_voltage(chips: &[HwmonChip]) -> Option<f32> {
+    chips.iter().find_map(|chip| {
+        chip.voltages
+            .iter()
+            .find(|(label, _)| label == "SVI2_Core" || label.eq_ignore_ascii_case("vcore"))
+            .map(|(_, value)| *value)
+    })
+}
+
+/// Chipset temperature from an Intel PCH hwmon device or a sensor labelled PCH/chipset.
+fn chipset_temperature(chips: &[HwmonChip]) -> Option<f32> {
+    chips.iter().find_map(|chip| {
+        if chip.name.starts_with("pch_") {
+            return chip.temperatures.first().map(|(_, value)| *value);
+        }
+        chip.temperatures
+            .iter()
+            .find(|(label, _)| {
+                let label = label.to_ascii_lowercase();
+                label.contains("pch") || label.contains("chipset")
+            })
+            .map(|(_, value)| *value)
+    })
+}
+
+/// Hottest DIMM, since each module has its own sensor.
+fn memory_temperature(chips: &[HwmonChip]) -> Option<f32> {
+    chips
+        .iter()
+        .filter(|chip| DIMM_DRIVERS.contains(&chip.name.as_str()))
+        .filter_map(|chip| chip.temperatures.first().map(|(_, value)| *value))
+        .reduce(f32::max)
+}
+
+/// A RAPL package zone from `/sys/class/powercap`.
+struct RaplZone {
+    /// Directory name, e.g. `intel-rapl:1`.
+    name: String,
+    energy_uj: u64,
+    max_energy_range_uj: u64,
+}
+
+/// Reads the top-level package zones (`intel-rapl:N` named `package-N`).
+/// AMD CPUs expose their package counter under the same names.
+fn read_rapl_packages(dir: &Path) -> Vec<RaplZone> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            // Subzones (intel-rapl:0:0 = core, ...) are already included in the package
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.starts_with("intel-rapl:") && name.matches(':').count() == 1
+                })
+        })
+        .filter(|path| {
+            read_trimmed(&path.join("name")).is_some_and(|name| name.starts_with("package"))
+        })
+        .filter_map(|path| {
+            Some(RaplZone {
+                name: path.file_name()?.to_string_lossy().into_owned(),
+                energy_uj: read_trimmed(&path.join("energy_uj"))?.parse().ok()?,
+                max_energy_range_uj: read_trimmed(&path.join("max_energy_range_uj"))
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(0),
+            })
+        })
+        .collect()
+}
+
+/// Energy in µJ used by the package zones between two readings. Each counter
+/// wraps at its own max_energy_range_uj, so the zones are corrected one by one
+/// before they are added up.
+fn energy_delta(before: &[RaplZone], now: &[RaplZone]) -> Option<u64> {
+    now.iter()
+        .map(|zone| {
+            let previous = before.iter().find(|previous| previous.name == zone.name)?;
+            if zone.energy_uj >= previous.energy_uj {
+                Some(zone.energy_uj - previous.energy_uj)
+            } else {
+                Some(zone.max_energy_range_uj.checked_sub(previous.energy_uj)? + zone.energy_uj)
+            }
+        })
+        .sum()
+}
+
+/// Memory usage in percent and total memory in MB from `/proc/meminfo`.
+fn read_meminfo(path: &Path) -> Option<(f32, i32)> {
+    let contents = fs::read_to_string(path).ok()?;
+    let field = |name: &str| -> Option<u64> {
+        contents
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))?
+            .split_whitespace()
+            .next()?
+            .parse()
+            .ok()
+    };
+
+    let total_kb = field("MemTotal")?;
+    let available_kb = field("MemAvailable")?;
+    if total_kb == 0 {
+        return None;
+    }
+    let used_kb = total_kb.saturating_sub(available_kb);
+    Some((
+        used_kb as f32 / total_kb as f32 * 100.0,
+        (total_kb / 1024) as i32,
+    ))
+}
+
+/// Board vendor and name from DMI, or "Unknown" like TempMonitor.exe.
+fn read_board_name(dir: &Path) -> String {
+    let vendor = read_trimmed(&dir.join("board_vendor")).unwrap_or_default();
+    let name = read_trimmed(&dir.join("board_name")).unwrap_or_default();
+    let model = format!("{} {}", vendor, name).trim().to_string();
+    if model.is_empty() {
+        "Unknown".to_string()
+    } else {
+        model
+    }
+}
+
+/// Reads a sysfs attribute without its trailing newline.
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|value| value.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Builds a fake sysfs/procfs tree in a temporary directory.
+    struct FakeRoot(PathBuf);
+
+    impl FakeRoot {
+        fn new(name: &str) -> Self {
+            let root = std::env::temp_dir().join(format!(
+                "cutemonitor_hwmon_{}_{}",
+                name,
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&root);
+            Self(root)
+        }
+
+        fn write(&self, path: &str, contents: &str) {
+            let path = self.0.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, format!("{}\n", contents)).unwrap();
+        }
+    }
+
+    impl Drop for FakeRoot {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_amd_system() {
+        let root = FakeRoot::new("amd");
+        root.write("sys/class/hwmon/hwmon0/name", "nvme");
+        root.write("sys/class/hwmon/hwmon0/temp1_input", "38850");
+        root.write("sys/class/hwmon/hwmon1/name", "k10temp");
+        root.write("sys/class/hwmon/hwmon1/temp1_input", "65500");
+        root.write("sys/class/hwmon/hwmon1/temp1_label", "Tctl");
+        root.write("sys/class/hwmon/hwmon1/temp3_input", "51250");
+        root.write("sys/class/hwmon/hwmon1/temp3_label", "Tccd1");
+        root.write("sys/class/hwmon/hwmon1/temp5_input", "49000");
+        root.write("sys/class/hwmon/hwmon1/temp5_label", "Tccd3");
+        root.write("sys/class/hwmon/hwmon2/name", "nct6798");
+        root.write("sys/class/hwmon/hwmon2/in0_input", "1104");
+        root.write("sys/class/hwmon/hwmon2/in0_label", "Vcore");
+        root.write("sys/class/hwmon/hwmon2/temp2_input", "45000");
+        root.write("sys/class/hwmon/hwmon2/temp2_label", "PCH_CHIP_TEMP");
+        root.write("sys/class/hwmon/hwmon2/fan1_input", "1187");
+        root.write("sys/class/hwmon/hwmon2/pwm1", "102");
+        root.write("sys/class/hwmon/hwmon2/fan2_input", "2400");
+        root.write("sys/class/hwmon/hwmon2/fan2_label", "Pump");
+        root.write("sys/class/hwmon/hwmon3/name", "spd5118");
+        root.write("sys/class/hwmon/hwmon3/temp1_input", "41250");
+        root.write("sys/class/hwmon/hwmon4/name", "spd5118");
+        root.write("sys/class/hwmon/hwmon4/temp1_input", "43500");
+        root.write("sys/class/dmi/id/board_vendor", "ASUSTeK COMPUTER INC.");
+        root.write("sys/class/dmi/id/board_name", "ROG STRIX X670E-E");
+        root.write(
+            "proc/meminfo",
+            "MemTotal:       32768000 kB\nMemFree:         1000000 kB\nMemAvailable:   24576000 kB",
+        );
+
+        let data = HwmonProvider::with_root(&root.0).sample();
+
+        assert_eq!(
+            data.motherboard_model,
+            "ASUSTeK COMPUTER INC. ROG STRIX X670E-E"
+        );
+        assert_eq!(data.cpu_temp, 65.5);
+        assert_eq!(data.ccd_temperatures, vec![Some(51.25), None, Some(49.0)]);
+        assert_eq!(data.cpu_voltage, Some(1.104));
+        assert_eq!(data.chipset_temp, Some(45.0));
+        assert_eq!(data.memory_usage, 25.0);
+        assert_eq!(data.memory_temp, Some(43.5));
+        assert_eq!(data.total_memory_mb, 32000);
+        assert_eq!(data.cpu_power, None);
+        assert_eq!(
+            data.fans,
+            vec![
+                FanReading {
+                    id: "nct6798-hwmon2/fan1".to_string(),
+                    name: "nct6798 fan 1".to_string(),
+                    rpm: Some(1187.0),
+                    duty_percent: Some(40.0),
+                },
+                FanReading {
+                    id: "nct6798-hwmon2/fan2".to_string(),
+                    name: "Pump".to_string(),
+                    rpm: Some(2400.0),
+                    duty_percent: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_intel_system() {
+        let root = FakeRoot::new("intel");
+        root.write("sys/class/hwmon/hwmon3/name", "coretemp");
+        root.write("sys/class/hwmon/hwmon3/temp1_input", "72000");
+        root.write("sys/class/hwmon/hwmon3/temp1_label", "Package id 0");
+        root.write("sys/class/hwmon/hwmon3/temp2_input", "70000");
+        root.write("sys/class/hwmon/hwmon3/temp2_label", "Core 0");
+        root.write("sys/class/hwmon/hwmon4/name", "pch_cannonlake");
+        root.write("sys/class/hwmon/hwmon4/temp1_input", "52000");
+
+        let data = HwmonProvider::with_root(&root.0).sample();
+
+        assert_eq!(data.cpu_temp, 72.0);
+        assert!(data.ccd_temperatures.is_empty());
+        assert_eq!(data.chipset_temp, Some(52.0));
+        assert_eq!(data.motherboard_model, "Unknown");
+        assert_eq!(data.total_memory_mb, 0);
+    }
+
+    // Symlinks, like sysfs
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_fans_of_identical_chips_get_distinct_ids() {
+        let root = FakeRoot::new("two_gpus");
+        for (hwmon, device) in [("hwmon5", "0000:03:00.0"), ("hwmon6", "0000:0c:00.0")] {
+            root.write(&format!("sys/class/hwmon/{}/name", hwmon), "amdgpu");
+            root.write(&format!("sys/class/hwmon/{}/fan1_input", hwmon), "1500");
+            root.write(&format!("sys/devices/pci/{}/vendor", device), "0x1002");
+            std::os::unix::fs::symlink(
+                format!("../../../devices/pci/{}", device),
+                root.0.join(format!("sys/class/hwmon/{}/device", hwmon)),
+            )
+            .unwrap();
+        }
+
+        let ids: Vec<String> = HwmonProvider::with_root(&root.0)
+            .sample()
+            .fans
+            .into_iter()
+            .map(|fan| fan.id)
+            .collect();
+        assert_eq!(
+            ids,
+            vec!["amdgpu-0000:03:00.0/fan1", "amdgpu-0000:0c:00.0/fan1"]
+        );
+    }
+
+    #[test]
+    fn test_tdie_preferred_over_tctl() {
+        let root = FakeRoot::new("tdie");
+        root.write("sys/class/hwmon/hwmon0/name", "k10temp");
+        root.write("sys/class/hwmon/hwmon0/temp1_input", "75000");
+        root.write("sys/class/hwmon/hwmon0/temp1_label", "Tctl");
+        root.write("sys/class/hwmon/hwmon0/temp2_input", "65000");
+        root.write("sys/class/hwmon/hwmon0/temp2_label", "Tdie");
+
+        assert_eq!(HwmonProvider::with_root(&root.0).sample().cpu_temp, 65.0);
+    }
+
+    #[test]
+    fn test_rapl_package_power() {
+        let root = FakeRoot::new("rapl");
+        let zone = "sys/class/powercap/intel-rapl:0";
+        root.write(&format!("{}/name", zone), "package-0");
+        root.write(&format!("{}/max_energy_range_uj", zone), "1000000000");
+        root.write(&format!("{}/energy_uj", zone), "999000000");
+        // Core subzone must not be counted twice
+        root.write("sys/class/powercap/intel-rapl:0:0/name", "core");
+        root.write("sys/class/powercap/intel-rapl:0:0/energy_uj", "5000000");
+
+        let mut provider = HwmonProvider::with_root(&root.0);
+        let start = Instant::now();
+        assert_eq!(provider.sample_at(start).cpu_power, None);
+
+        // 45 J in 500 ms across the wrap = 90 W
+        root.write(&format!("{}/energy_uj", zone), "44000000");
+        let power = provider
+            .sample_at(start + Duration::from_millis(500))
+            .cpu_power
+            .unwrap();
+        assert!((power - 90.0).abs() < 0.01, "{}", power);
+    }
+
+    #[test]
+    fn test_rapl_power_of_two_packages_when_one_wraps() {
+        let root = FakeRoot::new("rapl_two_packages");
+        for (zone, range, energy) in [
+            ("intel-rapl:0", "262143328850", "262100000000"),
+            ("intel-rapl:1", "65532610987", "1000000000"),
+        ] {
+            let zone = format!("sys/class/powercap/{}", zone);
+            root.write(&format!("{}/name", zone), "package-x");
+            root.write(&format!("{}/max_energy_range_uj", zone), range);
+            root.write(&format!("{}/energy_uj", zone), energy);
+        }
+
+        let mut provider = HwmonProvider::with_root(&root.0);
+        let start = Instant::now();
+        assert_eq!(provider.sample_at(start).cpu_power, None);
+
+        // Package 0 wraps after 43.32885 J and adds 16.67115 J, package 1 adds 60 J:
+        // 120 J in 1 s
+        root.write("sys/class/powercap/intel-rapl:0/energy_uj", "16671150");
+        root.write("sys/class/powercap/intel-rapl:1/energy_uj", "1060000000");
+        let power = provider
+            .sample_at(start + Duration::from_secs(1))
+            .cpu_power
+            .unwrap();
+        assert!((power - 120.0).abs() < 0.01, "{}", power);
+    }
+}
@@ -21,164 +21,571 @@
 //
 // STANDARD LIBRARY DEPENDENCIES:
 // - std::time::Instant: For timestamp measurements (always available)
-// - std::thread: For sleep operations (always available)
+// - std::fs: For reading /proc/net/dev on Linux (always available)
+//
+// PLATFORM BACKENDS:
+// - Windows: PDH wildcard counters (`\Network Interface(*)\Bytes Sent/sec`)
+// - Linux: cumulative byte counters parsed from `/proc/net/dev`
+// The backend is selected at compile time with `#[cfg(target_os = ...)]`.
 // ============================================================================
 
+#[cfg(target_os = "windows")]
 use windows::core::*;
+#[cfg(target_os = "windows")]
 use windows::Win32::Foundation::*;
+#[cfg(target_os = "windows")]
 use windows::Win32::System::Performance::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+#[cfg(target_os = "linux")]
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
 // ============================================================================
 // PUBLIC API
 // ============================================================================
 
-/// Network statistics structure containing upload and download rates
+/// Statistics for a single network interface
 /// 
 /// # Fields
-/// - `upload_bps`: Upload rate in bytes per second
-/// - `download_bps`: Download rate in bytes per second
+/// - `name`: Interface name (`eth0` on Linux, PDH instance name on Windows)
+/// - `upload_bps` / `download_bps`: Current rates in bytes per second
+/// - `total_bytes_sent` / `total_bytes_received`: Cumulative byte counters
+/// - `packets_sent` / `packets_received`: Cumulative packet counters
+/// - `errors_sent` / `errors_received`: Cumulative error counters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InterfaceStats {
+    pub name: String,
+    pub upload_bps: f64,
+    pub download_bps: f64,
+    pub total_bytes_sent: u64,
+    pub total_bytes_received: u64,
+    pub packets_sent: u64,
+    pub packets_received: u64,
+    pub errors_sent: u64,
+    pub errors_received: u64,
+}
+
+/// Network statistics structure containing one record per interface
+/// 
+/// # Fields
+/// - `interfaces`: Per-interface rates and counters, in the order the
+///   platform reported them
+/// - `dropped_interfaces`: Interfaces left out of this sample because their
+///   counters reset, jumped implausibly, or the adapter disappeared
 /// 
 /// # Usage Example
 /// ```rust
-/// if let Some(stats) = get_network_stats() {
-///     println!("Upload: {:.2} B/s", stats.upload_bps);
-///     println!("Download: {:.2} B/s", stats.download_bps);
+/// if let Some(stats) = sampler.sample() {
+///     for interface in &stats.interfaces {
+///         println!("{}: {:.2} B/s up", interface.name, interface.upload_bps);
+///     }
 /// }
 /// ```
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NetworkStats {
-    pub upload_bps: f64,
-    pub download_bps: f64,
+    pub interfaces: Vec<InterfaceStats>,
+    pub dropped_interfaces: Vec<String>,
+}
+
+impl NetworkStats {
+    /// Sum the upload and download rates of the selected interfaces
+    /// 
+    /// # Parameters
+    /// - `selected`: Interface names to include. When empty, every
+    ///   non-loopback interface is included.
+    /// 
+    /// # Returns
+    /// `(upload_bps, download_bps)` in bytes per second
+    pub fn selected_rates(&self, selected: &BTreeSet<String>) -> (f64, f64) {
+        self.interfaces
+            .iter()
+            .filter(|interface| is_selected(&interface.name, selected))
+            .fold((0.0, 0.0), |(upload, download), interface| {
+                (upload + interface.upload_bps, download + interface.download_bps)
+            })
+    }
+
+    /// Whether any of the selected interfaces was dropped from this sample
+    /// 
+    /// When true, `selected_rates()` is missing part of the traffic and the
+    /// sample should not be plotted.
+    pub fn selection_dropped(&self, selected: &BTreeSet<String>) -> bool {
+        self.dropped_interfaces
+            .iter()
+            .any(|name| is_selected(name, selected))
+    }
+
+    /// Interfaces to offer for selection: the current ones, then selected
+    /// ones that have vanished (so they can still be unselected)
+    /// 
+    /// # Returns
+    /// `(name, present)` pairs, `present` being false for vanished interfaces
+    pub fn selectable_interfaces<'a>(&'a self, selected: &'a BTreeSet<String>) -> Vec<(&'a str, bool)> {
+        let present = self.interfaces.iter().map(|interface| (interface.name.as_str(), true));
+        let absent = selected
+            .iter()
+            .filter(|name| !self.interfaces.iter().any(|interface| &interface.name == *name))
+            .map(|name| (name.as_str(), false));
+        present.chain(absent).collect()
+    }
+}
+
+/// Path of the kernel's per-interface network counters on Linux
+#[cfg(target_os = "linux")]
+pub const PROC_NET_DEV: &str = "/proc/net/dev";
+
+/// Long-lived network statistics sampler
+/// 
+/// Keeps the previous `CounterReading` between calls, so every call to
+/// `sample()` takes a single reading and computes rates against the last
+/// one using the real elapsed time. Nothing sleeps inside the collection
+/// call; the caller decides how often to sample.
+/// 
+/// # Platform Backends
+/// - Windows: an open PDH query with wildcard counters, which sees virtual
+///   interfaces, VPNs and hidden network adapters
+/// - Linux: the cumulative counters in `/proc/net/dev` (or a fixture file)
+pub struct NetworkSampler {
+    #[cfg(target_os = "windows")]
+    counters: Option<PdhCounters>,
+    #[cfg(target_os = "linux")]
+    path: PathBuf,
+    previous: Option<CounterReading>,
+}
+
+impl NetworkSampler {
+    /// Create a sampler for the current platform's network counters
+    pub fn new() -> Self {
+        Self {
+            #[cfg(target_os = "windows")]
+            counters: PdhCounters::open(),
+            #[cfg(target_os = "linux")]
+            path: PathBuf::from(PROC_NET_DEV),
+            previous: None,
+        }
+    }
+
+    /// Create a sampler that reads `/proc/net/dev` formatted counters from `path`
+    /// 
+    /// # Parameters
+    /// - `path`: A file in `/proc/net/dev` format (a fixture file in tests)
+    #[cfg(all(test, target_os = "linux"))]
+    pub fn with_path(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            previous: None,
+        }
+    }
+
+    /// Take one reading and return the rates since the previous one
+    /// 
+    /// # Returns
+    /// - `Some(NetworkStats)`: Per-interface rates since the previous reading
+    /// - `None`: First reading (nothing to compare against yet), or the
+    ///   counters could not be read
+    /// 
+    /// # Threading Note
+    /// - Does not sleep, but PDH/procfs reads are still blocking I/O
+    /// - Use sample_network_stats_async() from async contexts
+    pub fn sample(&mut self) -> Option<NetworkStats> {
+        let current = self.collect()?;
+        let stats = self
+            .previous
+            .as_ref()
+            .and_then(|previous| calculate_rates(previous, &current));
+        self.previous = Some(current);
+        stats
+    }
+
+    /// Read the platform counters once
+    #[cfg(target_os = "windows")]
+    fn collect(&mut self) -> Option<CounterReading> {
+        // Retry opening the PDH query if it failed at startup
+        if self.counters.is_none() {
+            self.counters = PdhCounters::open();
+        }
+        self.counters.as_ref()?.collect()
+    }
+
+    /// Read the platform counters once
+    #[cfg(target_os = "linux")]
+    fn collect(&mut self) -> Option<CounterReading> {
+        read_proc_net_dev(&self.path)
+    }
+}
+
+impl Default for NetworkSampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ============================================================================
+// INTERNAL HELPER FUNCTIONS
+// ============================================================================
+
+/// Whether an interface name refers to a loopback adapter
+/// 
+/// Linux names it `lo`; Windows PDH instances contain "Loopback".
+fn is_loopback_name(name: &str) -> bool {
+    name == "lo" || name.to_lowercase().contains("loopback")
+}
+
+/// Whether an interface is part of a selection (empty = all non-loopback)
+fn is_selected(name: &str, selected: &BTreeSet<String>) -> bool {
+    if selected.is_empty() {
+        !is_loopback_name(name)
+    } else {
+        selected.contains(name)
+    }
 }
 
-/// Get network interface statistics using Windows PDH (Performance Data Helper)
+/// Highest rate accepted from a single interface: 100 Gbps in bytes per second
 /// 
-/// This function uses wildcard PDH counters to aggregate data from ALL network
-/// interfaces, including virtual interfaces that are not visible through traditional
-/// enumeration methods.
+/// Anything above this is a bogus jump (e.g. a 64-bit counter reset to a
+/// larger value), not real traffic.
+const MAX_PLAUSIBLE_BPS: f64 = 100_000_000_000.0 / 8.0;
+
+/// Highest rate at which a counter is still treated as a 32-bit counter that
+/// wrapped: 1 Gbps in bytes per second
 /// 
-/// # Method
-/// 1. Opens a PDH query handle
-/// 2. Adds wildcard counters for bytes sent/received across all interfaces
-/// 3. Collects baseline data
-/// 4. Waits 500ms for data accumulation
-/// 5. Collects second data point
-/// 6. Calculates rates based on the difference
+/// A 32-bit byte counter on a faster link would wrap every few seconds, so
+/// such links report 64-bit counters.
+const MAX_32BIT_WRAP_BPS: f64 = 1_000_000_000.0 / 8.0;
+
+/// Difference between two readings of a cumulative counter taken
+/// `elapsed_seconds` apart
 /// 
 /// # Returns
-/// - `Some(NetworkStats)`: Successfully calculated upload/download rates
-/// - `None`: Failed to collect data or calculate rates
-/// 
-/// # Performance Notes
-/// - Each call takes ~500ms due to the measurement interval
-/// - Uses PDH wildcard counters: `\Network Interface(*)\Bytes Sent/sec`
-/// - Works with virtual interfaces, VPNs, and hidden network adapters
-/// 
-/// # Platform Requirements
-/// - Windows only (requires Windows Performance Data Helper)
-/// - Requires administrative privileges for some network interfaces
-/// 
-/// # Threading Note
-/// - This function is BLOCKING and should be called through async wrapper
-/// - Use get_network_stats_async() for non-blocking UI operation
-pub fn get_network_stats() -> Option<NetworkStats> {
-    unsafe {
-        let mut query: isize = 0;
-        
-        if PdhOpenQueryW(None, 0, &mut query) != ERROR_SUCCESS.0 {
-            return None;
-        }
-        
-        let sent_path = HSTRING::from("\\Network Interface(*)\\Bytes Sent/sec");
-        let received_path = HSTRING::from("\\Network Interface(*)\\Bytes Received/sec");
-        
-        let mut counter_sent: isize = 0;
-        let mut counter_received: isize = 0;
+/// - `Some(delta)`: The counter grew, or a 32-bit counter wrapped past
+///   `u32::MAX` (the previous value was in the upper half of the 32-bit range
+///   and the wrapped delta fits the elapsed time at `MAX_32BIT_WRAP_BPS`)
+/// - `None`: The counter went backwards for any other reason, i.e. the
+///   adapter or its driver reset the counter
+fn counter_delta(before: u64, now: u64, elapsed_seconds: f64) -> Option<u64> {
+    const U32_MAX: u64 = u32::MAX as u64;
+    
+    if now >= before {
+        return Some(now - before);
+    }
+    if before > U32_MAX || before <= U32_MAX / 2 {
+        return None;
+    }
+    // A 64-bit counter that reset from the upper half of the 32-bit range
+    // looks like a wrap too, but only a real wrap is this small
+    let wrapped = U32_MAX - before + now + 1;
+    (wrapped as f64 <= MAX_32BIT_WRAP_BPS * elapsed_seconds).then_some(wrapped)
+}
+
+/// Calculate per-interface rates from two cumulative counter readings
+/// 
+/// # Sample Validation
+/// - 32-bit counters that wrapped are corrected with `counter_delta()`
+/// - Interfaces whose counters reset, or whose rate exceeds
+///   `MAX_PLAUSIBLE_BPS`, are left out and listed in `dropped_interfaces`
+/// - Interfaces that disappeared since the baseline are listed in
+///   `dropped_interfaces`
+/// - New interfaces are left out until they have a baseline of their own
+/// 
+/// # Returns
+/// - `Some(NetworkStats)`: Rates in bytes per second
+/// - `None`: The readings were taken at the same instant
+fn calculate_rates(baseline: &CounterReading, current: &CounterReading) -> Option<NetworkStats> {
+    let elapsed = current.timestamp.duration_since(baseline.timestamp);
+    let elapsed_seconds = elapsed.as_secs_f64();
+    
+    if elapsed_seconds <= 0.0 {
+        return None;
+    }
+    
+    let mut interfaces = Vec::new();
+    let mut dropped_interfaces: Vec<String> = baseline
+        .interfaces
+        .iter()
+        .filter(|before| !current.interfaces.iter().any(|now| now.name == before.name))
+        .map(|before| before.name.clone())
+        .collect();
+    
+    for now in &current.interfaces {
+        let Some(before) = baseline.interfaces.iter().find(|before| before.name == now.name) else {
+            continue;
+        };
         
-        PdhAddCounterW(query, &sent_path, 0, &mut counter_sent);
-        PdhAddCounterW(query, &received_path, 0, &mut counter_received);
+        let sent = counter_delta(before.bytes_sent, now.bytes_sent, elapsed_seconds);
+        let received = counter_delta(before.bytes_received, now.bytes_received, elapsed_seconds);
+        let (Some(sent), Some(received)) = (sent, received) else {
+            dropped_interfaces.push(now.name.clone());
+            continue;
+        };
         
-        let baseline = collect_raw_values(query, counter_sent, counter_received)?;
-        let baseline_time = baseline.timestamp;
+        let upload_bps = sent as f64 / elapsed_seconds;
+        let download_bps = received as f64 / elapsed_seconds;
+        if upload_bps > MAX_PLAUSIBLE_BPS || download_bps > MAX_PLAUSIBLE_BPS {
+            dropped_interfaces.push(now.name.clone());
+            continue;
+        }
         
-        std::thread::sleep(std::time::Duration::from_millis(500));
+        interfaces.push(InterfaceStats {
+            name: now.name.clone(),
+            upload_bps,
+            download_bps,
+            total_bytes_sent: now.bytes_sent,
+            total_bytes_received: now.bytes_received,
+            packets_sent: now.packets_sent,
+            packets_received: now.packets_received,
+            errors_sent: now.errors_sent,
+            errors_received: now.errors_received,
+        });
+    }
+    
+    Some(NetworkStats {
+        interfaces,
+        dropped_interfaces,
+    })
+}
+
+/// Open PDH query with one wildcard counter per statistic
+/// 
+/// # Fields
+/// - `query`: PDH query handle, closed on drop
+/// - remaining fields: counter handles for `\Network Interface(*)\...`
+#[cfg(target_os = "windows")]
+struct PdhCounters {
+    query: isize,
+    bytes_sent: isize,
+    bytes_received: isize,
+    packets_sent: isize,
+    packets_received: isize,
+    errors_sent: isize,
+    errors_received: isize,
+}
+
+#[cfg(target_os = "windows")]
+impl PdhCounters {
+    /// Open a PDH query and add the wildcard network interface counters
+    /// 
+    /// # Returns
+    /// - `Some(PdhCounters)`: Query opened and all counters added
+    /// - `None`: PDH query or counter creation failed
+    fn open() -> Option<Self> {
+        unsafe {
+            let mut query: isize = 0;
+            
+            if PdhOpenQueryW(None, 0, &mut query) != ERROR_SUCCESS.0 {
+                return None;
+            }
+            
+            let mut counters = PdhCounters {
+                query,
+                bytes_sent: 0,
+                bytes_received: 0,
+                packets_sent: 0,
+                packets_received: 0,
+                errors_sent: 0,
+                errors_received: 0,
+            };
+            
+            let paths = [
+                ("\\Network Interface(*)\\Bytes Sent/sec", &mut counters.bytes_sent),
+                ("\\Network Interface(*)\\Bytes Received/sec", &mut counters.bytes_received),
+                ("\\Network Interface(*)\\Packets Sent/sec", &mut counters.packets_sent),
+                ("\\Network Interface(*)\\Packets Received/sec", &mut counters.packets_received),
+                ("\\Network Interface(*)\\Packets Outbound Errors", &mut counters.errors_sent),
+                ("\\Network Interface(*)\\Packets Received Errors", &mut counters.errors_received),
+            ];
+            
+            for (path, counter) in paths {
+                if PdhAddCounterW(query, &HSTRING::from(path), 0, counter) != ERROR_SUCCESS.0 {
+                    return None;
+                }
+            }
+            
+            Some(counters)
+        }
+    }
+    
+    /// Collect raw counter values for every interface at a single timestamp
+    /// 
+    /// # Returns
+    /// - `Some(CounterReading)`: Successfully collected raw values with timestamp
+    /// - `None`: Failed to collect data from any counter
+    /// 
+    /// # PDH Counter Details
+    /// - Uses `PdhCollectQueryData()` to refresh all counters
+    /// - Uses `PdhGetRawCounterArrayW()` to get raw 64-bit values per instance
+    /// - Returns cumulative counts (not rates)
+    fn collect(&self) -> Option<CounterReading> {
+        unsafe {
+            if PdhCollectQueryData(self.query) != ERROR_SUCCESS.0 {
+                return None;
+            }
+        }
         
-        let current = collect_raw_values(query, counter_sent, counter_received)?;
-        PdhCloseQuery(query);
+        let bytes_sent = raw_counter_array(self.bytes_sent)?;
+        let bytes_received = raw_counter_array(self.bytes_received)?;
+        let packets_sent = raw_counter_array(self.packets_sent).unwrap_or_default();
+        let packets_received = raw_counter_array(self.packets_received).unwrap_or_default();
+        let errors_sent = raw_counter_array(self.errors_sent).unwrap_or_default();
+        let errors_received = raw_counter_array(self.errors_received).unwrap_or_default();
         
-        let elapsed = current.timestamp.duration_since(baseline_time);
-        let elapsed_seconds = elapsed.as_secs_f64();
+        let lookup = |values: &[(String, u64)], name: &str| {
+            values.iter().find(|(instance, _)| instance == name).map_or(0, |(_, value)| *value)
+        };
         
-        if elapsed_seconds > 0.0 {
-            let upload_bps = (current.bytes_sent - baseline.bytes_sent) / elapsed_seconds;
-            let download_bps = (current.bytes_received - baseline.bytes_received) / elapsed_seconds;
-            
-            Some(NetworkStats {
-                upload_bps,
-                download_bps,
+        let interfaces = bytes_sent
+            .iter()
+            .map(|(name, sent)| InterfaceCounters {
+                name: name.clone(),
+                bytes_sent: *sent,
+                bytes_received: lookup(&bytes_received, name),
+                packets_sent: lookup(&packets_sent, name),
+                packets_received: lookup(&packets_received, name),
+                errors_sent: lookup(&errors_sent, name),
+                errors_received: lookup(&errors_received, name),
             })
-        } else {
-            None
-        }
+            .collect();
+        
+        Some(CounterReading {
+            timestamp: Instant::now(),
+            interfaces,
+        })
     }
 }
 
-// ============================================================================
-// INTERNAL HELPER FUNCTIONS
-// ============================================================================
+#[cfg(target_os = "windows")]
+impl Drop for PdhCounters {
+    fn drop(&mut self) {
+        unsafe {
+            PdhCloseQuery(self.query);
+        }
+    }
+}
 
-/// Collect raw counter values from PDH for a single timestamp
-/// 
-/// # Parameters
-/// - `query`: PDH query handle
-/// - `counter_sent`: Handle to bytes sent/sec counter
-/// - `counter_received`: Handle to bytes received/sec counter
+/// Read the raw value of every instance of a wildcard PDH counter
 /// 
 /// # Returns
-/// - `Some(CounterReading)`: Successfully collected raw values with timestamp
-/// - `None`: Failed to collect data from any counter
-/// 
-/// # PDH Counter Details
-/// - Uses `PdhCollectQueryData()` to refresh all counters
-/// - Uses `PdhGetRawCounterValue()` to get raw 64-bit values
-/// - Returns cumulative byte counts (not rates)
-fn collect_raw_values(query: isize, counter_sent: isize, counter_received: isize) -> Option<CounterReading> {
+/// - `Some(Vec<(instance name, raw value)>)`: One entry per instance
+/// - `None`: The counter array could not be read
+#[cfg(target_os = "windows")]
+pub fn raw_counter_array(counter: isize) -> Option<Vec<(String, u64)>> {
     unsafe {
-        if PdhCollectQueryData(query) != ERROR_SUCCESS.0 {
+        let mut buffer_size: u32 = 0;
+        let mut item_count: u32 = 0;
+        
+        // First call only reports the required buffer size
+        let status = PdhGetRawCounterArrayW(counter, &mut buffer_size, &mut item_count, None);
+        if status != PDH_MORE_DATA || buffer_size == 0 {
             return None;
         }
         
-        let mut raw_sent = PDH_RAW_COUNTER::default();
-        let mut raw_received = PDH_RAW_COUNTER::default();
+        // Item names are stored in the same buffer after the items, so size it in
+        // whole items and round up
+        let item_size = std::mem::size_of::<PDH_RAW_COUNTER_ITEM_W>();
+        let mut buffer = vec![PDH_RAW_COUNTER_ITEM_W::default(); (buffer_size as usize).div_ceil(item_size)];
         
-        if PdhGetRawCounterValue(counter_sent, None, &mut raw_sent) == ERROR_SUCCESS.0
-            && PdhGetRawCounterValue(counter_received, None, &mut raw_received) == ERROR_SUCCESS.0 {
-            Some(CounterReading {
-                timestamp: Instant::now(),
-                bytes_sent: raw_sent.FirstValue as f64,
-                bytes_received: raw_received.FirstValue as f64,
+        if PdhGetRawCounterArrayW(counter, &mut buffer_size, &mut item_count, Some(buffer.as_mut_ptr())) != ERROR_SUCCESS.0 {
+            return None;
+        }
+        
+        let values = buffer
+            .iter()
+            .take(item_count as usize)
+            .filter_map(|item| {
+                let name = item.szName.to_string().ok()?;
+                Some((name, item.RawValue.FirstValue.max(0) as u64))
             })
-        } else {
-            None
+            .collect();
+        
+        Some(values)
+    }
+}
+
+/// Read and parse a file in `/proc/net/dev` format
+/// 
+/// # Parameters
+/// - `path`: Usually `PROC_NET_DEV`, or a fixture file in tests
+/// 
+/// # Returns
+/// - `Some(CounterReading)`: Per-interface counters, timestamped now
+/// - `None`: The file could not be read or contained no interfaces
+#[cfg(target_os = "linux")]
+fn read_proc_net_dev(path: &Path) -> Option<CounterReading> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    parse_proc_net_dev(&contents)
+}
+
+/// Parse the contents of `/proc/net/dev` into per-interface counters
+/// 
+/// # Format
+/// Two header lines followed by one line per interface:
+/// `  eth0: <rx bytes> <rx packets> <rx errs> ... <tx bytes> <tx packets> <tx errs> ...`
+/// Receive bytes/packets/errors are fields 1-3 after the colon,
+/// transmit bytes/packets/errors are fields 9-11.
+/// 
+/// Every interface is reported, loopback included; callers decide which
+/// ones to aggregate. Malformed lines are skipped.
+#[cfg(target_os = "linux")]
+fn parse_proc_net_dev(contents: &str) -> Option<CounterReading> {
+    let mut interfaces = Vec::new();
+    
+    for line in contents.lines().skip(2) {
+        let Some((name, counters)) = line.split_once(':') else {
+            continue;
+        };
+        
+        let fields: Vec<u64> = counters
+            .split_whitespace()
+            .filter_map(|field| field.parse().ok())
+            .collect();
+        if fields.len() < 11 {
+            continue;
         }
+        
+        interfaces.push(InterfaceCounters {
+            name: name.trim().to_string(),
+            bytes_received: fields[0],
+            packets_received: fields[1],
+            errors_received: fields[2],
+            bytes_sent: fields[8],
+            packets_sent: fields[9],
+            errors_sent: fields[10],
+        });
     }
+    
+    if interfaces.is_empty() {
+        return None;
+    }
+    
+    Some(CounterReading {
+        timestamp: Instant::now(),
+        interfaces,
+    })
 }
 
 /// Internal structure to store raw counter readings with timestamps
 /// 
 /// # Fields
 /// - `timestamp`: When the reading was taken
-/// - `bytes_sent`: Cumulative bytes sent (since system boot)
-/// - `bytes_received`: Cumulative bytes received (since system boot)
+/// - `interfaces`: Cumulative counters for every interface
 /// 
 /// # Note
 /// These are cumulative values, not rates. Rates are calculated by
 /// comparing two readings taken at different times.
 struct CounterReading {
     timestamp: Instant,
-    bytes_sent: f64,
-    bytes_received: f64,
+    interfaces: Vec<InterfaceCounters>,
+}
+
+/// Cumulative counters of a single interface (since system boot)
+struct InterfaceCounters {
+    name: String,
+    bytes_sent: u64,
+    bytes_received: u64,
+    packets_sent: u64,
+    packets_received: u64,
+    errors_sent: u64,
+    errors_received: u64,
 }
 
 /// Format bytes per second as Megabits per second with fixed-width formatting
@@ -214,35 +621,322 @@ pub fn format_rate(bps: f64) -> String {
 // ASYNC NETWORK STATS COLLECTION
 // ============================================================================
 
-/// Async version of get_network_stats that uses tokio::task::spawn_blocking
+/// Async wrapper around `NetworkSampler::sample()` using tokio::task::spawn_blocking
 /// 
-/// This function runs the blocking network stats collection in a separate thread
-/// pool without blocking the async runtime, making it safe to use in async contexts.
+/// The sampler is moved onto tokio's blocking thread pool for the PDH/procfs
+/// read and handed back together with the result, so it can be kept in a
+/// stream's state between ticks.
 /// 
 /// # Returns
-/// - `Some(NetworkStats)`: Successfully calculated upload/download rates
-/// - `None`: Failed to collect data or calculate rates
+/// - The sampler (a fresh one if the blocking task panicked)
+/// - `Some(NetworkStats)` / `None` as returned by `NetworkSampler::sample()`
 /// 
 /// # Usage with Iced
 /// ```rust
-/// use iced::Task;
-/// 
-/// // In your update function:
-/// Task::perform(get_network_stats_async(), Message::StatsUpdated)
+/// stream::unfold(NetworkSampler::new(), |sampler| async move {
+///     let (sampler, stats) = sample_network_stats_async(sampler).await;
+///     Some((Message::StatsUpdated(stats), sampler))
+/// })
 /// ```
-/// 
-/// # Performance Notes
-/// - Uses tokio's blocking thread pool for optimal performance
-/// - Does not block the main async runtime
-/// - Still takes ~500ms due to Windows PDH measurement requirements
-pub async fn get_network_stats_async() -> Option<NetworkStats> {
+pub async fn sample_network_stats_async(mut sampler: NetworkSampler) -> (NetworkSampler, Option<NetworkStats>) {
     match tokio::task::spawn_blocking(move || {
-        get_network_stats()
+        let stats = sampler.sample();
+        (sampler, stats)
     }).await {
         Ok(result) => result,
         Err(e) => {
             eprintln!("Network stats task failed: {:?}", e);
-            None
+            (NetworkSampler::new(), None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interface(name: &str, upload_bps: f64, download_bps: f64) -> InterfaceStats {
+        InterfaceStats {
+            name: name.to_string(),
+            upload_bps,
+            download_bps,
+            total_bytes_sent: 0,
+            total_bytes_received: 0,
+            packets_sent: 0,
+            packets_received: 0,
+            errors_sent: 0,
+            errors_received: 0,
+        }
+    }
+
+    #[test]
+    fn test_selected_rates_defaults_to_non_loopback() {
+        let stats = NetworkStats {
+            interfaces: vec![
+                interface("lo", 1000.0, 1000.0),
+                interface("eth0", 10.0, 20.0),
+                interface("tun0", 5.0, 7.0),
+            ],
+            dropped_interfaces: Vec::new(),
+        };
+        assert_eq!(stats.selected_rates(&BTreeSet::new()), (15.0, 27.0));
+    }
+
+    #[test]
+    fn test_selectable_interfaces_keep_vanished_selection() {
+        let stats = NetworkStats {
+            interfaces: vec![interface("eth0", 0.0, 0.0), interface("wlan0", 0.0, 0.0)],
+            dropped_interfaces: Vec::new(),
+        };
+        let selected = BTreeSet::from(["tun0".to_string(), "wlan0".to_string()]);
+        assert_eq!(
+            stats.selectable_interfaces(&selected),
+            vec![("eth0", true), ("wlan0", true), ("tun0", false)]
+        );
+        assert_eq!(
+            stats.selectable_interfaces(&BTreeSet::new()),
+            vec![("eth0", true), ("wlan0", true)]
+        );
+    }
+
+    #[test]
+    fn test_selected_rates_only_sums_selection() {
+        let stats = NetworkStats {
+            interfaces: vec![
+                interface("eth0", 10.0, 20.0),
+                interface("tun0", 5.0, 7.0),
+            ],
+            dropped_interfaces: Vec::new(),
+        };
+        let selected = BTreeSet::from(["tun0".to_string()]);
+        assert_eq!(stats.selected_rates(&selected), (5.0, 7.0));
+    }
+
+    fn counters(name: &str, sent: u64, received: u64) -> InterfaceCounters {
+        InterfaceCounters {
+            name: name.to_string(),
+            bytes_sent: sent,
+            bytes_received: received,
+            packets_sent: 3,
+            packets_received: 4,
+            errors_sent: 0,
+            errors_received: 1,
         }
     }
-}
\ No newline at end of file
+
+    /// Two readings of the given interfaces taken `seconds` apart
+    fn readings(
+        seconds: u64,
+        before: Vec<InterfaceCounters>,
+        now: Vec<InterfaceCounters>,
+    ) -> (CounterReading, CounterReading) {
+        let start = Instant::now();
+        let baseline = CounterReading {
+            timestamp: start,
+            interfaces: before,
+        };
+        let current = CounterReading {
+            timestamp: start + std::time::Duration::from_secs(seconds),
+            interfaces: now,
+        };
+        (baseline, current)
+    }
+
+    #[test]
+    fn test_calculate_rates_per_interface() {
+        let (baseline, current) = readings(
+            2,
+            vec![counters("eth0", 1000, 2000)],
+            vec![counters("eth0", 3000, 6000), counters("wg0", 50, 50)],
+        );
+
+        let stats = calculate_rates(&baseline, &current).unwrap();
+        // wg0 has no baseline yet, so only eth0 is reported
+        assert_eq!(stats.interfaces.len(), 1);
+        assert!(stats.dropped_interfaces.is_empty());
+        let eth0 = &stats.interfaces[0];
+        assert_eq!(eth0.upload_bps, 1000.0);
+        assert_eq!(eth0.download_bps, 2000.0);
+        assert_eq!(eth0.total_bytes_sent, 3000);
+        assert_eq!(eth0.errors_received, 1);
+    }
+
+    #[test]
+    fn test_calculate_rates_same_instant() {
+        let (baseline, _) = readings(0, vec![counters("eth0", 0, 0)], Vec::new());
+        assert!(calculate_rates(&baseline, &baseline).is_none());
+    }
+
+    #[test]
+    fn test_counter_delta() {
+        assert_eq!(counter_delta(100, 250, 1.0), Some(150));
+        assert_eq!(counter_delta(100, 100, 1.0), Some(0));
+        // 32-bit wrap: 15 bytes up to u32::MAX, 1 to wrap to 0, then 9 more
+        assert_eq!(counter_delta(u32::MAX as u64 - 15, 9, 1.0), Some(25));
+        // Going backwards from the lower half of the 32-bit range is a reset
+        assert_eq!(counter_delta(1_000_000, 10, 1.0), None);
+        // 64-bit counters never wrap in practice, going backwards is a reset
+        assert_eq!(counter_delta(u64::MAX / 2, 10, 1.0), None);
+        // A 64-bit counter reset from ~3 GB would be a ~1.3 GB "wrap"
+        assert_eq!(counter_delta(3_000_000_000, 10, 0.2), None);
+        assert_eq!(counter_delta(3_000_000_000, 10, 5.0), None);
+    }
+
+    #[test]
+    fn test_calculate_rates_corrects_32bit_wrap() {
+        let before = u32::MAX as u64 - 999;
+        let (baseline, current) = readings(
+            1,
+            vec![counters("eth0", before, 5000)],
+            vec![counters("eth0", 1000, 6000)],
+        );
+
+        let stats = calculate_rates(&baseline, &current).unwrap();
+        assert!(stats.dropped_interfaces.is_empty());
+        assert_eq!(stats.interfaces[0].upload_bps, 2000.0);
+        assert_eq!(stats.interfaces[0].download_bps, 1000.0);
+    }
+
+    #[test]
+    fn test_calculate_rates_drops_reset_counters() {
+        let (baseline, current) = readings(
+            1,
+            vec![counters("eth0", 500_000, 900_000), counters("wlan0", 100, 100)],
+            vec![counters("eth0", 1_000_000, 20), counters("wlan0", 300, 400)],
+        );
+
+        let stats = calculate_rates(&baseline, &current).unwrap();
+        assert_eq!(stats.dropped_interfaces, ["eth0"]);
+        assert_eq!(stats.interfaces.len(), 1);
+        assert_eq!(stats.interfaces[0].name, "wlan0");
+        assert_eq!(stats.selected_rates(&BTreeSet::new()), (200.0, 300.0));
+        assert!(stats.selection_dropped(&BTreeSet::new()));
+        assert!(!stats.selection_dropped(&BTreeSet::from(["wlan0".to_string()])));
+    }
+
+    #[test]
+    fn test_calculate_rates_drops_64bit_reset_from_upper_32bit_range() {
+        let start = Instant::now();
+        let baseline = CounterReading {
+            timestamp: start,
+            interfaces: vec![counters("eth0", 3_000_000_000, 3_100_000_000)],
+        };
+        let current = CounterReading {
+            timestamp: start + std::time::Duration::from_millis(200),
+            interfaces: vec![counters("eth0", 4_000, 8_000)],
+        };
+
+        let stats = calculate_rates(&baseline, &current).unwrap();
+        assert_eq!(stats.dropped_interfaces, ["eth0"]);
+        assert!(stats.interfaces.is_empty());
+    }
+
+    #[test]
+    fn test_calculate_rates_drops_implausible_jumps() {
+        let (baseline, current) = readings(
+            1,
+            vec![counters("eth0", 5_000_000_000, 0)],
+            vec![counters("eth0", 900_000_000_000, 10)],
+        );
+
+        let stats = calculate_rates(&baseline, &current).unwrap();
+        assert_eq!(stats.dropped_interfaces, ["eth0"]);
+        assert!(stats.interfaces.is_empty());
+    }
+
+    #[test]
+    fn test_calculate_rates_reports_disappeared_adapters() {
+        let (baseline, current) = readings(
+            1,
+            vec![counters("eth0", 0, 0), counters("tun0", 0, 0)],
+            vec![counters("eth0", 10, 10)],
+        );
+
+        let stats = calculate_rates(&baseline, &current).unwrap();
+        assert_eq!(stats.dropped_interfaces, ["tun0"]);
+        assert_eq!(stats.interfaces.len(), 1);
+        assert!(stats.selection_dropped(&BTreeSet::new()));
+        assert!(!stats.selection_dropped(&BTreeSet::from(["eth0".to_string()])));
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod linux_tests {
+    use super::*;
+
+    const PROC_NET_DEV_FIXTURE: &str = "\
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo: 10709699    1756    0    0    0     0          0         0 10709699    1756    0    0    0     0       0          0
+  eth0:    3810      58    2    0    0     0          0         0     5215      59    1    0    0     0       0          0
+ wlan0:1000000    900    0    0    0     0          0         0   250000     400    0    0    0     0       0          0
+";
+
+    #[test]
+    fn test_parse_proc_net_dev_per_interface() {
+        let reading = parse_proc_net_dev(PROC_NET_DEV_FIXTURE).unwrap();
+        let names: Vec<&str> = reading.interfaces.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, ["lo", "eth0", "wlan0"]);
+
+        let eth0 = &reading.interfaces[1];
+        assert_eq!(eth0.bytes_received, 3810);
+        assert_eq!(eth0.packets_received, 58);
+        assert_eq!(eth0.errors_received, 2);
+        assert_eq!(eth0.bytes_sent, 5215);
+        assert_eq!(eth0.packets_sent, 59);
+        assert_eq!(eth0.errors_sent, 1);
+
+        assert_eq!(reading.interfaces[2].bytes_received, 1_000_000);
+    }
+
+    #[test]
+    fn test_parse_proc_net_dev_without_interfaces() {
+        let headers_only: String = PROC_NET_DEV_FIXTURE.lines().take(2).collect::<Vec<_>>().join("\n");
+        assert!(parse_proc_net_dev(&headers_only).is_none());
+    }
+
+    #[test]
+    fn test_read_proc_net_dev_fixture_file() {
+        let path = std::env::temp_dir().join(format!("cutemonitor_proc_net_dev_{}", std::process::id()));
+        std::fs::write(&path, PROC_NET_DEV_FIXTURE).unwrap();
+
+        let reading = read_proc_net_dev(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(reading.unwrap().interfaces.len(), 3);
+    }
+
+    #[test]
+    fn test_sampler_uses_previous_reading() {
+        let path = std::env::temp_dir().join(format!("cutemonitor_sampler_{}", std::process::id()));
+        std::fs::write(&path, PROC_NET_DEV_FIXTURE).unwrap();
+        let mut sampler = NetworkSampler::with_path(&path);
+
+        // Nothing to compare the first reading against
+        assert!(sampler.sample().is_none());
+
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        std::fs::write(&path, PROC_NET_DEV_FIXTURE.replace("3810", "13810")).unwrap();
+        let stats = sampler.sample();
+
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        let unchanged = sampler.sample();
+        std::fs::remove_file(&path).unwrap();
+
+        let stats = stats.unwrap();
+        assert_eq!(stats.interfaces.len(), 3);
+        assert!(is_loopback_name(&stats.interfaces[0].name));
+        let (upload, download) = stats.selected_rates(&BTreeSet::new());
+        assert_eq!(upload, 0.0);
+        // 10000 bytes over at least 20ms is at most 500 kB/s
+        assert!(download > 0.0 && download <= 500_000.0);
+
+        // The counters did not move since the previous sample
+        assert_eq!(unchanged.unwrap().selected_rates(&BTreeSet::new()), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_read_proc_net_dev_missing_file() {
+        assert!(read_proc_net_dev(Path::new("/nonexistent/proc/net/dev")).is_none());
+    }
+}
@@ -39,22 +39,22 @@ impl LaunchGpuDetector {
 
     /// Perform one-time GPU detection with virtual environment support
     pub async fn detect_gpus(&mut self) -> Result<GpuDetectionResult> {
-        println!("Detecting GPUs...");
+        eprintln!("Detecting GPUs...");
         let detection_start = Instant::now();
 
         // Check if running in virtual environment first
         let is_virtual = self.vm_detector.is_virtual_environment();
         if is_virtual {
-            println!("Running in virtual environment detected");
+            eprintln!("Running in virtual environment detected");
             let detection_info = self.vm_detector.get_detection_info();
             if detection_info.contains(vm_detect::Detection::HYPERVISOR_BIT) {
-                println!("  - Hypervisor bit detected");
+                eprintln!("  - Hypervisor bit detected");
             }
             if detection_info.contains(vm_detect::Detection::HYPERVISOR_CPU_VENDOR) {
-                println!("  - Hypervisor CPU vendor detected");
+                eprintln!("  - Hypervisor CPU vendor detected");
             }
             if detection_info.contains(vm_detect::Detection::UNEXPECTED_CPU_VENDOR) {
-                println!("  - Unexpected CPU vendor detected");
+                eprintln!("  - Unexpected CPU vendor detected");
             }
         }
 
@@ -62,7 +62,7 @@ impl LaunchGpuDetector {
             Ok(mut gpus) => {
                 // Enrich GPU data with virtual GPU information if in VM
                 if is_virtual {
-                    println!("Enriching GPU data with virtual GPU information...");
+                    eprintln!("Enriching GPU data with virtual GPU information...");
                     for gpu in &mut gpus {
                         if let Err(e) = self.vm_detector.enrich_vm_gpu(gpu) {
                             warn!("Failed to enrich GPU {}: {}", gpu.name, e);
@@ -79,7 +79,7 @@ impl LaunchGpuDetector {
                             });
 
                             if !already_exists {
-                                println!("  + Found additional virtual GPU: {}", virtual_gpu.name);
+                                eprintln!("  + Found additional virtual GPU: {}", virtual_gpu.name);
                                 gpus.push(virtual_gpu);
                             }
                         }
@@ -87,7 +87,7 @@ impl LaunchGpuDetector {
                 }
 
                 let detection_time = detection_start.elapsed();
-                println!(
+                eprintln!(
                     "GPU Detection completed in {:.2}ms",
                     detection_time.as_millis()
                 );
@@ -127,7 +127,7 @@ impl LaunchGpuDetector {
                 let version = self.amd_version_detector.detect_version_for_gpu(&gpu.name);
                 amd_gpu_versions.push((gpu_list.iter().position(|g| std::ptr::eq(g, gpu)).unwrap(), version));
 
-                println!(
+                eprintln!(
                     "  AMD GPU {} detected - will use {}",
                     gpu.name,
                     AmdVersionDetector::get_version_name(version)
@@ -154,7 +154,7 @@ impl LaunchGpuDetector {
             }
         });
 
-        println!("GPU Update Order (optimized for speed):");
+        eprintln!("GPU Update Order (optimized for speed):");
         for &index in &gpu_indices {
             let gpu = &gpu_list[index];
             let vendor = if gpu.name.to_lowercase().contains("nvidia")
@@ -168,11 +168,11 @@ impl LaunchGpuDetector {
             } else {
                 "Unknown"
             };
-            println!("  {}. {} - {}", index + 1, gpu.name, vendor);
+            eprintln!("  {}. {} - {}", index + 1, gpu.name, vendor);
         }
 
         // Print detection summary
-        println!("\nDetection Summary:");
+        eprintln!("\nDetection Summary:");
         if has_nvidia {
         }
 
@@ -12,7 +12,7 @@ use std::io::Cursor; // For reading data from memory
 
 // Import our custom modules
 use crate::state::State; // Our application state
-use crate::utils::is_admin; // Function to check if running as administrator
+use crate::utils::{attach_parent_console, is_admin, relaunch_as_admin}; // Admin check and console helpers
 
 // Embedded logos - these are compiled into the binary at build time
 // CPU manufacturer logos
@@ -24,10 +24,30 @@ static VM_LOGO: &[u8] = include_bytes!("../VM_PC256.png"); // For virtual machin
 
 // Declare our modules - these contain the actual implementation
 mod canvas; // Canvas drawing programs for charts
+mod alerts; // Alert rules with hysteresis and hooks
+mod cli; // Command-line option parsing
+mod collector; // Latest-value channels between collectors and the UI
+mod cpu_frequency; // Per-CPU clocks, reference clocks and throttling detection
+mod cpu_topology; // Packages, CCDs, cores and SMT siblings of the CPU
+mod cpu_times; // User/system/iowait/irq/steal breakdown of CPU time
 mod data_colouring; // Functions to color-code data based on values
+mod diagnostics; // Parse-error counters of the hardware collector and --diagnose
+mod external_sensors; // Extra sensors read from user-configured commands
+mod fans; // Fan and pump readings with RPM history
 mod hardware_checker; // Hardware monitoring and data collection
+mod headless; // Windowless mode printing JSON snapshots
+#[cfg(target_os = "linux")]
+mod hwmon; // Native Linux sensors (hwmon, powercap) for hardware_checker
+mod history; // Recording readings to disk and replaying them
+mod settings; // settings.json with intervals, history sizes and window geometry
+mod sensors; // Generic sensor tree and the TempMonitor.exe protocol
+mod metrics_exporter; // Optional Prometheus /metrics endpoint
+mod process_actions; // Terminate, reprioritize or pin a process
+mod process_table; // Sortable, filterable table of user processes
 mod state; // Application state management
 mod styles; // UI styling functions
+mod supervisor; // Restarts helper processes such as TempMonitor.exe
+mod system_sampler; // Shared sysinfo sampler for CPU and process data
 mod subscriptions; // Asynchronous data streams
 mod utils; // Utility functions
 mod what_cpu_check; // CPU information detection
@@ -53,18 +73,89 @@ mod interface_stats;
 mod bandwidth;
 
 // Constants for easy configuration - these can be changed to customize the app
-pub const HISTORY_SIZE: usize = 30; // How many past CPU readings to keep in memory
+// (polling intervals, history sizes and the window size live in settings.json)
 pub const BAR_HEIGHT: f32 = 24.0; // Height of each progress bar in pixels
 
 // The main entry point of our application
 pub fn main() -> iced::Result {
-    // Check if we're running as administrator (required for hardware monitoring)
-    if !is_admin() {
-        // Show an error message box if not running as admin
+    // Parse command-line options before anything else touches the console
+    let options = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            attach_parent_console();
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if options.help {
+        attach_parent_console();
+        println!("{}", cli::USAGE);
+        return Ok(());
+    }
+
+    // Load settings.json before any collector reads its interval
+    settings::init(options.config.clone());
+
+    // Diagnostics print the collector health and exit
+    if options.diagnose {
+        attach_parent_console();
+        std::process::exit(diagnostics::run());
+    }
+
+    if let Some(address) = options.metrics_address {
+        metrics_exporter::enable(address);
+    }
+
+    if let Some(path) = &options.alerts {
+        match alerts::load_config(path) {
+            Ok(config) => alerts::enable(config),
+            Err(e) => {
+                attach_parent_console();
+                eprintln!("Invalid alert rules: {}", e);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    if let Some(path) = &options.record {
+        if let Err(e) = history::start_recording(path) {
+            attach_parent_console();
+            eprintln!("Failed to open {} for recording: {}", path.display(), e);
+            std::process::exit(1);
+        }
+        history::record(|| history::Record::Cpu(what_cpu_check::get_cpu_info()));
+    }
+
+    // Headless mode prints JSON to stdout and needs neither a window nor admin rights
+    if options.headless {
+        attach_parent_console();
+        if let Err(e) = headless::run(&options) {
+            eprintln!("Headless mode failed: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // A replay reads a file instead of the sensors and needs no admin rights
+    let replaying = options.replay.is_some();
+    if let Some(path) = options.replay.clone() {
+        history::enable_replay(history::Replay { path, speed: options.speed });
+    }
+
+    // Check if we're running as administrator (required for hardware monitoring).
+    // The manifest runs the exe as the invoking user, so only the live window asks
+    // for elevation, by starting itself again through the UAC prompt.
+    if !replaying && !is_admin() {
+        if relaunch_as_admin() {
+            // The elevated copy takes over
+            std::process::exit(0);
+        }
+        // Show an error message box if the elevated copy couldn't be started
         unsafe {
             MessageBoxA(
                 HWND::default(), // Default window handle
-                PCSTR::from_raw(c"This program requires administrator privileges.".as_ptr() as *const u8), // Message text
+                PCSTR::from_raw(c"This program requires administrator privileges to read the hardware sensors.".as_ptr() as *const u8), // Message text
                 PCSTR::from_raw(c"Administrator Required".as_ptr() as *const u8), // Window title
                 MB_ICONERROR, // Error icon
             );
@@ -89,30 +180,33 @@ pub fn main() -> iced::Result {
         icon::from_rgba(rgba, width, height).unwrap()
     };
 
-    // Extract embedded DLLs for GPU monitoring
-    match extract_embedded_dlls() {
-        Ok(_) => {
-            println!("Embedded DLLs extracted successfully");
-        }
-        Err(e) => {
-            eprintln!("Failed to extract embedded DLLs: {}", e);
-            // Continue without GPU monitoring if DLL extraction fails
+    if !replaying {
+        // Extract embedded DLLs for GPU monitoring
+        match extract_embedded_dlls() {
+            Ok(_) => {
+                println!("Embedded DLLs extracted successfully");
+            }
+            Err(e) => {
+                eprintln!("Failed to extract embedded DLLs: {}", e);
+                // Continue without GPU monitoring if DLL extraction fails
+            }
         }
-    }
 
-    // Start collecting user process data
-    crate::user_process_fetch::start_collection();
+        // Start collecting user process data
+        crate::user_process_fetch::start_collection();
+    }
 
-    // Load saved window position
-    let saved_position = crate::utils::load_window_position();
+    // Window size and saved position from settings.json
+    let window = settings::current().window;
 
     // Create and run the Iced application
-    iced::application("CuteMonitor", State::update, State::view)
+    let title = if replaying { "CuteMonitor (replay)" } else { "CuteMonitor" };
+    iced::application(title, State::update, State::view)
         .subscription(State::subscription) // Set up data subscriptions
         .window(iced::window::Settings {
             icon: Some(icon), // Set the window icon
-            size: (940.0, 900.0).into(), // Set initial window size
-            position: saved_position.map_or(iced::window::Position::default(), |(x, y)| iced::window::Position::Specific(iced::Point::new(x as f32, y as f32))),
+            size: (window.width, window.height).into(), // Set initial window size
+            position: window.position().map_or(iced::window::Position::default(), |(x, y)| iced::window::Position::Specific(iced::Point::new(x as f32, y as f32))),
             ..Default::default() // Use default settings for everything else
         })
         .run() // Start the application event loop
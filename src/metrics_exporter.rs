@@ -0,0 +1,829 @@
+//! Prometheus exporter for CuteMonitor.
+//!
+//! When enabled with `--metrics <address>`, a small HTTP server answers
+//! `GET /metrics` with every collected value in the Prometheus text
+//! exposition format. The GUI and headless mode record their latest readings
+//! here; the server renders them on each scrape.
+
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use iced_futures::futures::future;
+use iced_futures::stream;
+use lazy_static::lazy_static;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Semaphore;
+
+use crate::cpu_times::{self, CpuTimesSample};
+use crate::external_sensors::ExternalReading;
+use crate::gpu_data::{GpuData, NO_GPU_MODEL};
+use crate::hardware_checker::HardwareData;
+use crate::interface_stats::NetworkStats;
+use crate::sensors::SensorTree;
+use crate::system_sampler::{self, Overhead};
+
+/// Latest value of every collector, as served on `/metrics`.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsSnapshot {
+    pub hardware: Option<HardwareData>,
+    pub sensors: SensorTree,
+    pub gpus: Vec<GpuData>,
+    pub thread_usages: Vec<f32>,
+    pub thread_frequencies: Vec<u64>,
+    pub cpu_times: Option<CpuTimesSample>,
+    pub network: Option<NetworkStats>,
+    pub external: Vec<ExternalReading>,
+    /// Read from the sampler on every scrape rather than recorded.
+    pub sampler: Overhead,
+}
+
+/// Address the exporter listens on, set once from the command line.
+static LISTEN_ADDRESS: OnceLock<SocketAddr> = OnceLock::new();
+
+lazy_static! {
+    static ref SNAPSHOT: Mutex<MetricsSnapshot> = Mutex::new(MetricsSnapshot::default());
+}
+
+/// Enables the exporter. Recording is a no-op until this is called.
+pub fn enable(address: SocketAddr) {
+    let _ = LISTEN_ADDRESS.set(address);
+}
+
+/// Returns the listen address if the exporter is enabled.
+pub fn listen_address() -> Option<SocketAddr> {
+    LISTEN_ADDRESS.get().copied()
+}
+
+/// Applies `update` to the shared snapshot when the exporter is enabled.
+fn record(update: impl FnOnce(&mut MetricsSnapshot)) {
+    if listen_address().is_some() {
+        update(&mut SNAPSHOT.lock().unwrap());
+    }
+}
+
+pub fn record_hardware(data: &HardwareData) {
+    record(|snapshot| snapshot.hardware = Some(data.clone()));
+}
+
+pub fn record_sensors(tree: &SensorTree) {
+    record(|snapshot| snapshot.sensors = tree.clone());
+}
+
+pub fn record_gpus(gpus: &[GpuData]) {
+    record(|snapshot| snapshot.gpus = gpus.to_vec());
+}
+
+pub fn record_thread_usages(usages: &[f32]) {
+    record(|snapshot| snapshot.thread_usages = usages.to_vec());
+}
+
+pub fn record_frequencies(frequencies_mhz: &[u64]) {
+    record(|snapshot| snapshot.thread_frequencies = frequencies_mhz.to_vec());
+}
+
+pub fn record_cpu_times(sample: &CpuTimesSample) {
+    record(|snapshot| snapshot.cpu_times = Some(sample.clone()));
+}
+
+pub fn record_external(readings: &[ExternalReading]) {
+    record(|snapshot| snapshot.external = readings.to_vec());
+}
+
+pub fn record_network(stats: &NetworkStats) {
+    record(|snapshot| snapshot.network = Some(stats.clone()));
+}
+
+/// Creates an iced subscription that runs the HTTP server in the background.
+/// Produces no messages; returns `Subscription::none()` when the exporter is disabled.
+pub fn subscription<Message: Send + 'static>() -> iced::Subscription<Message> {
+    let Some(address) = listen_address() else {
+        return iced::Subscription::none();
+    };
+
+    let stream = stream::channel(1, move |_sender| async move {
+        serve(address).await;
+        // Keep the subscription alive even if binding failed, so it isn't restarted.
+        future::pending::<()>().await
+    });
+    iced::Subscription::run_with_id("metrics_exporter", stream)
+}
+
+/// Binds `address` and serves `/metrics` until the task is dropped.
+pub async fn serve(address: SocketAddr) {
+    match TcpListener::bind(address).await {
+        Ok(listener) => serve_listener(listener).await,
+        Err(e) => eprintln!("Failed to start metrics exporter on {}: {}", address, e),
+    }
+}
+
+/// Most connections served at once; further clients wait in the accept backlog.
+const MAX_CONNECTIONS: usize = 16;
+
+/// How long a client gets to send its request head before it is disconnected.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Accepts connections on an already bound listener.
+async fn serve_listener(listener: TcpListener) {
+    let slots = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+    loop {
+        // The semaphore is never closed, so acquiring only waits for a free slot
+        let Ok(slot) = slots.clone().acquire_owned().await else {
+            return;
+        };
+        match listener.accept().await {
+            Ok((socket, _)) => {
+                tokio::spawn(async move {
+                    handle_connection(socket, REQUEST_TIMEOUT).await;
+                    drop(slot);
+                });
+            }
+            Err(e) => eprintln!("Metrics exporter failed to accept a connection: {}", e),
+        }
+    }
+}
+
+/// Largest request head (request line and headers) the exporter accepts.
+const MAX_REQUEST_HEAD: usize = 8192;
+
+/// Reads until the blank line that ends the request head. Returns `None` if
+/// the client goes away first or the head grows past `MAX_REQUEST_HEAD`.
+async fn read_request_head(socket: &mut TcpStream) -> Option<String> {
+    let mut head = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        if head.len() > MAX_REQUEST_HEAD {
+            return None;
+        }
+        match socket.read(&mut buffer).await {
+            Ok(0) | Err(_) => return None,
+            Ok(read) => head.extend_from_slice(&buffer[..read]),
+        }
+    }
+    Some(String::from_utf8_lossy(&head).into_owned())
+}
+
+/// Answers a single HTTP request and closes the connection. Clients that
+/// don't send a complete request head within `timeout` are dropped.
+async fn handle_connection(mut socket: TcpStream, timeout: Duration) {
+    let Ok(Some(request)) = tokio::time::timeout(timeout, read_request_head(&mut socket)).await else {
+        return;
+    };
+
+    // Request line: "GET /metrics?query HTTP/1.1"
+    let mut request_line = request
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .split_whitespace();
+    let method = request_line.next().unwrap_or_default();
+    let path = request_line.next().unwrap_or_default();
+    let path = path.split('?').next().unwrap_or_default();
+
+    let (status, body) = match (method, path) {
+        ("GET", "/metrics") => {
+            let mut snapshot = SNAPSHOT.lock().unwrap().clone();
+            snapshot.sampler = system_sampler::overhead();
+            ("200 OK", render(&snapshot))
+        }
+        ("GET", _) => ("404 Not Found", "Not Found\n".to_string()),
+        _ => ("405 Method Not Allowed", "Method Not Allowed\n".to_string()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = socket.write_all(response.as_bytes()).await;
+    let _ = socket.shutdown().await;
+}
+
+/// One sample of a metric family: label pairs and value.
+type Sample = (Vec<(&'static str, String)>, f64);
+
+/// Appends a metric family (HELP, TYPE and samples) to `out`.
+/// Families without samples are left out entirely.
+fn write_family(out: &mut String, name: &str, kind: &str, help: &str, samples: Vec<Sample>) {
+    if samples.is_empty() {
+        return;
+    }
+
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    for (labels, value) in samples {
+        if labels.is_empty() {
+            let _ = writeln!(out, "{} {}", name, value);
+        } else {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(key, value)| format!("{}=\"{}\"", key, escape_label(value)))
+                .collect();
+            let _ = writeln!(out, "{}{{{}}} {}", name, labels.join(","), value);
+        }
+    }
+}
+
+/// Escapes a label value as required by the text exposition format.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// A single unlabelled sample, if the value is present.
+fn single(value: Option<f32>) -> Vec<Sample> {
+    value
+        .map(|value| (Vec::new(), value as f64))
+        .into_iter()
+        .collect()
+}
+
+/// One sample per GPU that reports the value, labelled with its index and model.
+/// The UI's "No GPU detected" placeholder is not a device and is left out.
+fn per_gpu(gpus: &[GpuData], value: impl Fn(&GpuData) -> Option<f64>) -> Vec<Sample> {
+    gpus.iter()
+        .enumerate()
+        .filter(|(_, gpu)| gpu.model != NO_GPU_MODEL)
+        .filter_map(|(index, gpu)| {
+            let labels = vec![("gpu", index.to_string()), ("model", gpu.model.clone())];
+            value(gpu).map(|value| (labels, value))
+        })
+        .collect()
+}
+
+/// Renders the snapshot in the Prometheus text exposition format.
+pub fn render(snapshot: &MetricsSnapshot) -> String {
+    let mut out = String::new();
+
+    if let Some(hardware) = &snapshot.hardware {
+        write_family(
+            &mut out,
+            "cutemonitor_cpu_temperature_celsius",
+            "gauge",
+            "CPU temperature.",
+            single(Some(hardware.cpu_temp)),
+        );
+        let ccds = hardware
+            .ccd_temperatures
+            .iter()
+            .enumerate()
+            .filter_map(|(i, temp)| {
+                temp.map(|temp| (vec![("ccd", (i + 1).to_string())], temp as f64))
+            })
+            .collect();
+        write_family(
+            &mut out,
+            "cutemonitor_ccd_temperature_celsius",
+            "gauge",
+            "Temperature of each CPU CCD.",
+            ccds,
+        );
+        write_family(
+            &mut out,
+            "cutemonitor_cpu_voltage_volts",
+            "gauge",
+            "CPU core voltage.",
+            single(hardware.cpu_voltage),
+        );
+        write_family(
+            &mut out,
+            "cutemonitor_cpu_power_watts",
+            "gauge",
+            "CPU package power.",
+            single(hardware.cpu_power),
+        );
+        write_family(
+            &mut out,
+            "cutemonitor_chipset_temperature_celsius",
+            "gauge",
+            "Chipset temperature.",
+            single(hardware.chipset_temp),
+        );
+        write_family(
+            &mut out,
+            "cutemonitor_memory_usage_percent",
+            "gauge",
+            "System memory in use.",
+            single(Some(hardware.memory_usage)),
+        );
+        write_family(
+            &mut out,
+            "cutemonitor_memory_temperature_celsius",
+            "gauge",
+            "Memory (DIMM) temperature.",
+            single(hardware.memory_temp),
+        );
+        let per_fan = |value: &dyn Fn(&crate::fans::FanReading) -> Option<f32>| -> Vec<Sample> {
+            hardware
+                .fans
+                .iter()
+                .filter_map(|fan| {
+                    let labels = vec![("fan", fan.id.clone()), ("name", fan.name.clone())];
+                    value(fan).map(|value| (labels, value as f64))
+                })
+                .collect()
+        };
+        write_family(
+            &mut out,
+            "cutemonitor_fan_speed_rpm",
+            "gauge",
+            "Speed of each fan or pump.",
+            per_fan(&|fan| fan.rpm),
+        );
+        write_family(
+            &mut out,
+            "cutemonitor_fan_duty_percent",
+            "gauge",
+            "PWM duty cycle of each fan or pump.",
+            per_fan(&|fan| fan.duty_percent),
+        );
+        write_family(
+            &mut out,
+            "cutemonitor_memory_total_megabytes",
+            "gauge",
+            "Installed system memory.",
+            single(Some(hardware.total_memory_mb as f32)),
+        );
+        write_family(
+            &mut out,
+            "cutemonitor_memory_speed_mts",
+            "gauge",
+            "Memory speed in MT/s.",
+            single(Some(hardware.memory_speed_mts as f32)),
+        );
+    }
+
+    // Every LibreHardwareMonitor sensor, for values without a dedicated family
+    let sensors = snapshot
+        .sensors
+        .sensors
+        .iter()
+        .filter_map(|sensor| {
+            let labels = vec![
+                ("id", sensor.id.clone()),
+                ("hardware", sensor.hardware_name.clone()),
+                ("hardware_type", sensor.hardware_type.clone()),
+                ("type", sensor.sensor_type.clone()),
+                ("name", sensor.name.clone()),
+            ];
+            sensor.value.map(|value| (labels, value as f64))
+        })
+        .collect();
+    write_family(
+        &mut out,
+        "cutemonitor_sensor_value",
+        "gauge",
+        "Raw value of each hardware sensor, in the unit of its type.",
+        sensors,
+    );
+
+    let external = snapshot
+        .external
+        .iter()
+        .map(|reading| {
+            let labels = vec![
+                ("source", reading.source.clone()),
+                ("id", reading.id.clone()),
+                ("label", reading.label.clone()),
+                ("unit", reading.unit.clone()),
+            ];
+            (labels, reading.value)
+        })
+        .collect();
+    write_family(
+        &mut out,
+        "cutemonitor_external_sensor_value",
+        "gauge",
+        "Latest sample of each external sensor command.",
+        external,
+    );
+
+    if !snapshot.thread_usages.is_empty() {
+        let total =
+            snapshot.thread_usages.iter().sum::<f32>() / snapshot.thread_usages.len() as f32;
+        write_family(
+            &mut out,
+            "cutemonitor_cpu_usage_percent",
+            "gauge",
+            "Average usage over all logical CPUs.",
+            single(Some(total)),
+        );
+        let threads = snapshot
+            .thread_usages
+            .iter()
+            .enumerate()
+            .map(|(i, usage)| (vec![("thread", i.to_string())], *usage as f64))
+            .collect();
+        write_family(
+            &mut out,
+            "cutemonitor_cpu_thread_usage_percent",
+            "gauge",
+            "Usage of each logical CPU.",
+            threads,
+        );
+    }
+
+    let frequencies = snapshot
+        .thread_frequencies
+        .iter()
+        .enumerate()
+        .filter(|(_, mhz)| **mhz > 0)
+        .map(|(i, mhz)| (vec![("thread", i.to_string())], *mhz as f64))
+        .collect();
+    write_family(
+        &mut out,
+        "cutemonitor_cpu_thread_frequency_mhz",
+        "gauge",
+        "Current clock of each logical CPU.",
+        frequencies,
+    );
+
+    if let Some(sample) = &snapshot.cpu_times {
+        let total = cpu_times::STATES
+            .iter()
+            .zip(sample.total.parts())
+            .map(|(state, share)| (vec![("state", state.to_string())], share as f64))
+            .collect();
+        write_family(
+            &mut out,
+            "cutemonitor_cpu_time_percent",
+            "gauge",
+            "Share of CPU time spent in each state over all logical CPUs.",
+            total,
+        );
+        let per_cpu = sample
+            .per_cpu
+            .iter()
+            .enumerate()
+            .flat_map(|(cpu, breakdown)| {
+                cpu_times::STATES
+                    .iter()
+                    .zip(breakdown.parts())
+                    .map(move |(state, share)| {
+                        (
+                            vec![("cpu", cpu.to_string()), ("state", state.to_string())],
+                            share as f64,
+                        )
+                    })
+            })
+            .collect();
+        write_family(
+            &mut out,
+            "cutemonitor_cpu_thread_time_percent",
+            "gauge",
+            "Share of CPU time spent in each state on each logical CPU.",
+            per_cpu,
+        );
+    }
+
+    // Cost of the shared sysinfo sampler itself
+    let sampler = &snapshot.sampler;
+    let per_part = |value: fn(&crate::system_sampler::PartOverhead) -> f64| -> Vec<Sample> {
+        [("cpu", &sampler.cpu), ("processes", &sampler.processes)]
+            .into_iter()
+            .filter(|(_, part)| part.refreshes > 0)
+            .map(|(name, part)| (vec![("part", name.to_string())], value(part)))
+            .collect()
+    };
+    write_family(
+        &mut out,
+        "cutemonitor_sampler_refreshes_total",
+        "counter",
+        "Refreshes of the shared CPU and process sampler.",
+        per_part(|part| part.refreshes as f64),
+    );
+    write_family(
+        &mut out,
+        "cutemonitor_sampler_refresh_seconds_total",
+        "counter",
+        "Time the shared sampler spent refreshing.",
+        per_part(|part| part.total_us as f64 / 1_000_000.0),
+    );
+
+    let gpus = &snapshot.gpus;
+    write_family(
+        &mut out,
+        "cutemonitor_gpu_utilization_percent",
+        "gauge",
+        "GPU core utilization.",
+        per_gpu(gpus, |gpu| gpu.utilization.map(f64::from)),
+    );
+    write_family(
+        &mut out,
+        "cutemonitor_gpu_temperature_celsius",
+        "gauge",
+        "GPU temperature.",
+        per_gpu(gpus, |gpu| gpu.temp.map(f64::from)),
+    );
+    write_family(
+        &mut out,
+        "cutemonitor_gpu_memory_usage_percent",
+        "gauge",
+        "GPU VRAM in use.",
+        per_gpu(gpus, |gpu| gpu.memory_usage.map(f64::from)),
+    );
+    write_family(
+        &mut out,
+        "cutemonitor_gpu_memory_total_megabytes",
+        "gauge",
+        "GPU VRAM size.",
+        per_gpu(gpus, |gpu| Some(gpu.vram_mb as f64)),
+    );
+    write_family(
+        &mut out,
+        "cutemonitor_gpu_encoder_percent",
+        "gauge",
+        "GPU video encoder load.",
+        per_gpu(gpus, |gpu| gpu.encoder.map(f64::from)),
+    );
+    write_family(
+        &mut out,
+        "cutemonitor_gpu_decoder_percent",
+        "gauge",
+        "GPU video decoder load.",
+        per_gpu(gpus, |gpu| gpu.decoder.map(f64::from)),
+    );
+
+    if let Some(network) = &snapshot.network {
+        let per_interface =
+            |value: &dyn Fn(&crate::interface_stats::InterfaceStats) -> f64| -> Vec<Sample> {
+                network
+                    .interfaces
+                    .iter()
+                    .map(|interface| {
+                        (
+                            vec![("interface", interface.name.clone())],
+                            value(interface),
+                        )
+                    })
+                    .collect()
+            };
+        write_family(
+            &mut out,
+            "cutemonitor_network_receive_bytes_per_second",
+            "gauge",
+            "Download rate of each network interface.",
+            per_interface(&|i| i.download_bps),
+        );
+        write_family(
+            &mut out,
+            "cutemonitor_network_transmit_bytes_per_second",
+            "gauge",
+            "Upload rate of each network interface.",
+            per_interface(&|i| i.upload_bps),
+        );
+        write_family(
+            &mut out,
+            "cutemonitor_network_receive_bytes_total",
+            "counter",
+            "Bytes received by each network interface.",
+            per_interface(&|i| i.total_bytes_received as f64),
+        );
+        write_family(
+            &mut out,
+            "cutemonitor_network_transmit_bytes_total",
+            "counter",
+            "Bytes sent by each network interface.",
+            per_interface(&|i| i.total_bytes_sent as f64),
+        );
+        write_family(
+            &mut out,
+            "cutemonitor_network_receive_packets_total",
+            "counter",
+            "Packets received by each network interface.",
+            per_interface(&|i| i.packets_received as f64),
+        );
+        write_family(
+            &mut out,
+            "cutemonitor_network_transmit_packets_total",
+            "counter",
+            "Packets sent by each network interface.",
+            per_interface(&|i| i.packets_sent as f64),
+        );
+        write_family(
+            &mut out,
+            "cutemonitor_network_receive_errors_total",
+            "counter",
+            "Receive errors on each network interface.",
+            per_interface(&|i| i.errors_received as f64),
+        );
+        write_family(
+            &mut out,
+            "cutemonitor_network_transmit_errors_total",
+            "counter",
+            "Transmit errors on each network interface.",
+            per_interface(&|i| i.errors_sent as f64),
+        );
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface_stats::InterfaceStats;
+    use crate::cpu_times::CpuBreakdown;
+
+    fn hardware() -> HardwareData {
+        serde_json::from_str(
+            r#"{"MotherboardModel":"Test Board","CpuTemp":55.5,"CcdTemperatures":[50.0,null,52.0],
+                "CpuVoltage":1.2,"CpuPower":null,"ChipsetTemp":40.0,"MemoryUsage":42.0,"MemoryTemp":38.5,
+                "TotalMemoryMB":32768,"MemorySpeedMTS":6000,
+                "Fans":[{"Id":"nct6798-nct6775.656/fan2","Name":"Pump","Rpm":2400.0,"DutyPercent":null}]}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_render_hardware_metrics() {
+        let snapshot = MetricsSnapshot {
+            hardware: Some(hardware()),
+            sensors: SensorTree {
+                sensors: vec![crate::sensors::Sensor {
+                    hardware_id: "/lpc/nct6798d".to_string(),
+                    hardware_name: "Nuvoton NCT6798D".to_string(),
+                    hardware_type: "SuperIO".to_string(),
+                    id: "/lpc/nct6798d/fan/1".to_string(),
+                    sensor_type: "Fan".to_string(),
+                    name: "Fan #2".to_string(),
+                    value: Some(1150.0),
+                    min: None,
+                    max: None,
+                }],
+            },
+            ..Default::default()
+        };
+        let out = render(&snapshot);
+
+        assert!(out.contains("# TYPE cutemonitor_cpu_temperature_celsius gauge\ncutemonitor_cpu_temperature_celsius 55.5\n"));
+        assert!(out.contains("cutemonitor_ccd_temperature_celsius{ccd=\"1\"} 50\n"));
+        assert!(!out.contains("ccd=\"2\""));
+        assert!(out.contains("cutemonitor_ccd_temperature_celsius{ccd=\"3\"} 52\n"));
+        // Missing readings produce no family at all
+        assert!(!out.contains("cutemonitor_cpu_power_watts"));
+        assert!(out.contains("cutemonitor_memory_total_megabytes 32768\n"));
+        assert!(out.contains("cutemonitor_memory_temperature_celsius 38.5\n"));
+        assert!(out.contains("cutemonitor_fan_speed_rpm{fan=\"nct6798-nct6775.656/fan2\",name=\"Pump\"} 2400\n"));
+        assert!(!out.contains("cutemonitor_fan_duty_percent{"));
+        assert!(out.contains(
+            "cutemonitor_sensor_value{id=\"/lpc/nct6798d/fan/1\",hardware=\"Nuvoton NCT6798D\",\
+             hardware_type=\"SuperIO\",type=\"Fan\",name=\"Fan #2\"} 1150\n"
+        ));
+    }
+
+    #[test]
+    fn test_render_no_gpu_detected() {
+        let snapshot = MetricsSnapshot {
+            gpus: vec![GpuData::default()],
+            ..Default::default()
+        };
+        assert!(!render(&snapshot).contains("cutemonitor_gpu_"));
+    }
+
+    #[test]
+    fn test_render_gpu_and_network_labels() {
+        let gpu = GpuData {
+            model: "Radeon \"Pro\"".to_string(),
+            utilization: Some(75.0),
+            ..Default::default()
+        };
+        let snapshot = MetricsSnapshot {
+            gpus: vec![GpuData::default(), gpu],
+            thread_usages: vec![10.0, 30.0],
+            thread_frequencies: vec![4650, 0],
+            cpu_times: Some(CpuTimesSample {
+                total: CpuBreakdown {
+                    user: 30.0,
+                    steal: 2.5,
+                    ..Default::default()
+                },
+                per_cpu: vec![CpuBreakdown::default(), CpuBreakdown {
+                    iowait: 12.0,
+                    ..Default::default()
+                }],
+            }),
+            sampler: Overhead {
+                cpu: crate::system_sampler::PartOverhead {
+                    refreshes: 4,
+                    last_us: 250,
+                    total_us: 1_500_000,
+                },
+                ..Default::default()
+            },
+            network: Some(NetworkStats {
+                interfaces: vec![InterfaceStats {
+                    name: "eth0".to_string(),
+                    upload_bps: 100.0,
+                    download_bps: 200.0,
+                    total_bytes_sent: 5,
+                    total_bytes_received: 6,
+                    packets_sent: 7,
+                    packets_received: 8,
+                    errors_sent: 0,
+                    errors_received: 1,
+                }],
+                dropped_interfaces: Vec::new(),
+            }),
+            external: vec![ExternalReading {
+                source: "arduino".to_string(),
+                id: "room".to_string(),
+                label: "Room".to_string(),
+                unit: "°C".to_string(),
+                value: 21.5,
+            }],
+            ..Default::default()
+        };
+        let out = render(&snapshot);
+
+        assert!(out.contains(
+            "cutemonitor_gpu_utilization_percent{gpu=\"1\",model=\"Radeon \\\"Pro\\\"\"} 75\n"
+        ));
+        assert!(!out.contains("gpu=\"0\""));
+        assert!(out.contains(
+            "cutemonitor_gpu_memory_total_megabytes{gpu=\"1\",model=\"Radeon \\\"Pro\\\"\"} 0\n"
+        ));
+        assert!(out.contains("cutemonitor_cpu_usage_percent 20\n"));
+        assert!(out.contains("cutemonitor_cpu_thread_usage_percent{thread=\"1\"} 30\n"));
+        assert!(out.contains("cutemonitor_cpu_thread_frequency_mhz{thread=\"0\"} 4650\n"));
+        assert!(!out.contains("cutemonitor_cpu_thread_frequency_mhz{thread=\"1\"}"));
+        assert!(out.contains("cutemonitor_cpu_time_percent{state=\"user\"} 30\n"));
+        assert!(out.contains("cutemonitor_cpu_time_percent{state=\"steal\"} 2.5\n"));
+        assert!(out.contains("cutemonitor_cpu_thread_time_percent{cpu=\"1\",state=\"iowait\"} 12\n"));
+        assert!(out.contains("cutemonitor_sampler_refreshes_total{part=\"cpu\"} 4\n"));
+        assert!(out.contains("cutemonitor_sampler_refresh_seconds_total{part=\"cpu\"} 1.5\n"));
+        assert!(!out.contains("part=\"processes\""));
+        assert!(out.contains("# TYPE cutemonitor_network_receive_bytes_total counter\n"));
+        assert!(
+            out.contains("cutemonitor_network_receive_bytes_per_second{interface=\"eth0\"} 200\n")
+        );
+        assert!(out.contains("cutemonitor_network_receive_errors_total{interface=\"eth0\"} 1\n"));
+        assert!(out.contains(
+            "cutemonitor_external_sensor_value{source=\"arduino\",id=\"room\",label=\"Room\",unit=\"°C\"} 21.5\n"
+        ));
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label("a\\b\"c\nd"), "a\\\\b\\\"c\\nd");
+    }
+
+    #[tokio::test]
+    async fn test_http_endpoint() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(serve_listener(listener));
+
+        let request = |request: &'static str| async move {
+            let mut socket = TcpStream::connect(address).await.unwrap();
+            socket.write_all(request.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            socket.read_to_string(&mut response).await.unwrap();
+            response
+        };
+
+        let metrics = request("GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n").await;
+        assert!(metrics.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(metrics.contains("Content-Type: text/plain; version=0.0.4"));
+
+        let missing = request("GET /other HTTP/1.1\r\n\r\n").await;
+        assert!(missing.starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+        let post = request("POST /metrics HTTP/1.1\r\n\r\n").await;
+        assert!(post.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+
+        // A request line split across segments is still routed on the full path
+        let mut socket = TcpStream::connect(address).await.unwrap();
+        for part in ["GET /met", "rics HTTP/1.1\r\nHost: local", "host\r\n\r\n"] {
+            socket.write_all(part.as_bytes()).await.unwrap();
+            socket.flush().await.unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        let mut response = String::new();
+        socket.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+    }
+
+    #[tokio::test]
+    async fn test_idle_client_is_disconnected() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            handle_connection(socket, Duration::from_millis(50)).await;
+        });
+
+        // Send half a request line and then nothing
+        let mut socket = TcpStream::connect(address).await.unwrap();
+        socket.write_all(b"GET /met").await.unwrap();
+        let mut response = String::new();
+        let read = tokio::time::timeout(Duration::from_secs(5), socket.read_to_string(&mut response)).await;
+        assert!(matches!(read, Ok(Ok(0))), "{:?}", read);
+        server.await.unwrap();
+    }
+}
@@ -0,0 +1,660 @@
+//! Actions on a process from the process table: terminate it, change its
+//! priority and set its CPU affinity.
+//!
+//! The platform calls sit behind `ProcessControl`. On Linux they go through
+//! `kill`, `renice` and `taskset`, like the supervisor's `kill` call; on
+//! Windows `taskkill` asks a process to close and the Win32 process API does
+//! the rest. Terminating first asks the process to exit and only forces it
+//! if it is still running after `TERMINATE_GRACE`.
+//!
+//! An action names its process by PID and start time. A process that started
+//! at another time has reused the PID, and is left alone.
+
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use crate::what_cpu_check::ProcessInfo;
+
+/// How long a process may take to exit before it is killed.
+const TERMINATE_GRACE: Duration = Duration::from_secs(3);
+
+/// How often a terminating process is checked.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Scheduling priority, mapped to a nice value on Linux and a priority class on Windows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    Low,
+    BelowNormal,
+    Normal,
+    AboveNormal,
+    High,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 5] = [
+        Priority::Low,
+        Priority::BelowNormal,
+        Priority::Normal,
+        Priority::AboveNormal,
+        Priority::High,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Priority::Low => "Low",
+            Priority::BelowNormal => "Below normal",
+            Priority::Normal => "Normal",
+            Priority::AboveNormal => "Above normal",
+            Priority::High => "High",
+        }
+    }
+
+    /// Nice value on Linux (raising the priority above normal needs root).
+    #[cfg(target_os = "linux")]
+    pub fn nice(self) -> i32 {
+        match self {
+            Priority::Low => 19,
+            Priority::BelowNormal => 10,
+            Priority::Normal => 0,
+            Priority::AboveNormal => -5,
+            Priority::High => -10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProcessAction {
+    Terminate,
+    SetPriority(Priority),
+    /// Logical CPUs the process may run on
+    SetAffinity(Vec<usize>),
+}
+
+impl ProcessAction {
+    /// What the action does, for the confirmation, e.g. "set the priority of firefox (42) to Low"
+    pub fn describe(&self, name: &str, pid: u32) -> String {
+        match self {
+            ProcessAction::Terminate => format!("end {} ({})", name, pid),
+            ProcessAction::SetPriority(priority) => {
+                format!(
+                    "set the priority of {} ({}) to {}",
+                    name,
+                    pid,
+                    priority.title()
+                )
+            }
+            ProcessAction::SetAffinity(cpus) => {
+                let cpus: Vec<String> = cpus.iter().map(|cpu| cpu.to_string()).collect();
+                format!("run {} ({}) only on CPUs {}", name, pid, cpus.join(","))
+            }
+        }
+    }
+}
+
+/// The process an action is meant for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Target {
+    pub pid: u32,
+    /// Seconds since the Unix epoch, as `ProcessInfo::start_time`
+    pub start_time: u64,
+}
+
+impl Target {
+    pub fn of(process: &ProcessInfo) -> Self {
+        Self {
+            pid: process.pid,
+            start_time: process.start_time,
+        }
+    }
+}
+
+/// How a terminated process went away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    /// It exited when asked to
+    Exited,
+    /// It was still running after the grace period and was killed
+    Killed,
+}
+
+/// The platform calls behind the process actions.
+pub trait ProcessControl {
+    /// Asks the process to exit (SIGTERM on Linux, WM_CLOSE on Windows).
+    fn request_exit(&self, pid: u32) -> Result<(), String>;
+    /// Ends the process without giving it a chance to clean up.
+    fn kill(&self, pid: u32) -> Result<(), String>;
+    fn is_running(&self, pid: u32) -> bool;
+    /// When the process started, in seconds since the Unix epoch.
+    fn start_time(&self, pid: u32) -> Option<u64>;
+    fn set_priority(&self, pid: u32, priority: Priority) -> Result<(), String>;
+    fn set_affinity(&self, pid: u32, cpus: &[usize]) -> Result<(), String>;
+}
+
+/// Whether the target still runs under its PID.
+fn is_running(control: &dyn ProcessControl, target: Target) -> bool {
+    control.is_running(target.pid) && control.start_time(target.pid) == Some(target.start_time)
+}
+
+/// Asks the process to exit and kills it if it is still running after `grace`.
+pub fn terminate(
+    control: &dyn ProcessControl,
+    target: Target,
+    grace: Duration,
+) -> Result<Termination, String> {
+    let pid = target.pid;
+    // Some processes (e.g. console programs on Windows) can't be asked, only killed
+    if control.request_exit(pid).is_ok() {
+        let deadline = Instant::now() + grace;
+        while Instant::now() < deadline {
+            if !is_running(control, target) {
+                return Ok(Termination::Exited);
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+    // The PID may have been reused while waiting
+    if !is_running(control, target) {
+        return Ok(Termination::Exited);
+    }
+    control.kill(pid)?;
+    Ok(Termination::Killed)
+}
+
+/// Runs an action and describes the outcome for the status line.
+pub fn run(
+    control: &dyn ProcessControl,
+    target: Target,
+    action: &ProcessAction,
+) -> Result<String, String> {
+    let pid = target.pid;
+    if pid == std::process::id() {
+        return Err("Refusing to act on CuteMonitor itself".to_string());
+    }
+    if !is_running(control, target) {
+        return Err(format!("Process {} is no longer running", pid));
+    }
+    match action {
+        ProcessAction::Terminate => match terminate(control, target, TERMINATE_GRACE)? {
+            Termination::Exited => Ok(format!("Process {} exited", pid)),
+            Termination::Killed => Ok(format!("Process {} did not exit and was killed", pid)),
+        },
+        ProcessAction::SetPriority(priority) => {
+            control.set_priority(pid, *priority)?;
+            Ok(format!(
+                "Process {} now runs at {} priority",
+                pid,
+                priority.title()
+            ))
+        }
+        ProcessAction::SetAffinity(cpus) => {
+            if cpus.is_empty() {
+                return Err("Select at least one CPU".to_string());
+            }
+            control.set_affinity(pid, cpus)?;
+            Ok(format!("Process {} now runs on {} CPU(s)", pid, cpus.len()))
+        }
+    }
+}
+
+/// Runs an action on a blocking thread with the platform's `ProcessControl`.
+pub async fn perform(target: Target, action: ProcessAction) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || run(&platform(), target, &action))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Runs a command and turns a failure into its error output.
+#[cfg(target_os = "linux")]
+fn run_command(program: &str, args: &[String]) -> Result<(), String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("{}: {}", program, e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(format!("{}: {}", program, stderr.trim()))
+    }
+}
+
+/// Fields of `/proc/[pid]/stat` after the command name, starting with the state.
+#[cfg(target_os = "linux")]
+fn stat_fields(pid: u32) -> Option<Vec<String>> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    Some(
+        stat.rsplit_once(')')?
+            .1
+            .split_whitespace()
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+/// Boot time in seconds since the Unix epoch, from the `btime` line of `/proc/stat`.
+#[cfg(target_os = "linux")]
+fn boot_time() -> Option<u64> {
+    static BOOT_TIME: std::sync::OnceLock<Option<u64>> = std::sync::OnceLock::new();
+    *BOOT_TIME.get_or_init(|| {
+        std::fs::read_to_string("/proc/stat")
+            .ok()?
+            .lines()
+            .find_map(|line| line.strip_prefix("btime"))?
+            .trim()
+            .parse()
+            .ok()
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn platform() -> LinuxProcessControl {
+    LinuxProcessControl
+}
+
+/// `kill`, `renice` and `taskset`, with `/proc` telling whether a process
+/// still runs and when it started.
+#[cfg(target_os = "linux")]
+pub struct LinuxProcessControl;
+
+#[cfg(target_os = "linux")]
+impl ProcessControl for LinuxProcessControl {
+    fn request_exit(&self, pid: u32) -> Result<(), String> {
+        run_command("kill", &["-TERM".to_string(), pid.to_string()])
+    }
+
+    fn kill(&self, pid: u32) -> Result<(), String> {
+        run_command("kill", &["-KILL".to_string(), pid.to_string()])
+    }
+
+    fn is_running(&self, pid: u32) -> bool {
+        // A process that exited but wasn't reaped yet is a zombie (Z) or dead (X)
+        stat_fields(pid)
+            .and_then(|fields| fields.first().map(|state| state != "Z" && state != "X"))
+            .unwrap_or(false)
+    }
+
+    fn start_time(&self, pid: u32) -> Option<u64> {
+        // Field 22 counts clock ticks (USER_HZ) since boot. Rounded down to
+        // seconds like sysinfo does, so it compares equal to `ProcessInfo::start_time`.
+        const USER_HZ: u64 = 100;
+        let ticks: u64 = stat_fields(pid)?.get(19)?.parse().ok()?;
+        Some(boot_time()? + ticks / USER_HZ)
+    }
+
+    fn set_priority(&self, pid: u32, priority: Priority) -> Result<(), String> {
+        run_command(
+            "renice",
+            &[
+                "-n".to_string(),
+                priority.nice().to_string(),
+                "-p".to_string(),
+                pid.to_string(),
+            ],
+        )
+    }
+
+    fn set_affinity(&self, pid: u32, cpus: &[usize]) -> Result<(), String> {
+        let list: Vec<String> = cpus.iter().map(|cpu| cpu.to_string()).collect();
+        run_command(
+            "taskset",
+            &[
+                "-p".to_string(),
+                "-c".to_string(),
+                list.join(","),
+                pid.to_string(),
+            ],
+        )
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn platform() -> WindowsProcessControl {
+    WindowsProcessControl
+}
+
+/// `taskkill` to ask a process to close, the Win32 process API for the rest.
+#[cfg(target_os = "windows")]
+pub struct WindowsProcessControl;
+
+#[cfg(target_os = "windows")]
+impl WindowsProcessControl {
+    /// Runs `f` on a handle to the process opened with `access`.
+    fn with_process<T>(
+        pid: u32,
+        access: windows::Win32::System::Threading::PROCESS_ACCESS_RIGHTS,
+        f: impl FnOnce(windows::Win32::Foundation::HANDLE) -> windows::core::Result<T>,
+    ) -> Result<T, String> {
+        use windows::Win32::Foundation::CloseHandle;
+        use windows::Win32::System::Threading::OpenProcess;
+
+        unsafe {
+            let handle =
+                OpenProcess(access, false, pid).map_err(|e| format!("Process {}: {}", pid, e))?;
+            let result = f(handle);
+            let _ = CloseHandle(handle);
+            result.map_err(|e| format!("Process {}: {}", pid, e))
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl ProcessControl for WindowsProcessControl {
+    fn request_exit(&self, pid: u32) -> Result<(), String> {
+        use std::os::windows::process::CommandExt;
+
+        // Without /f taskkill posts WM_CLOSE to the process's windows
+        let output = Command::new("taskkill")
+            .args(["/pid", &pid.to_string()])
+            .creation_flags(0x08000000) // CREATE_NO_WINDOW
+            .output()
+            .map_err(|e| format!("taskkill: {}", e))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(format!(
+                "taskkill: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+        }
+    }
+
+    fn kill(&self, pid: u32) -> Result<(), String> {
+        use windows::Win32::System::Threading::{TerminateProcess, PROCESS_TERMINATE};
+
+        Self::with_process(pid, PROCESS_TERMINATE, |handle| unsafe {
+            TerminateProcess(handle, 1)
+        })
+    }
+
+    fn is_running(&self, pid: u32) -> bool {
+        use windows::Win32::System::Threading::{
+            GetExitCodeProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+        };
+
+        // Exit code reported while the process hasn't exited
+        const STILL_ACTIVE: u32 = 259;
+        Self::with_process(pid, PROCESS_QUERY_LIMITED_INFORMATION, |handle| {
+            let mut code = 0u32;
+            unsafe { GetExitCodeProcess(handle, &mut code) }.map(|_| code == STILL_ACTIVE)
+        })
+        .unwrap_or(false)
+    }
+
+    fn start_time(&self, pid: u32) -> Option<u64> {
+        use windows::Win32::Foundation::FILETIME;
+        use windows::Win32::System::Threading::{
+            GetProcessTimes, PROCESS_QUERY_LIMITED_INFORMATION,
+        };
+
+        // Seconds between the Windows epoch (1601) and the Unix epoch
+        const EPOCH_OFFSET: u64 = 11_644_473_600;
+        let creation = Self::with_process(pid, PROCESS_QUERY_LIMITED_INFORMATION, |handle| {
+            let [mut creation, mut exit, mut kernel, mut user] = [FILETIME::default(); 4];
+            unsafe { GetProcessTimes(handle, &mut creation, &mut exit, &mut kernel, &mut user) }
+                .map(|_| creation)
+        })
+        .ok()?;
+        // 100 ns intervals, truncated to seconds like `ProcessInfo::start_time`
+        let intervals =
+            (u64::from(creation.dwHighDateTime) << 32) | u64::from(creation.dwLowDateTime);
+        (intervals / 10_000_000).checked_sub(EPOCH_OFFSET)
+    }
+
+    fn set_priority(&self, pid: u32, priority: Priority) -> Result<(), String> {
+        use windows::Win32::System::Threading::{
+            SetPriorityClass, ABOVE_NORMAL_PRIORITY_CLASS, BELOW_NORMAL_PRIORITY_CLASS,
+            HIGH_PRIORITY_CLASS, IDLE_PRIORITY_CLASS, NORMAL_PRIORITY_CLASS,
+            PROCESS_SET_INFORMATION,
+        };
+
+        let class = match priority {
+            Priority::Low => IDLE_PRIORITY_CLASS,
+            Priority::BelowNormal => BELOW_NORMAL_PRIORITY_CLASS,
+            Priority::Normal => NORMAL_PRIORITY_CLASS,
+            Priority::AboveNormal => ABOVE_NORMAL_PRIORITY_CLASS,
+            Priority::High => HIGH_PRIORITY_CLASS,
+        };
+        Self::with_process(pid, PROCESS_SET_INFORMATION, |handle| unsafe {
+            SetPriorityClass(handle, class)
+        })
+    }
+
+    fn set_affinity(&self, pid: u32, cpus: &[usize]) -> Result<(), String> {
+        use windows::Win32::System::Threading::{SetProcessAffinityMask, PROCESS_SET_INFORMATION};
+
+        // The mask only covers the process's processor group (64 CPUs)
+        if let Some(cpu) = cpus.iter().find(|&&cpu| cpu >= usize::BITS as usize) {
+            return Err(format!(
+                "CPU {} is outside the process's processor group",
+                cpu
+            ));
+        }
+        let mask = cpus.iter().fold(0usize, |mask, cpu| mask | (1 << cpu));
+        Self::with_process(pid, PROCESS_SET_INFORMATION, |handle| unsafe {
+            SetProcessAffinityMask(handle, mask)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// Records calls; the process exits on request only if `obeys` is set.
+    struct FakeControl {
+        obeys: bool,
+        running: RefCell<bool>,
+        calls: RefCell<Vec<&'static str>>,
+    }
+
+    impl ProcessControl for FakeControl {
+        fn request_exit(&self, _pid: u32) -> Result<(), String> {
+            self.calls.borrow_mut().push("request_exit");
+            if self.obeys {
+                *self.running.borrow_mut() = false;
+            }
+            Ok(())
+        }
+
+        fn kill(&self, _pid: u32) -> Result<(), String> {
+            self.calls.borrow_mut().push("kill");
+            *self.running.borrow_mut() = false;
+            Ok(())
+        }
+
+        fn is_running(&self, _pid: u32) -> bool {
+            *self.running.borrow()
+        }
+
+        fn start_time(&self, _pid: u32) -> Option<u64> {
+            self.running.borrow().then_some(STARTED)
+        }
+
+        fn set_priority(&self, _pid: u32, _priority: Priority) -> Result<(), String> {
+            Ok(())
+        }
+
+        fn set_affinity(&self, _pid: u32, _cpus: &[usize]) -> Result<(), String> {
+            self.calls.borrow_mut().push("set_affinity");
+            Ok(())
+        }
+    }
+
+    const STARTED: u64 = 1_700_000_000;
+
+    const TARGET: Target = Target {
+        pid: 42,
+        start_time: STARTED,
+    };
+
+    fn fake(obeys: bool) -> FakeControl {
+        FakeControl {
+            obeys,
+            running: RefCell::new(true),
+            calls: RefCell::new(Vec::new()),
+        }
+    }
+
+    #[test]
+    fn test_terminate_is_graceful_first() {
+        let control = fake(true);
+        assert_eq!(
+            terminate(&control, TARGET, Duration::from_millis(300)),
+            Ok(Termination::Exited)
+        );
+        assert_eq!(*control.calls.borrow(), vec!["request_exit"]);
+
+        let control = fake(false);
+        assert_eq!(
+            terminate(&control, TARGET, Duration::from_millis(300)),
+            Ok(Termination::Killed)
+        );
+        assert_eq!(*control.calls.borrow(), vec!["request_exit", "kill"]);
+    }
+
+    #[test]
+    fn test_affinity_needs_a_cpu() {
+        let control = fake(true);
+        assert!(run(&control, TARGET, &ProcessAction::SetAffinity(Vec::new())).is_err());
+        assert!(control.calls.borrow().is_empty());
+        assert_eq!(
+            ProcessAction::SetAffinity(vec![0, 2]).describe("code", 7),
+            "run code (7) only on CPUs 0,2"
+        );
+    }
+
+    #[test]
+    fn test_reused_pid_is_left_alone() {
+        let control = fake(true);
+        let earlier = Target {
+            start_time: STARTED - 60,
+            ..TARGET
+        };
+        assert_eq!(
+            run(&control, earlier, &ProcessAction::Terminate),
+            Err("Process 42 is no longer running".to_string())
+        );
+        assert!(run(&control, earlier, &ProcessAction::SetAffinity(vec![0])).is_err());
+        assert!(control.calls.borrow().is_empty());
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod linux_tests {
+    use super::*;
+    use std::process::{Child, Stdio};
+
+    fn spawn(script: &str) -> Child {
+        let child = Command::new("sh")
+            .args(["-c", script])
+            .stdout(Stdio::null())
+            .spawn()
+            .unwrap();
+        // Give the shell time to install its traps
+        std::thread::sleep(Duration::from_millis(200));
+        child
+    }
+
+    /// Field 19 of /proc/[pid]/stat
+    fn nice(pid: u32) -> i32 {
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).unwrap();
+        let fields: Vec<&str> = stat
+            .rsplit_once(')')
+            .unwrap()
+            .1
+            .split_whitespace()
+            .collect();
+        fields[16].parse().unwrap()
+    }
+
+    fn target(pid: u32) -> Target {
+        Target {
+            pid,
+            start_time: LinuxProcessControl.start_time(pid).unwrap(),
+        }
+    }
+
+    fn cpus_allowed(pid: u32) -> String {
+        let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).unwrap();
+        let line = status
+            .lines()
+            .find(|line| line.starts_with("Cpus_allowed_list:"))
+            .unwrap();
+        line.split_whitespace().nth(1).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_terminate_spawned_children() {
+        let control = LinuxProcessControl;
+
+        let mut obedient = spawn("sleep 30");
+        let pid = obedient.id();
+        assert!(control.is_running(pid));
+        assert_eq!(
+            terminate(&control, target(pid), Duration::from_secs(2)),
+            Ok(Termination::Exited)
+        );
+        assert!(!obedient.wait().unwrap().success());
+
+        // Ignores SIGTERM, so it has to be killed
+        let mut stubborn = spawn("trap '' TERM; while true; do sleep 0.1; done");
+        let pid = stubborn.id();
+        assert_eq!(
+            terminate(&control, target(pid), Duration::from_millis(500)),
+            Ok(Termination::Killed)
+        );
+        stubborn.wait().unwrap();
+        assert!(!control.is_running(pid));
+    }
+
+    #[test]
+    fn test_start_time_matches_sampler() {
+        let mut child = spawn("sleep 30");
+        let pid = child.id();
+        let mut system = sysinfo::System::new();
+        system.refresh_process(sysinfo::Pid::from_u32(pid));
+        let sampled = system.process(sysinfo::Pid::from_u32(pid)).unwrap();
+
+        assert_eq!(
+            LinuxProcessControl.start_time(pid),
+            Some(sampled.start_time())
+        );
+        assert_eq!(LinuxProcessControl.start_time(u32::MAX), None);
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn test_priority_and_affinity_of_spawned_child() {
+        let control = LinuxProcessControl;
+        let mut child = spawn("sleep 30");
+        let pid = child.id();
+
+        // Lowering the priority needs no privileges
+        run(
+            &control,
+            target(pid),
+            &ProcessAction::SetPriority(Priority::BelowNormal),
+        )
+        .unwrap();
+        assert_eq!(nice(pid), 10);
+
+        run(&control, target(pid), &ProcessAction::SetAffinity(vec![0])).unwrap();
+        assert_eq!(cpus_allowed(pid), "0");
+        assert!(control.set_affinity(pid, &[4096]).is_err());
+
+        // Another process under the same PID is refused
+        let reused = Target {
+            start_time: target(pid).start_time - 1,
+            ..target(pid)
+        };
+        assert!(run(&control, reused, &ProcessAction::Terminate).is_err());
+        assert!(control.is_running(pid));
+
+        control.kill(pid).unwrap();
+        child.wait().unwrap();
+    }
+}
@@ -0,0 +1,974 @@
+//! Table of the running user processes.
+//!
+//! Collapsed, the panel shows the names of the four busiest processes.
+//! Expanded, it lists every process with its PID, description, CPU usage,
+//! resident and private memory, thread count, I/O rate and uptime. Clicking a
+//! column header sorts by that column (a second click reverses the order) and
+//! the text box keeps only processes whose name, description or PID contain
+//! the filter text.
+//!
+//! Processes can be grouped by executable name or by process tree (each
+//! process under its oldest listed ancestor). A group's row shows the summed
+//! CPU, memory, threads and I/O of its members and expands to list them.
+//! The collapsed panel also names the processes doing the most disk I/O.
+//!
+//! Clicking a process selects it and shows its actions: end it, change its
+//! priority or pin it to some logical CPUs. Every action asks for confirmation
+//! before it is carried out by [`crate::process_actions`].
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use iced::widget::{button, column, container, row, scrollable, text, text_input};
+use iced::{Element, Length};
+
+use crate::process_actions::{Priority, ProcessAction, Target};
+use crate::what_cpu_check::ProcessInfo;
+
+/// Rows drawn when expanded; the filter narrows down the rest.
+const MAX_ROWS: usize = 100;
+
+/// Height of the expanded table.
+const TABLE_HEIGHT: f32 = 260.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessColumn {
+    Pid,
+    Name,
+    Description,
+    Cpu,
+    Memory,
+    Private,
+    Threads,
+    Io,
+    Uptime,
+}
+
+impl ProcessColumn {
+    pub const ALL: [ProcessColumn; 9] = [
+        ProcessColumn::Pid,
+        ProcessColumn::Name,
+        ProcessColumn::Description,
+        ProcessColumn::Cpu,
+        ProcessColumn::Memory,
+        ProcessColumn::Private,
+        ProcessColumn::Threads,
+        ProcessColumn::Io,
+        ProcessColumn::Uptime,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            ProcessColumn::Pid => "PID",
+            ProcessColumn::Name => "Name",
+            ProcessColumn::Description => "Description",
+            ProcessColumn::Cpu => "CPU",
+            ProcessColumn::Memory => "RSS",
+            ProcessColumn::Private => "Private",
+            ProcessColumn::Threads => "Threads",
+            ProcessColumn::Io => "I/O",
+            ProcessColumn::Uptime => "Uptime",
+        }
+    }
+
+    /// Share of the row width
+    fn portion(self) -> u16 {
+        match self {
+            ProcessColumn::Name | ProcessColumn::Description => 4,
+            _ => 2,
+        }
+    }
+
+    /// Text columns start A to Z, numbers largest first
+    fn descending_by_default(self) -> bool {
+        !matches!(self, ProcessColumn::Name | ProcessColumn::Description)
+    }
+
+    fn compare(self, a: &ProcessInfo, b: &ProcessInfo) -> Ordering {
+        match self {
+            ProcessColumn::Pid => a.pid.cmp(&b.pid),
+            ProcessColumn::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            ProcessColumn::Description => a
+                .description
+                .to_lowercase()
+                .cmp(&b.description.to_lowercase()),
+            ProcessColumn::Cpu => a.cpu_usage.total_cmp(&b.cpu_usage),
+            ProcessColumn::Memory => a.memory_bytes.cmp(&b.memory_bytes),
+            ProcessColumn::Private => a.private_bytes.cmp(&b.private_bytes),
+            ProcessColumn::Threads => a.threads.cmp(&b.threads),
+            ProcessColumn::Io => a.io_bytes_per_second().total_cmp(&b.io_bytes_per_second()),
+            ProcessColumn::Uptime => a.uptime_secs.cmp(&b.uptime_secs),
+        }
+    }
+
+    fn cell(self, process: &ProcessInfo) -> String {
+        match self {
+            ProcessColumn::Pid => process.pid.to_string(),
+            ProcessColumn::Name => process.name.clone(),
+            ProcessColumn::Description => process.description.clone(),
+            ProcessColumn::Cpu => format!("{:.1}%", process.cpu_usage),
+            ProcessColumn::Memory => format_bytes(process.memory_bytes as f64),
+            ProcessColumn::Private => process
+                .private_bytes
+                .map_or_else(|| "-".to_string(), |bytes| format_bytes(bytes as f64)),
+            ProcessColumn::Threads => process
+                .threads
+                .map_or_else(|| "-".to_string(), |threads| threads.to_string()),
+            ProcessColumn::Io => format!("{}/s", format_bytes(process.io_bytes_per_second())),
+            ProcessColumn::Uptime => format_uptime(process.uptime_secs),
+        }
+    }
+}
+
+/// How processes are combined into rows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Grouping {
+    /// One row per process
+    #[default]
+    None,
+    /// One row per executable name
+    Executable,
+    /// One row per process tree
+    Tree,
+}
+
+impl Grouping {
+    const ALL: [Grouping; 3] = [Grouping::None, Grouping::Executable, Grouping::Tree];
+
+    fn title(self) -> &'static str {
+        match self {
+            Grouping::None => "List",
+            Grouping::Executable => "By name",
+            Grouping::Tree => "Tree",
+        }
+    }
+}
+
+/// One line of the expanded table.
+#[derive(Debug, Clone, Copy)]
+enum TableLine<'g, 'a> {
+    /// A process on its own
+    Single(&'a ProcessInfo),
+    /// The totals of a group of processes, open or not
+    Group(&'g ProcessGroup<'a>, bool),
+    /// A member of an open group with its depth below the first one
+    Member(usize, &'a ProcessInfo),
+}
+
+/// One row of the table: a group of processes and their totals.
+#[derive(Debug, Clone)]
+pub struct ProcessGroup<'a> {
+    /// Identifies the group between refreshes, to remember which are expanded
+    pub key: String,
+    /// CPU, memory, threads and I/O summed over the members, uptime of the
+    /// oldest; PID, start time, name and description of the first member
+    pub total: ProcessInfo,
+    /// Members in display order with their depth below the first one
+    pub members: Vec<(usize, &'a ProcessInfo)>,
+}
+
+impl<'a> ProcessGroup<'a> {
+    fn new(key: String, members: Vec<(usize, &'a ProcessInfo)>) -> Self {
+        let first = members[0].1;
+        let processes = || members.iter().map(|(_, process)| *process);
+        let total = ProcessInfo {
+            pid: first.pid,
+            parent_pid: first.parent_pid,
+            name: first.name.clone(),
+            description: first.description.clone(),
+            cpu_usage: processes().map(|process| process.cpu_usage).sum(),
+            memory_bytes: processes().map(|process| process.memory_bytes).sum(),
+            private_bytes: processes()
+                .filter_map(|process| process.private_bytes)
+                .reduce(|a, b| a + b),
+            threads: processes()
+                .filter_map(|process| process.threads)
+                .reduce(|a, b| a + b),
+            read_bytes_per_second: processes()
+                .map(|process| process.read_bytes_per_second)
+                .sum(),
+            write_bytes_per_second: processes()
+                .map(|process| process.write_bytes_per_second)
+                .sum(),
+            total_read_bytes: processes().map(|process| process.total_read_bytes).sum(),
+            total_written_bytes: processes().map(|process| process.total_written_bytes).sum(),
+            uptime_secs: processes()
+                .map(|process| process.uptime_secs)
+                .max()
+                .unwrap_or(0),
+            start_time: first.start_time,
+        };
+        Self {
+            key,
+            total,
+            members,
+        }
+    }
+
+    /// Name with the member count, e.g. "chrome (10)"
+    fn label(&self) -> String {
+        if self.members.len() > 1 {
+            format!("{} ({})", self.total.name, self.members.len())
+        } else {
+            self.total.name.clone()
+        }
+    }
+}
+
+/// Groups processes that are already in display order, keeping that order
+/// within each group.
+pub fn group_processes<'a>(rows: &[&'a ProcessInfo], grouping: Grouping) -> Vec<ProcessGroup<'a>> {
+    match grouping {
+        Grouping::None => rows
+            .iter()
+            .map(|&process| ProcessGroup::new(format!("pid:{}", process.pid), vec![(0, process)]))
+            .collect(),
+        Grouping::Executable => {
+            let mut order: Vec<String> = Vec::new();
+            let mut members: HashMap<String, Vec<(usize, &ProcessInfo)>> = HashMap::new();
+            for &process in rows {
+                let key = format!("exe:{}", process.name.to_lowercase());
+                if !members.contains_key(&key) {
+                    order.push(key.clone());
+                }
+                members.entry(key).or_default().push((0, process));
+            }
+            order
+                .into_iter()
+                .map(|key| {
+                    let members = members.remove(&key).unwrap_or_default();
+                    ProcessGroup::new(key, members)
+                })
+                .collect()
+        }
+        Grouping::Tree => tree_groups(rows),
+    }
+}
+
+/// One group per process whose parent isn't listed, holding all its listed
+/// descendants depth-first.
+fn tree_groups<'a>(rows: &[&'a ProcessInfo]) -> Vec<ProcessGroup<'a>> {
+    let listed: HashSet<u32> = rows.iter().map(|process| process.pid).collect();
+    let mut children: HashMap<u32, Vec<&ProcessInfo>> = HashMap::new();
+    let mut roots = Vec::new();
+    for &process in rows {
+        match process
+            .parent_pid
+            .filter(|parent| *parent != process.pid && listed.contains(parent))
+        {
+            Some(parent) => children.entry(parent).or_default().push(process),
+            None => roots.push(process),
+        }
+    }
+
+    let mut visited = HashSet::new();
+    let mut groups = Vec::new();
+    for root in roots {
+        let mut members = Vec::new();
+        let mut stack = vec![(0, root)];
+        while let Some((depth, process)) = stack.pop() {
+            if !visited.insert(process.pid) {
+                continue;
+            }
+            members.push((depth, process));
+            if let Some(kids) = children.get(&process.pid) {
+                stack.extend(kids.iter().rev().map(|&kid| (depth + 1, kid)));
+            }
+        }
+        groups.push(ProcessGroup::new(format!("tree:{}", root.pid), members));
+    }
+    // A reused PID can make two processes each other's parent; list those on their own
+    for &process in rows {
+        if visited.insert(process.pid) {
+            groups.push(ProcessGroup::new(
+                format!("tree:{}", process.pid),
+                vec![(0, process)],
+            ));
+        }
+    }
+    groups
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// Sort by a column, or reverse the order if it already is the sort column
+    SortBy(ProcessColumn),
+    Filter(String),
+    ToggleExpanded,
+    SetGrouping(Grouping),
+    /// Show or hide the members of a group (by its key)
+    ToggleGroup(String),
+    /// Select a process, or clear the selection if it already is selected
+    Select(Target),
+    /// Ask to confirm an action on the selected process
+    Request(ProcessAction),
+    /// Include or exclude a logical CPU in the affinity being edited
+    ToggleAffinityCpu(usize),
+    Confirm,
+    Cancel,
+    /// Outcome of a confirmed action, shown under the table
+    ActionFinished(Result<String, String>),
+}
+
+/// Sort order, filter text, grouping and which parts of the table are shown.
+#[derive(Debug, Clone)]
+pub struct ProcessTable {
+    sort: ProcessColumn,
+    descending: bool,
+    filter: String,
+    expanded: bool,
+    grouping: Grouping,
+    expanded_groups: HashSet<String>,
+    selected: Option<Target>,
+    /// Action waiting for confirmation
+    pending: Option<ProcessAction>,
+    /// Logical CPUs ticked for "Set affinity"
+    affinity: Vec<bool>,
+    status: Option<Result<String, String>>,
+}
+
+impl Default for ProcessTable {
+    fn default() -> Self {
+        Self {
+            sort: ProcessColumn::Cpu,
+            descending: true,
+            filter: String::new(),
+            expanded: false,
+            grouping: Grouping::default(),
+            expanded_groups: HashSet::new(),
+            selected: None,
+            pending: None,
+            affinity: all_cpus(),
+            status: None,
+        }
+    }
+}
+
+impl ProcessTable {
+    /// Applies a message; a confirmed action is returned with the process to run it on.
+    pub fn update(&mut self, message: Message) -> Option<(Target, ProcessAction)> {
+        match message {
+            Message::SortBy(column) => {
+                if column == self.sort {
+                    self.descending = !self.descending;
+                } else {
+                    self.sort = column;
+                    self.descending = column.descending_by_default();
+                }
+            }
+            Message::Filter(filter) => self.filter = filter,
+            Message::ToggleExpanded => self.expanded = !self.expanded,
+            Message::SetGrouping(grouping) => {
+                self.grouping = grouping;
+                self.expanded_groups.clear();
+            }
+            Message::ToggleGroup(key) => {
+                if !self.expanded_groups.remove(&key) {
+                    self.expanded_groups.insert(key);
+                }
+            }
+            Message::Select(target) => {
+                self.selected = (self.selected != Some(target)).then_some(target);
+                self.pending = None;
+                self.affinity = all_cpus();
+            }
+            Message::Request(action) => self.pending = Some(action),
+            Message::ToggleAffinityCpu(cpu) => {
+                if let Some(ticked) = self.affinity.get_mut(cpu) {
+                    *ticked = !*ticked;
+                }
+            }
+            Message::Confirm => {
+                let action = self.pending.take()?;
+                return self.selected.map(|target| (target, action));
+            }
+            Message::Cancel => self.pending = None,
+            Message::ActionFinished(result) => self.status = Some(result),
+        }
+        None
+    }
+
+    /// Drops the selection and its pending action once the selected process is
+    /// no longer listed, so they don't carry over to a process reusing its PID.
+    pub fn retain_selection(&mut self, processes: &[ProcessInfo]) {
+        let listed = self.selected.is_some_and(|target| {
+            processes
+                .iter()
+                .any(|process| Target::of(process) == target)
+        });
+        if !listed {
+            self.selected = None;
+            self.pending = None;
+        }
+    }
+
+    fn order(&self, a: &ProcessInfo, b: &ProcessInfo) -> Ordering {
+        let order = self.sort.compare(a, b);
+        if self.descending {
+            order.reverse()
+        } else {
+            order
+        }
+    }
+
+    /// The processes matching the filter, in the selected order.
+    pub fn rows<'a>(&self, processes: &'a [ProcessInfo]) -> Vec<&'a ProcessInfo> {
+        let filter = self.filter.trim().to_lowercase();
+        let mut rows: Vec<&ProcessInfo> = processes
+            .iter()
+            .filter(|process| {
+                filter.is_empty()
+                    || process.name.to_lowercase().contains(&filter)
+                    || process.description.to_lowercase().contains(&filter)
+                    || process.pid.to_string().contains(&filter)
+            })
+            .collect();
+        rows.sort_by(|a, b| self.order(a, b));
+        rows
+    }
+
+    /// The matching processes grouped as selected, groups ordered by their totals.
+    pub fn groups<'a>(&self, processes: &'a [ProcessInfo]) -> Vec<ProcessGroup<'a>> {
+        let mut groups = group_processes(&self.rows(processes), self.grouping);
+        groups.sort_by(|a, b| self.order(&a.total, &b.total));
+        groups
+    }
+
+    /// Lines of the expanded table, at most `MAX_ROWS` of them, and how many
+    /// more there would be.
+    fn lines<'g, 'a>(&self, groups: &'g [ProcessGroup<'a>]) -> (Vec<TableLine<'g, 'a>>, usize) {
+        let mut lines = Vec::new();
+        for group in groups {
+            if group.members.len() == 1 {
+                lines.push(TableLine::Single(group.members[0].1));
+                continue;
+            }
+            let open = self.expanded_groups.contains(&group.key);
+            lines.push(TableLine::Group(group, open));
+            if open {
+                lines.extend(
+                    group
+                        .members
+                        .iter()
+                        .map(|&(depth, process)| TableLine::Member(depth, process)),
+                );
+            }
+        }
+        let hidden = lines.len().saturating_sub(MAX_ROWS);
+        lines.truncate(MAX_ROWS);
+        (lines, hidden)
+    }
+
+    /// `top_io` is the ranking by disk I/O, shown under the busiest processes when collapsed.
+    pub fn view<'a>(
+        &'a self,
+        processes: &'a [ProcessInfo],
+        top_io: &'a [ProcessInfo],
+    ) -> Element<'a, Message> {
+        let toggle = button(
+            text(if self.expanded {
+                "Show less"
+            } else {
+                "Show all"
+            })
+            .size(11),
+        )
+        .on_press(Message::ToggleExpanded)
+        .padding([1, 6])
+        .style(crate::styles::table_header);
+
+        let groups = self.groups(processes);
+        let grouping = row(Grouping::ALL.iter().map(|&grouping| {
+            button(text(grouping.title()).size(11))
+                .on_press(Message::SetGrouping(grouping))
+                .padding([1, 6])
+                .style(if grouping == self.grouping {
+                    crate::styles::table_header_active
+                } else {
+                    crate::styles::table_header
+                })
+                .into()
+        }))
+        .spacing(2);
+
+        if !self.expanded {
+            return column![
+                row![
+                    container(text("TOP USER PROCESSES:").size(13)).width(Length::Fill),
+                    grouping,
+                    toggle
+                ]
+                .spacing(8),
+                top_names(&groups),
+                top_io_line(top_io)
+            ]
+            .spacing(4)
+            .into();
+        }
+
+        let filter = text_input("Filter by name, description or PID", &self.filter)
+            .on_input(Message::Filter)
+            .size(12)
+            .padding(3);
+        let header = row(ProcessColumn::ALL.iter().map(|&column| {
+            let arrow = match (column == self.sort, self.descending) {
+                (false, _) => "",
+                (true, true) => " ▼",
+                (true, false) => " ▲",
+            };
+            button(text(format!("{}{}", column.title(), arrow)).size(12))
+                .on_press(Message::SortBy(column))
+                .padding(0)
+                .style(crate::styles::table_header)
+                .width(Length::FillPortion(column.portion()))
+                .into()
+        }))
+        .spacing(6);
+
+        let (table_lines, hidden) = self.lines(&groups);
+        let mut lines: Vec<Element<'a, Message>> = table_lines
+            .into_iter()
+            .map(|line| match line {
+                TableLine::Single(process) => self.process_row(process, process.name.clone()),
+                TableLine::Group(group, open) => {
+                    let arrow = if open { "▼" } else { "▶" };
+                    button(table_row(
+                        &group.total,
+                        format!("{} {}", arrow, group.label()),
+                    ))
+                    .on_press(Message::ToggleGroup(group.key.clone()))
+                    .padding(0)
+                    .style(crate::styles::table_header)
+                    .into()
+                }
+                TableLine::Member(depth, process) => {
+                    let indent = "    ".repeat(depth + 1);
+                    self.process_row(process, format!("{}{}", indent, process.name))
+                }
+            })
+            .collect();
+        if hidden > 0 {
+            lines.push(
+                text(format!("{} more, use the filter to find them", hidden))
+                    .size(11)
+                    .into(),
+            );
+        }
+
+        column![
+            row![
+                container(text("USER PROCESSES:").size(13)).width(Length::Fill),
+                grouping,
+                toggle
+            ]
+            .spacing(8),
+            filter,
+            header,
+            scrollable(column(lines).spacing(1)).height(Length::Fixed(TABLE_HEIGHT)),
+            self.actions(processes),
+        ]
+        .spacing(4)
+        .into()
+    }
+
+    /// A process line that selects the process when clicked
+    fn process_row<'a>(&self, process: &ProcessInfo, name: String) -> Element<'a, Message> {
+        button(table_row(process, name))
+            .on_press(Message::Select(Target::of(process)))
+            .padding(0)
+            .style(if self.selected == Some(Target::of(process)) {
+                crate::styles::table_header_active
+            } else {
+                crate::styles::table_header
+            })
+            .into()
+    }
+
+    /// The actions for the selected process, the pending confirmation and the last outcome
+    fn actions<'a>(&'a self, processes: &'a [ProcessInfo]) -> Element<'a, Message> {
+        let mut lines: Vec<Element<'a, Message>> = Vec::new();
+        let selected = self.selected.and_then(|target| {
+            processes
+                .iter()
+                .find(|process| Target::of(process) == target)
+        });
+
+        if let Some(process) = selected {
+            lines.push(
+                text(format!(
+                    "Read {}/s ({} total), written {}/s ({} total)",
+                    format_bytes(process.read_bytes_per_second),
+                    format_bytes(process.total_read_bytes as f64),
+                    format_bytes(process.write_bytes_per_second),
+                    format_bytes(process.total_written_bytes as f64),
+                ))
+                .size(11)
+                .into(),
+            );
+            if let Some(action) = &self.pending {
+                lines.push(
+                    row![
+                        text(format!(
+                            "Really {}?",
+                            action.describe(&process.name, process.pid)
+                        ))
+                        .size(12)
+                        .width(Length::Fill),
+                        small_button("Confirm", Message::Confirm, true),
+                        small_button("Cancel", Message::Cancel, false),
+                    ]
+                    .spacing(4)
+                    .into(),
+                );
+            } else {
+                let mut actions = row![
+                    text(format!("{} ({}):", process.name, process.pid))
+                        .size(12)
+                        .width(Length::Fill),
+                    small_button(
+                        "End process",
+                        Message::Request(ProcessAction::Terminate),
+                        false
+                    ),
+                    text("Priority").size(11),
+                ]
+                .spacing(4);
+                for priority in Priority::ALL {
+                    actions = actions.push(small_button(
+                        priority.title(),
+                        Message::Request(ProcessAction::SetPriority(priority)),
+                        false,
+                    ));
+                }
+                lines.push(actions.into());
+
+                let cpus: Vec<usize> = (0..self.affinity.len())
+                    .filter(|&cpu| self.affinity[cpu])
+                    .collect();
+                let mut affinity = row![text("CPUs").size(11)].spacing(2);
+                for (cpu, &ticked) in self.affinity.iter().enumerate() {
+                    affinity = affinity.push(small_button(
+                        &cpu.to_string(),
+                        Message::ToggleAffinityCpu(cpu),
+                        ticked,
+                    ));
+                }
+                affinity = affinity.push(small_button(
+                    "Set affinity",
+                    Message::Request(ProcessAction::SetAffinity(cpus)),
+                    false,
+                ));
+                lines.push(affinity.wrap().into());
+            }
+        }
+
+        if let Some(status) = &self.status {
+            let (message, colour) = match status {
+                Ok(message) => (message, iced::Color::from_rgb(0.4, 0.9, 0.4)),
+                Err(message) => (message, iced::Color::from_rgb(1.0, 0.4, 0.4)),
+            };
+            lines.push(text(message.as_str()).size(11).color(colour).into());
+        }
+        column(lines).spacing(2).into()
+    }
+}
+
+/// Every logical CPU ticked
+fn all_cpus() -> Vec<bool> {
+    vec![true; crate::cpu_topology::current().thread_count()]
+}
+
+/// A compact button, highlighted when `active`
+fn small_button<'a>(label: &str, message: Message, active: bool) -> Element<'a, Message> {
+    button(text(label.to_string()).size(11))
+        .on_press(message)
+        .padding([1, 6])
+        .style(if active {
+            crate::styles::table_header_active
+        } else {
+            crate::styles::table_header
+        })
+        .into()
+}
+
+/// One line of the table, with `name` in place of the process name
+fn table_row<'a>(process: &ProcessInfo, name: String) -> Element<'a, Message> {
+    row(ProcessColumn::ALL.iter().map(|&column| {
+        let cell = if column == ProcessColumn::Name {
+            name.clone()
+        } else {
+            column.cell(process)
+        };
+        text(cell)
+            .size(12)
+            .width(Length::FillPortion(column.portion()))
+            .into()
+    }))
+    .spacing(6)
+    .into()
+}
+
+/// The names of the four busiest processes or groups side by side
+fn top_names<'a>(groups: &[ProcessGroup]) -> Element<'a, Message> {
+    let mut process_columns: Vec<Element<'a, Message>> = vec![];
+    for (i, group) in groups.iter().take(4).enumerate() {
+        let label = format!("{}. {}", i + 1, group.label());
+        let col = container(
+            text(label)
+                .size(16)
+                .align_x(iced::alignment::Horizontal::Center),
+        )
+        .width(Length::FillPortion(1))
+        .align_x(iced::alignment::Horizontal::Center);
+        process_columns.push(col.into());
+    }
+    while process_columns.len() < 3 {
+        process_columns.push(
+            container(text("").size(16))
+                .width(Length::FillPortion(1))
+                .into(),
+        );
+    }
+    row(process_columns).spacing(4).into()
+}
+
+/// The three processes doing the most disk I/O with their read and write rates
+fn top_io_line<'a>(top_io: &[ProcessInfo]) -> Element<'a, Message> {
+    let busiest: Vec<String> = top_io
+        .iter()
+        .take(3)
+        .map(|process| {
+            format!(
+                "{} (R {}/s, W {}/s)",
+                process.name,
+                format_bytes(process.read_bytes_per_second),
+                format_bytes(process.write_bytes_per_second)
+            )
+        })
+        .collect();
+    let line = if busiest.is_empty() {
+        "none".to_string()
+    } else {
+        busiest.join("   ")
+    };
+    text(format!("TOP DISK I/O: {}", line)).size(12).into()
+}
+
+/// Bytes in the largest binary unit that keeps the number above 1, e.g. "1.5 GB"
+pub fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{:.0} {}", value, UNITS[unit])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// The two largest units of a duration, e.g. "3d 04h" or "5m 07s"
+pub fn format_uptime(secs: u64) -> String {
+    let (days, hours, minutes, seconds) =
+        (secs / 86400, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    if days > 0 {
+        format!("{}d {:02}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {:02}m", hours, minutes)
+    } else {
+        format!("{}m {:02}s", minutes, seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, name: &str, cpu_usage: f32, memory_bytes: u64) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: name.to_string(),
+            description: format!("{} description", name),
+            cpu_usage,
+            memory_bytes,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sort_and_filter() {
+        let processes = vec![
+            process(30, "code", 5.0, 900),
+            process(10, "Firefox", 12.5, 2000),
+            process(20, "bash", 0.5, 100),
+        ];
+        let pids = |table: &ProcessTable| -> Vec<u32> {
+            table
+                .rows(&processes)
+                .iter()
+                .map(|process| process.pid)
+                .collect()
+        };
+
+        let mut table = ProcessTable::default();
+        assert_eq!(pids(&table), vec![10, 30, 20]);
+
+        // Names sort A to Z first, then the other way round
+        table.update(Message::SortBy(ProcessColumn::Name));
+        assert_eq!(pids(&table), vec![20, 30, 10]);
+        table.update(Message::SortBy(ProcessColumn::Name));
+        assert_eq!(pids(&table), vec![10, 30, 20]);
+
+        table.update(Message::SortBy(ProcessColumn::Memory));
+        assert_eq!(pids(&table), vec![10, 30, 20]);
+
+        // Case-insensitive, on name, description or PID
+        table.update(Message::Filter("FIRE".to_string()));
+        assert_eq!(pids(&table), vec![10]);
+        table.update(Message::Filter("description".to_string()));
+        assert_eq!(pids(&table).len(), 3);
+        table.update(Message::Filter("2".to_string()));
+        assert_eq!(pids(&table), vec![20]);
+    }
+
+    #[test]
+    fn test_group_by_executable_and_tree() {
+        let mut processes = vec![
+            process(1, "explorer", 1.0, 100),
+            process(10, "chrome", 4.0, 300),
+            process(11, "chrome", 2.0, 200),
+            process(12, "chrome", 3.0, 100),
+            process(20, "code", 5.0, 50),
+        ];
+        // explorer -> chrome 10 -> chrome 11, chrome 12; code's parent isn't listed
+        processes[1].parent_pid = Some(1);
+        processes[2].parent_pid = Some(10);
+        processes[3].parent_pid = Some(10);
+        processes[4].parent_pid = Some(999);
+        processes[1].threads = Some(30);
+        processes[3].threads = Some(12);
+
+        let mut table = ProcessTable::default();
+        table.update(Message::SetGrouping(Grouping::Executable));
+        let groups = table.groups(&processes);
+        let labels: Vec<String> = groups.iter().map(|group| group.label()).collect();
+        assert_eq!(labels, vec!["chrome (3)", "code", "explorer"]);
+        assert_eq!(groups[0].total.cpu_usage, 9.0);
+        assert_eq!(groups[0].total.memory_bytes, 600);
+        assert_eq!(groups[0].total.threads, Some(42));
+        // Members keep the CPU order
+        let pids: Vec<u32> = groups[0]
+            .members
+            .iter()
+            .map(|(_, process)| process.pid)
+            .collect();
+        assert_eq!(pids, vec![10, 12, 11]);
+
+        table.update(Message::SetGrouping(Grouping::Tree));
+        let groups = table.groups(&processes);
+        assert_eq!(groups[0].label(), "explorer (4)");
+        assert_eq!(groups[0].total.cpu_usage, 10.0);
+        let members: Vec<(usize, u32)> = groups[0]
+            .members
+            .iter()
+            .map(|(depth, process)| (*depth, process.pid))
+            .collect();
+        assert_eq!(members, vec![(0, 1), (1, 10), (2, 12), (2, 11)]);
+        assert_eq!(groups[1].label(), "code");
+
+        // Groups expand and collapse by key
+        table.update(Message::ToggleGroup(groups[0].key.clone()));
+        assert!(table.expanded_groups.contains("tree:1"));
+        table.update(Message::ToggleGroup("tree:1".to_string()));
+        assert!(table.expanded_groups.is_empty());
+    }
+
+    #[test]
+    fn test_row_limit_counts_group_members() {
+        // One tree of 150 processes and 10 processes on their own
+        let mut processes: Vec<ProcessInfo> = (1..=150)
+            .map(|pid| process(pid, "worker", 1.0, 100))
+            .collect();
+        for process in processes.iter_mut().skip(1) {
+            process.parent_pid = Some(1);
+        }
+        processes.extend((1000..1010).map(|pid| process(pid, "tool", 0.0, 10)));
+
+        let mut table = ProcessTable::default();
+        table.update(Message::SetGrouping(Grouping::Tree));
+        let groups = table.groups(&processes);
+        assert_eq!(groups.len(), 11);
+
+        // Closed, the tree is a single row
+        let (lines, hidden) = table.lines(&groups);
+        assert_eq!((lines.len(), hidden), (11, 0));
+
+        // Open, its members count against the limit too
+        table.update(Message::ToggleGroup("tree:1".to_string()));
+        let (lines, hidden) = table.lines(&groups);
+        assert_eq!(lines.len(), MAX_ROWS);
+        assert_eq!(hidden, 1 + 150 + 10 - MAX_ROWS);
+    }
+
+    #[test]
+    fn test_actions_wait_for_confirmation() {
+        let target = |pid| Target {
+            pid,
+            start_time: 1_700_000_000,
+        };
+        let mut table = ProcessTable::default();
+        // Nothing is selected yet
+        table.update(Message::Request(ProcessAction::Terminate));
+        assert_eq!(table.update(Message::Confirm), None);
+
+        table.update(Message::Select(target(42)));
+        assert_eq!(
+            table.update(Message::Request(ProcessAction::Terminate)),
+            None
+        );
+        table.update(Message::Cancel);
+        assert_eq!(table.update(Message::Confirm), None);
+
+        let low = ProcessAction::SetPriority(Priority::Low);
+        table.update(Message::Request(low.clone()));
+        assert_eq!(table.update(Message::Confirm), Some((target(42), low)));
+        // A confirmation runs the action once
+        assert_eq!(table.update(Message::Confirm), None);
+
+        // Selecting another process drops the pending action; selecting it again clears the selection
+        table.update(Message::Request(ProcessAction::Terminate));
+        table.update(Message::Select(target(7)));
+        assert_eq!(table.update(Message::Confirm), None);
+        table.update(Message::Select(target(7)));
+        assert_eq!(table.selected, None);
+    }
+
+    #[test]
+    fn test_selection_ends_with_the_process() {
+        let mut processes = vec![process(42, "code", 1.0, 100)];
+        processes[0].start_time = 1_700_000_000;
+        let mut table = ProcessTable::default();
+        table.update(Message::Select(Target::of(&processes[0])));
+        table.update(Message::Request(ProcessAction::Terminate));
+        table.retain_selection(&processes);
+        assert_eq!(table.selected, Some(Target::of(&processes[0])));
+
+        // PID 42 now belongs to a process that started later
+        processes[0].start_time += 5;
+        table.retain_selection(&processes);
+        assert_eq!(table.selected, None);
+        assert_eq!(table.update(Message::Confirm), None);
+    }
+
+    #[test]
+    fn test_formatting() {
+        assert_eq!(format_bytes(512.0), "512 B");
+        assert_eq!(format_bytes(1536.0 * 1024.0 * 1024.0), "1.5 GB");
+        assert_eq!(format_uptime(42), "0m 42s");
+        assert_eq!(format_uptime(2 * 3600 + 5 * 60), "2h 05m");
+        assert_eq!(format_uptime(3 * 86400 + 4 * 3600 + 59), "3d 04h");
+    }
+}
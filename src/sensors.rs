@@ -0,0 +1,347 @@
+//! Generic sensor model and the TempMonitor protocol.
+//!
+//! TempMonitor.exe prints one JSON object per line. Version 1 (the original
+//! format, no `Version` field) carries the fixed `HardwareData` fields.
+//! Version 2 streams LibreHardwareMonitor's whole sensor tree instead:
+//!
+//! ```json
+//! {"Version":2,"MotherboardModel":"...","TotalMemoryMB":32768,"MemorySpeedMTS":6000,
+//!  "Sensors":[{"HardwareId":"/amdcpu/0","HardwareName":"AMD Ryzen 9 7950X",
+//!              "HardwareType":"Cpu","Id":"/amdcpu/0/temperature/2","Type":"Temperature",
+//!              "Name":"Core (Tctl/Tdie)","Value":65.1,"Min":38.0,"Max":88.4}]}
+//! ```
+//!
+//! The fixed `HardwareData` fields are derived from the tree, so the rest of
+//! the app works with either version; the tree itself is kept as a
+//! [`SensorTree`] that the UI and exporters can query by sensor id.
+//!
+//! The TempMonitor.exe embedded in CuteMonitor is built from TempMonitor.cs by
+//! build.rs and prints version 2; version 1 comes from the checked-in binary
+//! build.rs falls back to without the .NET SDK, and from older builds.
+
+use serde::{Deserialize, Serialize};
+
+use crate::hardware_checker::HardwareData;
+
+/// Newest protocol version this build understands.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// One LibreHardwareMonitor sensor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Sensor {
+    /// Identifier of the hardware the sensor belongs to, e.g. `/amdcpu/0`.
+    pub hardware_id: String,
+    /// Display name of that hardware, e.g. the CPU model.
+    pub hardware_name: String,
+    /// LibreHardwareMonitor hardware type: `Cpu`, `Memory`, `Motherboard`, `SuperIO`, ...
+    pub hardware_type: String,
+    /// Unique sensor identifier, e.g. `/amdcpu/0/temperature/2`.
+    pub id: String,
+    /// Sensor type: `Temperature`, `Voltage`, `Power`, `Load`, `Fan`, `Clock`, ...
+    #[serde(rename = "Type")]
+    pub sensor_type: String,
+    pub name: String,
+    pub value: Option<f32>,
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+}
+
+/// Every sensor from the latest reading.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SensorTree {
+    pub sensors: Vec<Sensor>,
+}
+
+impl SensorTree {
+    /// Looks a sensor up by its id (used by `sensor` alert rules).
+    pub fn get(&self, id: &str) -> Option<&Sensor> {
+        self.sensors.iter().find(|sensor| sensor.id == id)
+    }
+
+    /// Sensors of one type on hardware of one type, in protocol order.
+    pub fn find<'a>(
+        &'a self,
+        hardware_types: &'a [&'a str],
+        sensor_type: &'a str,
+    ) -> impl Iterator<Item = &'a Sensor> + 'a {
+        self.sensors.iter().filter(move |sensor| {
+            hardware_types.contains(&sensor.hardware_type.as_str())
+                && sensor.sensor_type == sensor_type
+        })
+    }
+
+    /// Value of the first sensor matching one of `names`, tried in order.
+    fn value_named(
+        &self,
+        hardware_types: &[&str],
+        sensor_type: &str,
+        names: &[&str],
+    ) -> Option<f32> {
+        names.iter().find_map(|name| {
+            self.find(hardware_types, sensor_type)
+                .find(|sensor| sensor.name == *name)
+                .and_then(|sensor| sensor.value)
+        })
+    }
+
+    /// Derives the fixed fields the rest of the app uses, following the same
+    /// sensor choices as the version 1 output of TempMonitor.exe (except for
+    /// the chipset, which is matched by name).
+    fn derive(&self, message: &ProtocolMessage) -> HardwareData {
+        const CPU: &[&str] = &["Cpu"];
+        const MEMORY: &[&str] = &["Memory"];
+        const BOARD: &[&str] = &["Motherboard", "SuperIO"];
+
+        let cpu_temp = self
+            .value_named(
+                CPU,
+                "Temperature",
+                &[
+                    "Core (Tctl/Tdie)",
+                    "CPU Package",
+                    "Core (Tdie)",
+                    "Core (Tctl)",
+                ],
+            )
+            .or_else(|| {
+                self.find(CPU, "Temperature")
+                    .find_map(|sensor| sensor.value)
+            })
+            .unwrap_or(0.0);
+
+        // "CCD1 (Tdie)", "CCD2 (Tdie)", ... but not "CCDs Max (Tdie)". Missing
+        // CCDs stay None so the index matches the CCD number (as in hwmon.rs).
+        let mut ccd_temperatures: Vec<Option<f32>> = Vec::new();
+        for sensor in self.find(CPU, "Temperature") {
+            let Some(number) = sensor
+                .name
+                .strip_prefix("CCD")
+                .and_then(|name| name.strip_suffix(" (Tdie)"))
+                .and_then(|number| number.parse::<usize>().ok())
+                .filter(|&number| number >= 1)
+            else {
+                continue;
+            };
+            if ccd_temperatures.len() < number {
+                ccd_temperatures.resize(number, None);
+            }
+            ccd_temperatures[number - 1] = sensor.value;
+        }
+
+        let memory_usage = self
+            .value_named(MEMORY, "Load", &["Memory"])
+            .or_else(|| self.find(MEMORY, "Load").find_map(|sensor| sensor.value))
+            .unwrap_or(0.0);
+
+        HardwareData {
+            motherboard_model: message.motherboard_model.clone(),
+            cpu_temp,
+            ccd_temperatures,
+            cpu_voltage: self.value_named(
+                CPU,
+                "Voltage",
+                &["Core (SVI2 TFN)", "Core (SVI3 TFN)", "Core (VID)"],
+            ),
+            cpu_power: self.value_named(CPU, "Power", &["Package", "CPU Package"]),
+            // Super I/O chips list their CPU and system sensors first; only a
+            // sensor labelled PCH/chipset is the chipset (as in hwmon.rs)
+            chipset_temp: self
+                .find(BOARD, "Temperature")
+                .find(|sensor| {
+                    let name = sensor.name.to_ascii_lowercase();
+                    name.contains("pch") || name.contains("chipset")
+                })
+                .and_then(|sensor| sensor.value),
+            memory_usage,
+            // Hottest DIMM, like hwmon.rs
+            memory_temp: self
+                .find(MEMORY, "Temperature")
+                .filter_map(|sensor| sensor.value)
+                .reduce(f32::max),
+            total_memory_mb: message.total_memory_mb,
+            memory_speed_mts: message.memory_speed_mts,
+            fans: crate::fans::from_sensor_tree(self),
+        }
+    }
+}
+
+/// A version 2 line.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ProtocolMessage {
+    // `Version` is checked on the raw value before this is parsed
+    #[serde(default)]
+    motherboard_model: String,
+    #[serde(rename = "TotalMemoryMB", default)]
+    total_memory_mb: i32,
+    #[serde(rename = "MemorySpeedMTS", default)]
+    memory_speed_mts: i32,
+    sensors: Vec<Sensor>,
+}
+
+/// Why a line from TempMonitor.exe was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParseErrorKind {
+    /// Not valid JSON at all (truncated, garbage, log output).
+    Malformed,
+    /// Valid JSON of the wrong shape: wrong types or an unsupported version.
+    SchemaMismatch,
+    /// Valid JSON missing a field this build needs.
+    MissingField,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<serde_json::Error> for ParseError {
+    fn from(error: serde_json::Error) -> Self {
+        let kind = match error.classify() {
+            serde_json::error::Category::Data if error.to_string().starts_with("missing field") => {
+                ParseErrorKind::MissingField
+            }
+            serde_json::error::Category::Data => ParseErrorKind::SchemaMismatch,
+            _ => ParseErrorKind::Malformed,
+        };
+        Self {
+            kind,
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Parses one line from TempMonitor.exe in either protocol version.
+/// Returns the sensor tree as well for version 2 lines.
+// Only TempMonitor.exe speaks the protocol; Linux just carries the model
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub fn parse_line(line: &str) -> Result<(HardwareData, Option<SensorTree>), ParseError> {
+    let value: serde_json::Value = serde_json::from_str(line)?;
+
+    match value.get("Version").and_then(|version| version.as_u64()) {
+        None => Ok((serde_json::from_value(value)?, None)),
+        Some(version) if version == u64::from(PROTOCOL_VERSION) => {
+            let message: ProtocolMessage = serde_json::from_value(value)?;
+            let tree = SensorTree {
+                sensors: message.sensors.clone(),
+            };
+            Ok((tree.derive(&message), Some(tree)))
+        }
+        Some(version) => Err(ParseError {
+            kind: ParseErrorKind::SchemaMismatch,
+            message: format!("Unsupported protocol version {}", version),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sensor(hardware_type: &str, sensor_type: &str, name: &str, value: f32) -> serde_json::Value {
+        serde_json::json!({
+            "HardwareId": format!("/{}/0", hardware_type.to_lowercase()),
+            "HardwareName": hardware_type,
+            "HardwareType": hardware_type,
+            "Id": format!("/{}/0/{}/{}", hardware_type.to_lowercase(), sensor_type.to_lowercase(), name),
+            "Type": sensor_type,
+            "Name": name,
+            "Value": value,
+            "Min": value - 1.0,
+            "Max": value + 1.0,
+        })
+    }
+
+    #[test]
+    fn test_version_1_line() {
+        let line = r#"{"MotherboardModel":"Board","CpuTemp":50.0,"CcdTemperatures":[48.0],"CpuVoltage":1.1,
+            "CpuPower":60.0,"ChipsetTemp":null,"MemoryUsage":40.0,"MemoryTemp":35.0,"TotalMemoryMB":16384,"MemorySpeedMTS":3200}"#;
+        let (data, tree) = parse_line(line).unwrap();
+        assert_eq!(data.cpu_temp, 50.0);
+        assert_eq!(data.memory_temp, Some(35.0));
+        assert!(tree.is_none());
+    }
+
+    #[test]
+    fn test_version_2_derives_fixed_fields() {
+        let line = serde_json::json!({
+            "Version": 2,
+            "MotherboardModel": "Board",
+            "TotalMemoryMB": 32768,
+            "MemorySpeedMTS": 6000,
+            "Sensors": [
+                sensor("Cpu", "Temperature", "Core (Tctl/Tdie)", 65.0),
+                sensor("Cpu", "Temperature", "CCD3 (Tdie)", 58.0),
+                sensor("Cpu", "Temperature", "CCD1 (Tdie)", 61.0),
+                sensor("Cpu", "Temperature", "CCDs Max (Tdie)", 61.0),
+                sensor("Cpu", "Voltage", "Core (SVI2 TFN)", 1.25),
+                sensor("Cpu", "Power", "Package", 88.0),
+                sensor("Memory", "Load", "Memory", 42.0),
+                sensor("Memory", "Temperature", "DIMM #1", 39.5),
+                sensor("Memory", "Temperature", "DIMM #2", 41.0),
+                sensor("SuperIO", "Temperature", "CPU", 55.0),
+                sensor("SuperIO", "Temperature", "System", 33.0),
+                sensor("SuperIO", "Temperature", "Chipset", 47.0),
+                sensor("SuperIO", "Fan", "Fan #1", 1200.0),
+            ],
+        })
+        .to_string();
+
+        let (data, tree) = parse_line(&line).unwrap();
+        assert_eq!(data.motherboard_model, "Board");
+        assert_eq!(data.cpu_temp, 65.0);
+        assert_eq!(data.ccd_temperatures, vec![Some(61.0), None, Some(58.0)]);
+        assert_eq!(data.cpu_voltage, Some(1.25));
+        assert_eq!(data.cpu_power, Some(88.0));
+        assert_eq!(data.chipset_temp, Some(47.0));
+        assert_eq!(data.memory_usage, 42.0);
+        assert_eq!(data.memory_temp, Some(41.0));
+        assert_eq!(data.total_memory_mb, 32768);
+        assert_eq!(data.fans.len(), 1);
+        assert_eq!(data.fans[0].rpm, Some(1200.0));
+
+        let tree = tree.unwrap();
+        let fan = tree.get("/superio/0/fan/Fan #1").unwrap();
+        assert_eq!(fan.value, Some(1200.0));
+        assert_eq!(fan.max, Some(1201.0));
+        assert_eq!(tree.find(&["SuperIO"], "Fan").count(), 1);
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let error = parse_line(r#"{"Version":3,"Sensors":[]}"#).unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::SchemaMismatch);
+        assert!(error.message.contains("version 3"));
+
+        // Not truncated to 2
+        let error = parse_line(r#"{"Version":4294967298,"Sensors":[]}"#).unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::SchemaMismatch);
+    }
+
+    #[test]
+    fn test_error_kinds() {
+        let kind = |line: &str| parse_line(line).unwrap_err().kind;
+        assert_eq!(kind("not json"), ParseErrorKind::Malformed);
+        assert_eq!(
+            kind(r#"{"MotherboardModel":"Board","CpuTemp":5"#),
+            ParseErrorKind::Malformed
+        );
+        assert_eq!(
+            kind(r#"{"MotherboardModel":"Board"}"#),
+            ParseErrorKind::MissingField
+        );
+        assert_eq!(
+            kind(r#"{"Version":2,"Sensors":{}}"#),
+            ParseErrorKind::SchemaMismatch
+        );
+    }
+}
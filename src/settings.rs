@@ -0,0 +1,418 @@
+//! User settings for CuteMonitor.
+//!
+//! Polling intervals, history lengths and the window geometry live in
+//! `settings.json` in the user's config directory (`%APPDATA%\Cutemonitor`
+//! on Windows, `$XDG_CONFIG_HOME/cutemonitor` or `~/.config/cutemonitor`
+//! elsewhere), or in the file given with `--config`:
+//!
+//! ```json
+//! {
+//!   "intervals_ms": { "hardware": 500, "cpu_threads": 500, "cpu_cores": 300,
+//!                     "processes": 2000, "gpu": 1000, "bandwidth": 200 },
+//!   "history_size": 30,
+//!   "graph_points": 300,
+//!   "window": { "width": 940, "height": 900, "x": 100, "y": 100 },
+//!   "external_sensors": [
+//!     { "name": "arduino", "command": "python", "args": ["bridge.py", "COM3"] },
+//!     { "name": "ipmi", "command": "ipmi.sh", "stall_timeout_ms": 90000 }
+//!   ],
+//!   "ignored_processes": ["idle", "system", "*cutemonitor*", "*tempmonitor*", "*powershell*"]
+//! }
+//! ```
+//!
+//! Missing fields take their defaults. The file is watched while the app runs;
+//! valid edits apply immediately, invalid ones are reported and ignored.
+
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+use std::time::{Duration, SystemTime};
+
+use iced::futures::stream;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::external_sensors::ExternalCommand;
+
+/// How often the settings file is checked for changes.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Polling intervals of the collectors in milliseconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Intervals {
+    /// Hardware data from TempMonitor.exe (on Windows, applied when the helper restarts)
+    pub hardware: u64,
+    /// CpuThreadsMonitor
+    pub cpu_threads: u64,
+    /// CpuCoresMonitor
+    pub cpu_cores: u64,
+    /// ProcessesMonitor
+    pub processes: u64,
+    /// GPU metrics loop
+    pub gpu: u64,
+    /// Network bandwidth sampler
+    pub bandwidth: u64,
+}
+
+impl Default for Intervals {
+    fn default() -> Self {
+        Self {
+            hardware: 500,
+            cpu_threads: 500,
+            cpu_cores: 300,
+            processes: 2000,
+            gpu: 1000,
+            bandwidth: 200,
+        }
+    }
+}
+
+/// Window size and last position.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowSettings {
+    pub width: f32,
+    pub height: f32,
+    /// Last position, saved once the window stops moving (None = let the OS decide).
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            width: 940.0,
+            height: 900.0,
+            x: None,
+            y: None,
+        }
+    }
+}
+
+impl WindowSettings {
+    pub fn position(&self) -> Option<(i32, i32)> {
+        self.x.zip(self.y)
+    }
+}
+
+/// Everything stored in `settings.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub intervals_ms: Intervals,
+    /// How many past CPU readings to keep for the usage bars.
+    pub history_size: usize,
+    /// How many points the bandwidth graph plots.
+    pub graph_points: usize,
+    pub window: WindowSettings,
+    /// Commands printing extra sensor samples (see `external_sensors.rs`).
+    pub external_sensors: Vec<ExternalCommand>,
+    /// Process names left out of the process table; `*` matches any text and
+    /// case is ignored.
+    pub ignored_processes: Vec<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            intervals_ms: Intervals::default(),
+            history_size: 30,
+            graph_points: 300,
+            window: WindowSettings::default(),
+            external_sensors: Vec::new(),
+            // The monitor itself, its helpers and the idle/system pseudo-processes
+            ignored_processes: ["idle", "system", "*cutemonitor*", "*tempmonitor*", "*powershell*"]
+                .iter()
+                .map(|pattern| pattern.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl Settings {
+    /// Checks every value against its allowed range and lists all problems.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut errors = Vec::new();
+
+        let intervals = &self.intervals_ms;
+        for (name, value) in [
+            ("hardware", intervals.hardware),
+            ("cpu_threads", intervals.cpu_threads),
+            ("cpu_cores", intervals.cpu_cores),
+            ("processes", intervals.processes),
+            ("gpu", intervals.gpu),
+            ("bandwidth", intervals.bandwidth),
+        ] {
+            if !(50..=60_000).contains(&value) {
+                errors.push(format!(
+                    "intervals_ms.{} must be between 50 and 60000",
+                    name
+                ));
+            }
+        }
+        if !(3..=1000).contains(&self.history_size) {
+            errors.push("history_size must be between 3 and 1000".to_string());
+        }
+        if !(2..=10_000).contains(&self.graph_points) {
+            errors.push("graph_points must be between 2 and 10000".to_string());
+        }
+        for (name, value) in [("width", self.window.width), ("height", self.window.height)] {
+            if !(200.0..=10_000.0).contains(&value) {
+                errors.push(format!("window.{} must be between 200 and 10000", name));
+            }
+        }
+
+        for (i, command) in self.external_sensors.iter().enumerate() {
+            if command.name.is_empty() || command.command.is_empty() {
+                errors.push(format!(
+                    "external_sensors[{}] needs a name and a command",
+                    i
+                ));
+            } else if self.external_sensors[..i]
+                .iter()
+                .any(|other| other.name == command.name)
+            {
+                errors.push(format!(
+                    "external_sensors[{}]: duplicate name {}",
+                    i, command.name
+                ));
+            }
+            if let Some(ms) = command.stall_timeout_ms {
+                if !(1_000..=3_600_000).contains(&ms) {
+                    errors.push(format!(
+                        "external_sensors[{}].stall_timeout_ms must be between 1000 and 3600000",
+                        i
+                    ));
+                }
+            }
+        }
+
+        if self.ignored_processes.iter().any(|pattern| pattern.trim().is_empty()) {
+            errors.push("ignored_processes may not contain empty patterns".to_string());
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+}
+
+lazy_static! {
+    static ref CURRENT: RwLock<Settings> = RwLock::new(Settings::default());
+}
+
+/// Settings file in use, chosen once at startup.
+static PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Returns a copy of the settings currently in effect.
+pub fn current() -> Settings {
+    CURRENT.read().unwrap().clone()
+}
+
+/// Replaces the settings in effect and returns the previous ones.
+pub fn apply(settings: Settings) -> Settings {
+    std::mem::replace(&mut *CURRENT.write().unwrap(), settings)
+}
+
+/// Default location of the settings file, if a config directory can be found.
+pub fn default_path() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    let dir = std::env::var_os("APPDATA").map(|appdata| PathBuf::from(appdata).join("Cutemonitor"));
+    #[cfg(not(target_os = "windows"))]
+    let dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|config| config.join("cutemonitor"));
+
+    dir.map(|dir| dir.join("settings.json"))
+}
+
+/// Reads and validates a settings file.
+pub fn load(path: &Path) -> Result<Settings, String> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let settings: Settings =
+        serde_json::from_str(&contents).map_err(|e| format!("{}: {}", path.display(), e))?;
+    settings
+        .validate()
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(settings)
+}
+
+/// Writes the settings as pretty-printed JSON, creating the directory if needed.
+pub fn save(path: &Path, settings: &Settings) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_string_pretty(settings).map_err(std::io::Error::from)?;
+    std::fs::write(path, json + "\n")
+}
+
+/// Loads the settings file at startup (`path` overrides the default location).
+///
+/// A missing file is created with the defaults so it can be edited. An invalid
+/// file is reported on stderr and the defaults are used instead.
+pub fn init(path: Option<PathBuf>) {
+    let Some(path) = path.or_else(default_path) else {
+        return;
+    };
+
+    if path.exists() {
+        match load(&path) {
+            Ok(settings) => {
+                apply(settings);
+            }
+            Err(e) => eprintln!("Ignoring invalid settings, using defaults: {}", e),
+        }
+    } else {
+        let mut settings = Settings::default();
+        // Carry over the position older versions kept in the registry
+        if let Some((x, y)) = crate::utils::load_window_position() {
+            settings.window.x = Some(x);
+            settings.window.y = Some(y);
+        }
+        if let Err(e) = save(&path, &settings) {
+            eprintln!("Failed to create settings file {}: {}", path.display(), e);
+        }
+        apply(settings);
+    }
+
+    let _ = PATH.set(path);
+}
+
+/// Remembers the window position in the settings file.
+///
+/// Only the position is written back, on top of what is on disk, so pending
+/// edits aren't lost. An invalid file is left alone rather than overwritten.
+pub fn save_window_position(x: i32, y: i32) {
+    {
+        let mut current = CURRENT.write().unwrap();
+        if current.window.position() == Some((x, y)) {
+            return;
+        }
+        current.window.x = Some(x);
+        current.window.y = Some(y);
+    }
+
+    let Some(path) = PATH.get() else {
+        return;
+    };
+    if let Ok(mut on_disk) = load(path) {
+        on_disk.window.x = Some(x);
+        on_disk.window.y = Some(y);
+        if let Err(e) = save(path, &on_disk) {
+            eprintln!("Failed to save window position: {}", e);
+        }
+    }
+}
+
+/// Creates an iced subscription that watches the settings file and yields
+/// the new settings whenever a valid edit differs from the ones in effect.
+pub fn subscription() -> iced::Subscription<Settings> {
+    let Some(path) = PATH.get().cloned() else {
+        return iced::Subscription::none();
+    };
+
+    let modified = |path: &Path| {
+        std::fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .ok()
+    };
+    let initial = modified(&path);
+    let stream = stream::unfold(
+        (path, initial),
+        move |(path, mut last_modified)| async move {
+            loop {
+                tokio::time::sleep(WATCH_INTERVAL).await;
+
+                let now_modified: Option<SystemTime> = modified(&path);
+                if now_modified == last_modified {
+                    continue;
+                }
+                last_modified = now_modified;
+
+                match load(&path) {
+                    Ok(settings) if settings != current() => {
+                        return Some((settings, (path, last_modified)));
+                    }
+                    Ok(_) => {}
+                    Err(e) => eprintln!("Ignoring invalid settings change: {}", e),
+                }
+            }
+        },
+    );
+    iced::Subscription::run_with_id("settings_watcher", stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_file_uses_defaults() {
+        let settings: Settings = serde_json::from_str(
+            r#"{"intervals_ms": {"gpu": 2000}, "window": {"x": 10, "y": 20}}"#,
+        )
+        .unwrap();
+        assert_eq!(settings.intervals_ms.gpu, 2000);
+        assert_eq!(settings.intervals_ms.cpu_cores, 300);
+        assert_eq!(settings.history_size, 30);
+        assert_eq!(settings.window.position(), Some((10, 20)));
+        assert_eq!(settings.window.width, 940.0);
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validation() {
+        let mut settings = Settings {
+            graph_points: 1,
+            ..Default::default()
+        };
+        settings.intervals_ms.bandwidth = 0;
+        let error = settings.validate().unwrap_err();
+        assert!(error.contains("intervals_ms.bandwidth"));
+        assert!(error.contains("graph_points"));
+
+        assert!(serde_json::from_str::<Settings>(r#"{"histroy_size": 10}"#).is_err());
+
+        let settings: Settings = serde_json::from_str(
+            r#"{"external_sensors": [{"name": "a", "command": "a.sh"}, {"name": "a", "command": "b.sh"}]}"#,
+        )
+        .unwrap();
+        assert!(settings.validate().unwrap_err().contains("duplicate name a"));
+
+        let settings: Settings = serde_json::from_str(
+            r#"{"external_sensors": [{"name": "ipmi", "command": "ipmitool", "stall_timeout_ms": 100}]}"#,
+        )
+        .unwrap();
+        assert!(settings.validate().unwrap_err().contains("stall_timeout_ms"));
+
+        let settings: Settings = serde_json::from_str(r#"{"ignored_processes": ["steam*", " "]}"#).unwrap();
+        assert!(settings.validate().unwrap_err().contains("ignored_processes"));
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let path = std::env::temp_dir()
+            .join(format!("cutemonitor_settings_{}", std::process::id()))
+            .join("settings.json");
+        let settings = Settings {
+            history_size: 60,
+            window: WindowSettings {
+                x: Some(-5),
+                y: Some(40),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        save(&path, &settings).unwrap();
+        assert_eq!(load(&path).unwrap(), settings);
+
+        std::fs::write(&path, r#"{"history_size": 0}"#).unwrap();
+        assert!(load(&path).is_err());
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -7,6 +7,20 @@ use crate::what_cpu_check;
 use crate::gpu_data;
 use crate::gpu_gui;
 use crate::bandwidth;
+use crate::alerts::{self, Sample};
+use crate::cpu_frequency::{self, ThrottleDetector};
+use crate::cpu_times::{self, CpuBreakdown, CpuTimesSample};
+use crate::cpu_topology::{self, CpuTopology};
+use crate::diagnostics::CollectorHealth;
+use crate::external_sensors::{ExternalEvent, ExternalSensors};
+use crate::fans::{FanHistory, FanReading};
+use crate::history::{self, Record};
+use crate::metrics_exporter;
+use crate::process_actions;
+use crate::process_table::{self, ProcessTable};
+use crate::sensors::SensorTree;
+use crate::settings::{self, Settings};
+use crate::supervisor::ChildState;
 
 /// Messages that can be sent to update the application state
 /// Messages that can be sent to update the application state
@@ -15,47 +29,96 @@ use crate::bandwidth;
 pub enum Message {
     /// Update hardware data (temperatures, voltages, etc.)
     UpdateData(hardware_checker::HardwareData),
+    /// Replace the full sensor tree (TempMonitor.exe protocol version 2)
+    UpdateSensors(SensorTree),
+    /// A hardware data line was rejected; carries the updated counters
+    CollectorHealth(CollectorHealth),
+    /// A sample, state change or rejected line from an external sensor command
+    ExternalSensor(ExternalEvent),
+    /// The TempMonitor.exe helper started, stopped or is being restarted
+    HardwareMonitorState(ChildState),
     /// Update per-core CPU usage percentages
     UpdateCores(Vec<f32>),
     /// Update per-thread CPU usage percentages
     UpdateThreads(Vec<f32>),
-    /// Update the list of top user processes
-    UpdateProcesses(Vec<what_cpu_check::ProcessInfo>),
+    /// Update the current clock of every logical CPU (MHz) with the mean thread
+    /// usage of the same refresh (None when replaying a recording)
+    UpdateFrequencies(Vec<u64>, Option<f32>),
+    /// Update the user/system/iowait/irq/steal breakdown of CPU time
+    UpdateCpuTimes(CpuTimesSample),
+    /// Update the list of user processes and their ranking by disk I/O
+    UpdateProcesses(Vec<what_cpu_check::ProcessInfo>, Vec<what_cpu_check::ProcessInfo>),
+    /// Sort, filter or expand the process table
+    ProcessTable(process_table::Message),
     /// Update GPU information
     UpdateMultipleGpus(Vec<gpu_data::GpuData>),
     /// Update bandwidth monitoring
     BandwidthMessage(bandwidth::Message),
+    /// Replace the CPU model and topology (sent when replaying a recording)
+    UpdateCpuInfo(what_cpu_check::CpuInfo),
+    /// The settings file was edited while running
+    SettingsChanged(Settings),
 
     /// Handle window resize events
     WindowResized((f32, f32)),
     /// Handle other window events
     WindowEvent(iced::window::Event),
+    /// Save the window position if the window hasn't moved since (see `POSITION_SAVE_DELAY`)
+    SaveWindowPosition((i32, i32)),
 }
 
+/// How long the window has to stay put before its position is written to
+/// settings.json, so dragging doesn't rewrite the file on every move event.
+const POSITION_SAVE_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+
 /// The main application state containing all data needed for the UI
 /// This struct holds current hardware readings, CPU usage history, and process information
 pub struct State {
     pub motherboard_model: String,
+    pub hardware_monitor_state: Option<ChildState>,
+    pub collector_health: CollectorHealth,
     pub cpu_temp: f32,
     pub ccd_temperatures: Vec<Option<f32>>,
     pub cpu_voltage: Option<f32>,
     pub cpu_power: Option<f32>,
     pub chipset_temp: Option<f32>,
     pub memory_usage: f32,
+    pub memory_temp: Option<f32>,
     pub total_memory_mb: i32,
     pub used_memory_mb: f32,
     pub memory_speed_mts: i32,
+    pub fans: Vec<FanReading>,
+    /// Recent RPMs for the fan sparklines
+    pub fan_history: FanHistory,
+    /// Every sensor from the latest reading, queryable by id
+    pub sensors: SensorTree,
+    /// Latest samples from the commands in `external_sensors`
+    pub external_sensors: ExternalSensors,
     pub cpu_model: String,
     pub cpu_cores: usize,
     pub cpu_threads: usize,
+    /// Which threads belong to which core and CCD
+    pub topology: CpuTopology,
     
     pub core_usages: Vec<Vec<f32>>,
     pub thread_usages: Vec<Vec<f32>>,
+    /// Latest clock of every logical CPU in MHz
+    pub thread_frequencies: Vec<u64>,
+    pub throttle: ThrottleDetector,
     pub total_usages: Vec<f32>,
+    /// Breakdown of the total CPU time, newest first
+    pub cpu_times: Vec<CpuBreakdown>,
+    /// Breakdown of every logical CPU's time, newest first
+    pub thread_cpu_times: Vec<Vec<CpuBreakdown>>,
+    /// Steal time only matters inside a VM, so it's highlighted there
+    pub virtual_machine: bool,
     pub top_processes: Vec<what_cpu_check::ProcessInfo>,
+    pub top_io_processes: Vec<what_cpu_check::ProcessInfo>,
+    pub process_table: ProcessTable,
 
     pub gpu_monitor: gpu_gui::GpuMonitor,
     pub bandwidth_monitor: bandwidth::BandwidthGraph,
+    pub alerts: alerts::AlertEngine,
 
     pub window_size: (f32, f32),
     pub window_position: Option<(i32, i32)>,
@@ -66,52 +129,115 @@ impl Default for State {
     fn default() -> Self {
         // Get CPU information at startup
         let cpu_info = what_cpu_check::get_cpu_info();
+        let topology = cpu_topology::current().clone();
+        let settings = settings::current();
+        let history_size = settings.history_size;
 
         Self {
             // Initialize hardware data as empty/zero (will be filled by subscriptions)
             motherboard_model: String::new(),
+            hardware_monitor_state: None,
+            collector_health: CollectorHealth::default(),
             cpu_temp: 0.0,
             ccd_temperatures: Vec::new(),
             cpu_voltage: None,
             cpu_power: None,
             chipset_temp: None,
             memory_usage: 0.0,
+            memory_temp: None,
             total_memory_mb: 0,
             used_memory_mb: 0.0,
             memory_speed_mts: 0,
+            fans: Vec::new(),
+            fan_history: FanHistory::default(),
+            sensors: SensorTree::default(),
+            external_sensors: ExternalSensors::default(),
 
             // CPU info from system detection
             cpu_model: cpu_info.model,
-            cpu_cores: cpu_info.cores,
+            cpu_cores: topology.cores.len(),
             cpu_threads: cpu_info.threads,
 
             // Initialize usage history buffers
             // Each core gets its own history buffer, pre-filled with 10% usage
-            core_usages: vec![vec![10.0; crate::HISTORY_SIZE]; cpu_info.cores],
+            core_usages: vec![vec![10.0; history_size]; topology.cores.len()],
+            topology,
             // Each thread gets its own history buffer, initialized to 0%
-            thread_usages: vec![vec![0.0; crate::HISTORY_SIZE]; cpu_info.threads],
+            thread_usages: vec![vec![0.0; history_size]; cpu_info.threads],
+            thread_frequencies: Vec::new(),
+            throttle: ThrottleDetector::default(),
             // Total CPU usage history, initialized to 0%
-            total_usages: vec![0.0; crate::HISTORY_SIZE],
+            total_usages: vec![0.0; history_size],
+            cpu_times: vec![CpuBreakdown::default(); history_size],
+            thread_cpu_times: vec![vec![CpuBreakdown::default(); history_size]; cpu_info.threads],
+            virtual_machine: what_cpu_check::is_virtual_machine(),
 
             // Process monitoring starts empty
             top_processes: Vec::new(),
+            top_io_processes: Vec::new(),
+            process_table: ProcessTable::default(),
 
             // GPU monitor
             gpu_monitor: gpu_gui::GpuMonitor::default(),
             // Bandwidth monitor
             bandwidth_monitor: bandwidth::BandwidthGraph::new(),
+            // Alert rules from --alerts (none by default)
+            alerts: alerts::AlertEngine::from_config(),
 
-            // Default window size
-            window_size: (800.0, 600.0),
-            window_position: None,
+            // Window geometry from settings.json
+            window_size: (settings.window.width, settings.window.height),
+            window_position: settings.window.position(),
         }
     }
 }
 
 impl State {
+    /// Feeds a reading to the alert rules and runs the hooks of any that changed.
+    fn observe_alerts(&mut self, sample: Sample) {
+        let events = self.alerts.observe(&sample, std::time::Instant::now());
+        alerts::dispatch(&events);
+    }
+
+    /// Resizes histories and moves/resizes the window after a settings change
+    fn apply_settings(&mut self, old: &Settings, new: &Settings) -> iced::Task<Message> {
+        if new.history_size != old.history_size {
+            for history in self.core_usages.iter_mut().chain(self.thread_usages.iter_mut()) {
+                history.resize(new.history_size, 0.0);
+            }
+            self.total_usages.resize(new.history_size, 0.0);
+            self.cpu_times.resize(new.history_size, CpuBreakdown::default());
+            for history in &mut self.thread_cpu_times {
+                history.resize(new.history_size, CpuBreakdown::default());
+            }
+        }
+        if new.graph_points != old.graph_points {
+            self.bandwidth_monitor.set_graph_points(new.graph_points);
+        }
+        // Forget sensors of removed commands (a replay brings its own)
+        if history::replay().is_none() {
+            self.external_sensors.retain_sources(&new.external_sensors);
+        }
+
+        let mut tasks = vec![];
+        if (new.window.width, new.window.height) != (old.window.width, old.window.height) {
+            let size = iced::Size::new(new.window.width, new.window.height);
+            tasks.push(iced::window::get_latest().and_then(move |id| iced::window::resize(id, size)));
+        }
+        if new.window.position() != old.window.position() {
+            if let Some((x, y)) = new.window.position() {
+                let point = iced::Point::new(x as f32, y as f32);
+                tasks.push(iced::window::get_latest().and_then(move |id| iced::window::move_to(id, point)));
+            }
+        }
+        iced::Task::batch(tasks)
+    }
+
     pub fn update(&mut self, message: Message) -> iced::Task<Message> {
         match message {
             Message::UpdateData(data) => {
+                metrics_exporter::record_hardware(&data);
+                history::record(|| Record::Hardware(data.clone()));
+                self.observe_alerts(Sample::Hardware(&data));
                 self.motherboard_model = data.motherboard_model;
                 self.cpu_temp = data.cpu_temp;
                 self.ccd_temperatures = data.ccd_temperatures;
@@ -119,50 +245,163 @@ impl State {
                 self.cpu_power = data.cpu_power;
                 self.chipset_temp = data.chipset_temp;
                 self.memory_usage = data.memory_usage;
+                self.memory_temp = data.memory_temp;
                 self.total_memory_mb = data.total_memory_mb;
                 self.memory_speed_mts = data.memory_speed_mts;
                 self.used_memory_mb = (data.memory_usage / 100.0) * data.total_memory_mb as f32;
+                self.fan_history.update(&data.fans, settings::current().history_size);
+                self.fans = data.fans;
+                iced::Task::none()
+            }
+            Message::UpdateSensors(tree) => {
+                metrics_exporter::record_sensors(&tree);
+                history::record(|| Record::Sensors(tree.clone()));
+                self.observe_alerts(Sample::Sensors(&tree));
+                self.sensors = tree;
+                iced::Task::none()
+            }
+            Message::CollectorHealth(health) => {
+                self.collector_health = health;
+                iced::Task::none()
+            }
+            Message::ExternalSensor(event) => {
+                match event {
+                    ExternalEvent::Reading(reading) => {
+                        history::record(|| Record::External(reading.clone()));
+                        self.external_sensors.update(reading);
+                        metrics_exporter::record_external(self.external_sensors.readings());
+                    }
+                    // Shown in the EXTERNAL SENSORS panel
+                    ExternalEvent::State { source, state } => {
+                        self.external_sensors.set_state(&source, state);
+                        metrics_exporter::record_external(self.external_sensors.readings());
+                    }
+                    ExternalEvent::Rejected { source, error } => {
+                        self.external_sensors.reject(&source, &error);
+                    }
+                }
+                iced::Task::none()
+            }
+            Message::HardwareMonitorState(state) => {
+                self.hardware_monitor_state = Some(state);
                 iced::Task::none()
             }
             Message::UpdateCores(core) => {
-                for (i, &usage) in core.iter().enumerate() {
-                    self.core_usages[i].insert(0, usage);
-                    self.core_usages[i].truncate(crate::HISTORY_SIZE);
+                history::record(|| Record::Cores(core.clone()));
+                let history_size = settings::current().history_size;
+                for (history, &usage) in self.core_usages.iter_mut().zip(&core) {
+                    history.insert(0, usage);
+                    history.truncate(history_size);
                 }
                 // Calculate and update total CPU usage
                 let total: f32 = core.iter().sum();
                 let avg_total = total / core.len() as f32;
                 self.total_usages.insert(0, avg_total);
-                self.total_usages.truncate(crate::HISTORY_SIZE);
+                self.total_usages.truncate(history_size);
                 iced::Task::none()
             }
             // Update CPU thread usage data
             Message::UpdateThreads(thread) => {
+                metrics_exporter::record_thread_usages(&thread);
+                history::record(|| Record::Threads(thread.clone()));
+                let history_size = settings::current().history_size;
+                self.observe_alerts(Sample::Threads(&thread));
                 // Update usage history for each thread
-                for (i, &usage) in thread.iter().enumerate() {
-                    self.thread_usages[i].insert(0, usage); // Add new reading
-                    self.thread_usages[i].truncate(crate::HISTORY_SIZE); // Maintain history size
+                for (history, &usage) in self.thread_usages.iter_mut().zip(&thread) {
+                    history.insert(0, usage); // Add new reading
+                    history.truncate(history_size); // Maintain history size
+                }
+                iced::Task::none()
+            }
+
+            // Update CPU clocks and check for thermal throttling
+            Message::UpdateFrequencies(frequencies, usage) => {
+                metrics_exporter::record_frequencies(&frequencies);
+                history::record(|| Record::Frequencies(frequencies.clone()));
+                // Recordings keep the clocks only, the latest total usage stands in
+                let usage = usage.unwrap_or(self.total_usages[0]);
+                self.throttle.update(&frequencies, usage, self.cpu_temp);
+                self.thread_frequencies = frequencies;
+                iced::Task::none()
+            }
+
+            // Update the CPU time breakdown shown under the total usage
+            Message::UpdateCpuTimes(sample) => {
+                metrics_exporter::record_cpu_times(&sample);
+                history::record(|| Record::CpuTimes(sample.clone()));
+                let history_size = settings::current().history_size;
+                self.cpu_times.insert(0, sample.total);
+                self.cpu_times.truncate(history_size);
+                for (history, &breakdown) in self.thread_cpu_times.iter_mut().zip(&sample.per_cpu) {
+                    history.insert(0, breakdown);
+                    history.truncate(history_size);
                 }
                 iced::Task::none()
             }
 
             // Update process monitoring data
-            Message::UpdateProcesses(processes) => {
+            Message::UpdateProcesses(processes, top_io) => {
+                self.process_table.retain_selection(&processes);
                 self.top_processes = processes;
+                self.top_io_processes = top_io;
                 iced::Task::none()
             }
+            Message::ProcessTable(table_msg) => match self.process_table.update(table_msg) {
+                // Run confirmed actions off the UI thread; ending a process can take seconds
+                Some((target, action)) => iced::Task::perform(process_actions::perform(target, action), |result| {
+                    Message::ProcessTable(process_table::Message::ActionFinished(result))
+                }),
+                None => iced::Task::none(),
+            },
 
             // Update GPU monitoring data
             Message::UpdateMultipleGpus(data_list) => {
+                metrics_exporter::record_gpus(&data_list);
+                history::record(|| Record::Gpus(data_list.clone()));
+                self.observe_alerts(Sample::Gpus(&data_list));
                 self.gpu_monitor.update_multiple_gpu_data(data_list);
                 iced::Task::none()
             }
 
             // Update bandwidth monitoring data
             Message::BandwidthMessage(bandwidth_msg) => {
+                if let bandwidth::Message::StatsUpdated(Some(stats)) = &bandwidth_msg {
+                    metrics_exporter::record_network(stats);
+                    history::record(|| Record::Network(stats.clone()));
+                    self.observe_alerts(Sample::Network(stats));
+                }
                 self.bandwidth_monitor.update(bandwidth_msg).map(Message::BandwidthMessage)
             }
 
+            // Adopt the recorded machine's CPU so the usage bars line up
+            Message::UpdateCpuInfo(info) => {
+                if info.cores > 0 && info.threads > 0 {
+                    self.cpu_model = info.model;
+                    self.cpu_cores = info.cores;
+                    self.cpu_threads = info.threads;
+                    // The local topology only fits a recording of this machine
+                    let local = cpu_topology::current();
+                    self.topology = if local.cores.len() == info.cores && local.thread_count() == info.threads {
+                        local.clone()
+                    } else {
+                        CpuTopology::uniform(info.cores, info.threads)
+                    };
+                    let history_size = settings::current().history_size;
+                    self.core_usages = vec![vec![10.0; history_size]; info.cores];
+                    self.thread_usages = vec![vec![0.0; history_size]; info.threads];
+                    self.thread_cpu_times = vec![vec![CpuBreakdown::default(); history_size]; info.threads];
+                    self.thread_frequencies = Vec::new();
+                    self.throttle = ThrottleDetector::default();
+                }
+                iced::Task::none()
+            }
+
+            // Apply an edited settings file; intervals are read by the collectors on their next tick
+            Message::SettingsChanged(new_settings) => {
+                let old_settings = settings::apply(new_settings.clone());
+                self.apply_settings(&old_settings, &new_settings)
+            }
+
             // Handle window resize events
             Message::WindowResized(size) => {
                 self.window_size = size; // Update stored window size
@@ -174,8 +413,16 @@ impl State {
                     let x = point.x as i32;
                     let y = point.y as i32;
                     self.window_position = Some((x, y));
-                    // Save to registry
-                    crate::utils::save_window_position(x, y);
+                    // Save to settings.json once the window stops moving
+                    return iced::Task::perform(tokio::time::sleep(POSITION_SAVE_DELAY), move |()| {
+                        Message::SaveWindowPosition((x, y))
+                    });
+                }
+                iced::Task::none()
+            }
+            Message::SaveWindowPosition((x, y)) => {
+                if self.window_position == Some((x, y)) {
+                    settings::save_window_position(x, y);
                 }
                 iced::Task::none()
             }
@@ -211,6 +458,26 @@ impl State {
             .into(),
         ];
 
+        // Only worth a row while the sensor helper isn't delivering data
+        if let Some(state) = &self.hardware_monitor_state {
+            if !matches!(state, ChildState::Running { .. }) {
+                elements.push(
+                    row![
+                        text("Sensor Helper:").size(13),
+                        container(
+                            text(state.to_string())
+                                .size(13)
+                                .color(iced::Color::from_rgb(1.0, 0.5, 0.0))
+                        )
+                        .align_x(iced::alignment::Horizontal::Right)
+                        .width(Length::Fill)
+                    ]
+                    .width(Length::Fill)
+                    .into(),
+                );
+            }
+        }
+
         for (i, &temp) in self.ccd_temperatures.iter().enumerate() {
             if let Some(t) = temp {
                 elements.push(
@@ -331,6 +598,24 @@ impl State {
             .into(),
         );
 
+        // Only boards with DIMM sensors report it
+        if let Some(temp) = self.memory_temp {
+            elements.push(
+                row![
+                    text("Memory Temperature:").size(13),
+                    container(
+                        text(format!("{:.1}°C", temp))
+                            .size(13)
+                            .color(crate::data_colouring::temperature_color(temp))
+                    )
+                    .align_x(iced::alignment::Horizontal::Right)
+                    .width(Length::Fill)
+                ]
+                .width(Length::Fill)
+                .into(),
+            );
+        }
+
         let hardware_info = container(
             column![
                 text("HARDWARE INFORMATION").size(17),
@@ -433,21 +718,58 @@ impl State {
             .padding(4)
             .height(Length::Fixed(200.0));
 
+        // Cores and threads are grouped by CCD, each group headed by the temperature
+        // of the same CCD number, where the L3 groups pair up with the reported CCDs.
+        // Otherwise they are grouped by L3 without temperatures.
+        let (groups, by_ccd) = match self.topology.ccds(self.ccd_temperatures.len()) {
+            Some(ccds) => (ccds, true),
+            None => (self.topology.l3_groups(), false),
+        };
+        let group_name = |n: usize| {
+            if by_ccd {
+                format!("CCD{}", n + 1)
+            } else {
+                format!("L3 group {}", n + 1)
+            }
+        };
+        let show_groups = groups.len() > 1 || by_ccd;
+
         // Create the CPU cores section
         let mut elements = vec![text("CPU CORES").size(13).into()];
-        for i in 0..self.cpu_cores {
-            // Get usage history for this core
-            let history = self.core_usages[i].clone();
-            // Create row with label and chart
-            let label = container(text(format!("Core {}", i)).size(13)).width(Length::Fixed(60.0)).align_x(iced::alignment::Horizontal::Left);
-            let chart = container(
-                canvas::Canvas::new(crate::canvas::BarChartProgram { history })
-                    .width(Length::Fill)
-                    .height(Length::Fixed(crate::BAR_HEIGHT)),
-            )
-            .style(crate::styles::black_border);
-            let row = row![label, chart].spacing(10).align_y(iced::Alignment::End);
-            elements.push(row.into());
+        let mut core_index = 0;
+        for (n, group) in groups.iter().enumerate() {
+            if show_groups {
+                let temperature = match self.ccd_temperatures.get(n).copied().flatten().filter(|_| by_ccd) {
+                    Some(t) => text(format!("{:.1}°C", t))
+                        .size(13)
+                        .color(crate::data_colouring::temperature_color(t)),
+                    None => text("").size(13),
+                };
+                elements.push(
+                    row![
+                        text(group_name(n)).size(13),
+                        container(temperature)
+                            .align_x(iced::alignment::Horizontal::Right)
+                            .width(Length::Fill)
+                    ]
+                    .into(),
+                );
+            }
+            for _ in group.iter() {
+                // Get usage history for this core
+                let history = self.core_usages.get(core_index).cloned().unwrap_or_default();
+                // Create row with label and chart
+                let label = container(text(format!("Core {}", core_index)).size(13)).width(Length::Fixed(60.0)).align_x(iced::alignment::Horizontal::Left);
+                let chart = container(
+                    canvas::Canvas::new(crate::canvas::BarChartProgram { history })
+                        .width(Length::Fill)
+                        .height(Length::Fixed(crate::BAR_HEIGHT)),
+                )
+                .style(crate::styles::black_border);
+                let row = row![label, chart].spacing(10).align_y(iced::Alignment::End);
+                elements.push(row.into());
+                core_index += 1;
+            }
         }
         let cores_column_inner = column(elements).spacing(1.0);
 
@@ -456,30 +778,78 @@ impl State {
             .padding([4.0, 10.0])
             .width(Length::FillPortion(65));
 
-        // Create the CPU threads section
-        let threads_per_core = self.cpu_threads / self.cpu_cores;
-        let mut threads_elements = vec![text("CPU THREADS").size(13).into()];
-        for i in 0..self.cpu_cores {
-            let mut thread_row = row![];
-            for j in 0..threads_per_core {
-                let idx = i * threads_per_core + j;
-                let current = self.thread_usages[idx][0];
-                let previous = self.thread_usages[idx].get(1).copied().unwrap_or(0.0);
-                let oldest = self.thread_usages[idx].get(2).copied().unwrap_or(0.0);
+        // Create the CPU threads section: one row per core with its SMT siblings and their clocks
+        let mut threads_header = row![text("CPU THREADS").size(13)].spacing(8);
+        if self.throttle.is_throttling() {
+            threads_header = threads_header.push(
+                text("THROTTLING")
+                    .size(13)
+                    .color(crate::data_colouring::temperature_color(crate::data_colouring::HOT_TEMPERATURE)),
+            );
+        }
+        if let Some(reference) = cpu_frequency::reference().describe() {
+            threads_header = threads_header.push(
+                container(text(reference).size(11))
+                    .align_x(iced::alignment::Horizontal::Right)
+                    .width(Length::Fill),
+            );
+        }
+        let mut threads_elements = vec![threads_header.into()];
+        for (n, group) in groups.iter().enumerate() {
+            if show_groups {
+                // Keeps the rows level with the cores column
+                threads_elements.push(text(format!("{} threads", group_name(n))).size(13).into());
+            }
+            for core in group.iter() {
+                let mut thread_row = row![];
+                for &idx in &core.threads {
+                    let Some(history) = self.thread_usages.get(idx) else {
+                        continue;
+                    };
+                    let current = history.first().copied().unwrap_or(0.0);
+                    let previous = history.get(1).copied().unwrap_or(0.0);
+                    let oldest = history.get(2).copied().unwrap_or(0.0);
+                    thread_row = thread_row.push(
+                        container(
+                            canvas::Canvas::new(crate::canvas::OverlayBarProgram {
+                                current,
+                                previous,
+                                oldest,
+                            })
+                            .width(Length::Fill)
+                            .height(Length::Fixed(crate::BAR_HEIGHT)),
+                        )
+                        .style(crate::styles::black_border),
+                    );
+                    // The thread's user/system/iowait/... history, same colors as under Total
+                    let times = self.thread_cpu_times.get(idx).map_or(&[][..], |history| history.as_slice());
+                    thread_row = thread_row.push(
+                        container(
+                            canvas::Canvas::new(crate::canvas::StackedBarChartProgram {
+                                history: times.iter().map(|breakdown| breakdown.parts().to_vec()).collect(),
+                                colors: crate::data_colouring::cpu_time_colors().to_vec(),
+                            })
+                            .width(Length::Fixed(36.0))
+                            .height(Length::Fixed(crate::BAR_HEIGHT)),
+                        )
+                        .style(crate::styles::black_border),
+                    );
+                }
+                // Clock of each sibling in GHz, e.g. "4.52/4.48"
+                let clocks: Vec<String> = core
+                    .threads
+                    .iter()
+                    .filter_map(|&idx| self.thread_frequencies.get(idx))
+                    .filter(|&&mhz| mhz > 0)
+                    .map(|&mhz| format!("{:.2}", mhz as f32 / 1000.0))
+                    .collect();
                 thread_row = thread_row.push(
-                    container(
-                        canvas::Canvas::new(crate::canvas::OverlayBarProgram {
-                            current,
-                            previous,
-                            oldest,
-                        })
-                        .width(Length::Fill)
-                        .height(Length::Fixed(crate::BAR_HEIGHT)),
-                    )
-                    .style(crate::styles::black_border),
+                    container(text(clocks.join("/")).size(11))
+                        .align_x(iced::alignment::Horizontal::Right)
+                        .width(Length::Fixed(64.0)),
                 );
+                threads_elements.push(thread_row.align_y(iced::Alignment::Center).into());
             }
-            threads_elements.push(thread_row.into());
         }
         let threads_column_inner = column(threads_elements).spacing(1.0);
         let graph_threads_container = container(threads_column_inner)
@@ -509,7 +879,42 @@ impl State {
         let total_row = row![total_text, total_graph, total_percentage]
             .spacing(10)
             .align_y(iced::Alignment::Center);
-        let graph_total_container = container(total_row)
+
+        // Stacked user/nice/system/iowait/irq/softirq/steal history with the current shares
+        let colors = crate::data_colouring::cpu_time_colors();
+        let latest = self.cpu_times.first().copied().unwrap_or_default();
+        let mut legend = row![].spacing(8);
+        for ((state, share), color) in cpu_times::STATES.iter().zip(latest.parts()).zip(colors) {
+            // Steal is only worth a slot inside a VM or when it actually happens
+            if *state == "steal" && !self.virtual_machine && share == 0.0 {
+                continue;
+            }
+            let label = text(format!("{} {:.1}%", state, share)).size(11).color(color);
+            legend = legend.push(if *state == "steal" && self.virtual_machine {
+                label.size(13)
+            } else {
+                label
+            });
+        }
+        let times_graph = container(
+            canvas::Canvas::new(crate::canvas::StackedBarChartProgram {
+                history: self.cpu_times.iter().map(|breakdown| breakdown.parts().to_vec()).collect(),
+                colors: colors.to_vec(),
+            })
+            .width(Length::Fill)
+            .height(Length::Fixed(crate::BAR_HEIGHT)),
+        )
+        .style(crate::styles::black_border)
+        .width(Length::FillPortion(90));
+        let times_row = row![
+            text("Time").size(13).width(Length::FillPortion(4)),
+            times_graph,
+            container(text("")).width(Length::FillPortion(6)),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center);
+
+        let graph_total_container = container(column![total_row, times_row, legend].spacing(2))
             .style(crate::styles::black_filled_box)
             .padding([4.0, 10.0])
             .width(Length::Fill);
@@ -537,33 +942,10 @@ impl State {
             ..Default::default()
         });
 
-        let top_processes: Vec<String> = self.top_processes.iter().take(4).map(|p| p.name.clone()).collect();
-
-        let mut process_columns = vec![];
-        for (i, name) in top_processes.iter().enumerate() {
-            let label = format!("{}. {}", i + 1, name);
-            let col = container(
-                text(label)
-                    .size(16)
-                    .align_x(iced::alignment::Horizontal::Center),
-            )
-            .width(Length::FillPortion(1))
-            .align_x(iced::alignment::Horizontal::Center);
-            process_columns.push(col.into());
-        }
-        while process_columns.len() < 3 {
-            process_columns.push(
-                container(text("").size(16))
-                    .width(Length::FillPortion(1))
-                    .into(),
-            );
-        }
         let userprocess_container = container(
-            column![
-                text("TOP USER PROCESSES:").size(13),
-                row(process_columns).spacing(4)
-            ]
-            .spacing(4),
+            self.process_table
+                .view(&self.top_processes, &self.top_io_processes)
+                .map(Message::ProcessTable),
         )
         .style(|_theme| container::Style {
             background: Some(iced::Background::Color(iced::Color::from_rgb(
@@ -579,11 +961,120 @@ impl State {
         .width(Length::Fill)
         .height(Length::Shrink);
 
+        // Fan panel: speed, PWM duty and a short RPM history per fan or pump
+        let fan_rows: Vec<iced::Element<'_, Message>> = self
+            .fans
+            .iter()
+            .map(|fan| {
+                let rpm_text = match fan.rpm {
+                    Some(rpm) => text(format!("{:.0} RPM", rpm))
+                        .size(13)
+                        .color(crate::data_colouring::fan_speed_color(rpm)),
+                    None => text("N/A").size(13),
+                };
+                let duty_text = match fan.duty_percent {
+                    Some(duty) => text(format!("{:.0}%", duty))
+                        .size(13)
+                        .color(crate::data_colouring::utilization_color(duty)),
+                    None => text("").size(13),
+                };
+                let sparkline = canvas::Canvas::new(crate::canvas::SparklineProgram {
+                    history: self.fan_history.get(&fan.id).to_vec(),
+                    color: crate::data_colouring::fan_speed_color(fan.rpm.unwrap_or(0.0)),
+                })
+                .width(Length::Fixed(120.0))
+                .height(Length::Fixed(16.0));
+                row![
+                    container(text(fan.name.clone()).size(13)).width(Length::Fill),
+                    container(rpm_text)
+                        .align_x(iced::alignment::Horizontal::Right)
+                        .width(Length::Fixed(80.0)),
+                    container(duty_text)
+                        .align_x(iced::alignment::Horizontal::Right)
+                        .width(Length::Fixed(50.0)),
+                    sparkline,
+                ]
+                .spacing(8)
+                .align_y(iced::alignment::Vertical::Center)
+                .width(Length::Fill)
+                .into()
+            })
+            .collect();
+        let fan_container = (!fan_rows.is_empty()).then(|| {
+            container(column![text("FANS").size(13), column(fan_rows).spacing(1)].spacing(4))
+                .style(|_theme| container::Style {
+                    background: Some(iced::Background::Color(iced::Color::from_rgb(
+                        0.3, 0.3, 0.3,
+                    ))),
+                    border: iced::Border {
+                        radius: 10.0.into(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .padding(6)
+                .width(Length::Fill)
+                .height(Length::Shrink)
+        });
+
+        // State of every external sensor command, then its samples one row each
+        let mut external_rows: Vec<iced::Element<'_, Message>> = Vec::new();
+        for status in self.external_sensors.sources() {
+            let state_color = match status.state {
+                ChildState::Running { .. } => iced::Color::from_rgb(0.6, 0.9, 0.6),
+                _ => iced::Color::from_rgb(1.0, 0.6, 0.3),
+            };
+            external_rows.push(
+                row![
+                    text(format!("{}:", status.source)).size(13),
+                    container(text(status.state.to_string()).size(13).color(state_color))
+                        .align_x(iced::alignment::Horizontal::Right)
+                        .width(Length::Fill)
+                ]
+                .width(Length::Fill)
+                .into(),
+            );
+            if let Some(error) = &status.last_error {
+                external_rows.push(text(format!("Last invalid sample: {}", error)).size(11).into());
+            }
+        }
+        external_rows.extend(self.external_sensors.readings().iter().map(|reading| {
+            row![
+                text(format!("{} ({}):", reading.label, reading.source)).size(13),
+                container(text(format!("{:.2} {}", reading.value, reading.unit)).size(13))
+                    .align_x(iced::alignment::Horizontal::Right)
+                    .width(Length::Fill)
+            ]
+            .width(Length::Fill)
+            .into()
+        }));
+        let external_container = (!external_rows.is_empty()).then(|| {
+            container(
+                column![text("EXTERNAL SENSORS").size(13), column(external_rows).spacing(1)]
+                    .spacing(4),
+            )
+            .style(|_theme| container::Style {
+                background: Some(iced::Background::Color(iced::Color::from_rgb(
+                    0.3, 0.3, 0.3,
+                ))),
+                border: iced::Border {
+                    radius: 10.0.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .padding(6)
+            .width(Length::Fill)
+            .height(Length::Shrink)
+        });
+
         let bandwidth_view = self.bandwidth_monitor.view().map(Message::BandwidthMessage);
         
         let bot_container = container(
             column![
                 userprocess_container,
+                column(fan_container.map(iced::Element::from)),
+                column(external_container.map(iced::Element::from)),
                 row![
                     container(bandwidth_view)
                         .height(iced::Length::Fixed(84.0))
@@ -612,25 +1103,85 @@ impl State {
 
 
 
-        container(
-            column![
-                top_container,
-                mid_container,
-                bot_container
-            ]
-            .spacing(0),
-        )
+        let mut sections = vec![];
+        // One banner line per firing alert rule, above everything else
+        let firing = self.alerts.firing();
+        if !firing.is_empty() {
+            let lines: Vec<iced::Element<'_, Message>> = firing
+                .into_iter()
+                .map(|(label, value)| text(format!("ALERT: {} (now {:.1})", label, value)).size(13).into())
+                .collect();
+            sections.push(
+                container(column(lines).spacing(2))
+                    .padding(6)
+                    .width(Length::Fill)
+                    .style(crate::styles::alert_banner)
+                    .into(),
+            );
+        }
+        // Rejected hardware data lines, so a frozen temperature panel explains itself
+        let health = &self.collector_health;
+        if health.errors() > 0 {
+            let mut lines: Vec<iced::Element<'_, Message>> = vec![text(format!(
+                "HARDWARE DATA: {} lines rejected ({} malformed, {} schema mismatch, {} missing field), {} parsed",
+                health.errors(),
+                health.malformed,
+                health.schema_mismatch,
+                health.missing_field,
+                health.parsed
+            ))
+            .size(13)
+            .into()];
+            if let Some(bad) = &health.last_bad_line {
+                lines.push(text(format!("Last error: {}", bad.error)).size(12).into());
+                let shown: String = bad.line.chars().take(150).collect();
+                lines.push(text(format!("Last line: {}", shown)).size(12).into());
+            }
+            sections.push(
+                container(column(lines).spacing(2))
+                    .padding(6)
+                    .width(Length::Fill)
+                    .style(crate::styles::diagnostics_panel)
+                    .into(),
+            );
+        }
+        sections.push(top_container.into());
+        sections.push(mid_container.into());
+        sections.push(bot_container.into());
+
+        container(column(sections).spacing(0))
             .into()
     }
 
     pub fn subscription(&self) -> iced::Subscription<Message> {
+        // A replay stands in for every live collector
+        if let Some(replay) = history::replay() {
+            return iced::Subscription::batch(vec![
+                history::replay_subscription(replay),
+                settings::subscription().map(Message::SettingsChanged),
+                iced::window::resize_events()
+                    .map(|(_id, size)| Message::WindowResized((size.width, size.height))),
+                iced::window::events().map(|(_id, event)| Message::WindowEvent(event)),
+            ]);
+        }
+
         iced::Subscription::batch(vec![
-            crate::hardware_checker::hardware_data_stream().map(Message::UpdateData),
+            crate::hardware_checker::hardware_data_stream().map(|event| match event {
+                hardware_checker::HardwareEvent::Data(data) => Message::UpdateData(data),
+                hardware_checker::HardwareEvent::Sensors(tree) => Message::UpdateSensors(tree),
+                hardware_checker::HardwareEvent::Health(health) => Message::CollectorHealth(health),
+                hardware_checker::HardwareEvent::Monitor(state) => Message::HardwareMonitorState(state),
+            }),
             iced_futures::subscription::from_recipe(crate::subscriptions::CpuCoresMonitor),
             iced_futures::subscription::from_recipe(crate::subscriptions::CpuThreadsMonitor),
+            iced_futures::subscription::from_recipe(crate::subscriptions::CpuFrequencyMonitor),
+            iced_futures::subscription::from_recipe(crate::subscriptions::CpuTimesMonitor),
             iced_futures::subscription::from_recipe(crate::subscriptions::ProcessesMonitor),
             crate::gpu_hardware_checker::multi_gpu_data_stream().map(Message::UpdateMultipleGpus),
             self.bandwidth_monitor.subscription().map(Message::BandwidthMessage),
+            metrics_exporter::subscription(),
+            settings::subscription().map(Message::SettingsChanged),
+            crate::external_sensors::subscription().map(Message::ExternalSensor),
 
             iced::window::resize_events()
                 .map(|(_id, size)| Message::WindowResized((size.width, size.height))),
@@ -33,4 +33,49 @@ pub fn black_filled_box(_theme: &iced::Theme) -> container::Style {
         },
         ..Default::default() // Use default values for other style properties
     }
-}
\ No newline at end of file
+}
+/// Creates a style for the alert banner shown while an alert rule is firing
+/// Dark red background so it stands out from the grey panels
+pub fn alert_banner(_theme: &iced::Theme) -> container::Style {
+    container::Style {
+        background: Some(iced::Background::Color(iced::Color::from_rgb(0.8, 0.0, 0.0))), // Dark red background
+        text_color: Some(iced::Color::WHITE), // White text for contrast
+        ..Default::default() // Use default values for other style properties
+    }
+}
+
+/// Creates a style for the diagnostics panel shown when hardware data lines are rejected
+/// Dark amber background: something is wrong, but it isn't an alert
+pub fn diagnostics_panel(_theme: &iced::Theme) -> container::Style {
+    container::Style {
+        background: Some(iced::Background::Color(iced::Color::from_rgb(0.45, 0.3, 0.0))), // Dark amber background
+        text_color: Some(iced::Color::WHITE), // White text for contrast
+        ..Default::default() // Use default values for other style properties
+    }
+}
+
+/// Creates a style for flat text buttons such as the process table headers
+/// No background so they read like labels; grey while hovered or pressed
+pub fn table_header(_theme: &iced::Theme, status: iced::widget::button::Status) -> iced::widget::button::Style {
+    let background = match status {
+        iced::widget::button::Status::Hovered | iced::widget::button::Status::Pressed => {
+            Some(iced::Background::Color(iced::Color::from_rgb(0.4, 0.4, 0.4))) // Lighter grey than the panel
+        }
+        _ => None, // Transparent otherwise
+    };
+    iced::widget::button::Style {
+        background,
+        text_color: iced::Color::WHITE, // Same as the other labels
+        ..Default::default() // Use default values for other style properties
+    }
+}
+
+/// Creates a style for the selected option among flat text buttons
+/// Same as table_header but with the grey background kept while selected
+pub fn table_header_active(_theme: &iced::Theme, _status: iced::widget::button::Status) -> iced::widget::button::Style {
+    iced::widget::button::Style {
+        background: Some(iced::Background::Color(iced::Color::from_rgb(0.4, 0.4, 0.4))), // Same grey as a hovered header
+        text_color: iced::Color::WHITE, // Same as the other labels
+        ..Default::default() // Use default values for other style properties
+    }
+}
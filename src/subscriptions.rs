@@ -2,6 +2,8 @@ use iced::advanced::subscription::{Hasher, Recipe};
 use iced::futures::stream::{self, BoxStream};
 use iced_futures::subscription::Event;
 
+use crate::cpu_times::CpuTimesSampler;
+use crate::settings;
 use crate::what_cpu_check;
 use crate::user_process_fetch;
 
@@ -21,7 +23,7 @@ impl Recipe for CpuThreadsMonitor {
         _input: BoxStream<'static, Event>,
     ) -> BoxStream<'static, Self::Output> {
         let stream = stream::unfold((), |()| async {
-            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            tokio::time::sleep(std::time::Duration::from_millis(settings::current().intervals_ms.cpu_threads)).await;
             let thread_usages = what_cpu_check::get_thread_usages().await;
             Some((crate::state::Message::UpdateThreads(thread_usages), ()))
         });
@@ -29,6 +31,60 @@ impl Recipe for CpuThreadsMonitor {
     }
 }
 
+/// A subscription recipe that reports the current clock of every logical CPU
+/// Runs at the thread interval, so both read the same sampler refresh
+pub struct CpuFrequencyMonitor;
+
+impl Recipe for CpuFrequencyMonitor {
+    type Output = crate::state::Message;
+
+    fn hash(&self, state: &mut Hasher) {
+        use std::hash::Hash;
+        std::any::TypeId::of::<Self>().hash(state);
+    }
+
+    fn stream(
+        self: Box<Self>,
+        _input: BoxStream<'static, Event>,
+    ) -> BoxStream<'static, Self::Output> {
+        let stream = stream::unfold((), |()| async {
+            tokio::time::sleep(std::time::Duration::from_millis(settings::current().intervals_ms.cpu_threads)).await;
+            let (frequencies, usage) = what_cpu_check::get_thread_frequencies_and_usage().await;
+            Some((crate::state::Message::UpdateFrequencies(frequencies, Some(usage)), ()))
+        });
+        Box::pin(stream)
+    }
+}
+
+/// A subscription recipe that breaks the CPU time down into user, system,
+/// iowait, irq and steal at the cpu_cores interval
+pub struct CpuTimesMonitor;
+
+impl Recipe for CpuTimesMonitor {
+    type Output = crate::state::Message;
+
+    fn hash(&self, state: &mut Hasher) {
+        use std::hash::Hash;
+        std::any::TypeId::of::<Self>().hash(state);
+    }
+
+    fn stream(
+        self: Box<Self>,
+        _input: BoxStream<'static, Event>,
+    ) -> BoxStream<'static, Self::Output> {
+        let stream = stream::unfold(CpuTimesSampler::new(), |mut sampler| async move {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_millis(settings::current().intervals_ms.cpu_cores)).await;
+                // The first reading only sets the baseline
+                if let Some(sample) = sampler.sample() {
+                    return Some((crate::state::Message::UpdateCpuTimes(sample), sampler));
+                }
+            }
+        });
+        Box::pin(stream)
+    }
+}
+
 /// A subscription recipe that monitors running processes and their CPU usage
 /// This helps identify which applications are using the most CPU resources
 pub struct ProcessesMonitor;
@@ -46,18 +102,12 @@ impl Recipe for ProcessesMonitor {
         _input: BoxStream<'static, Event>,
     ) -> BoxStream<'static, Self::Output> {
         let stream = stream::unfold((), |()| async {
-            // Update every 2000ms
-            tokio::time::sleep(std::time::Duration::from_millis(2000)).await;
-            // Get the top user processes by CPU usage
-            let top_processes = user_process_fetch::get_top_processes();
-            let processes: Vec<what_cpu_check::ProcessInfo> = top_processes.into_iter().map(|(name, description, cpu_usage)| {
-                what_cpu_check::ProcessInfo {
-                    name,
-                    description,
-                    cpu_usage: cpu_usage as f32,
-                }
-            }).collect();
-            Some((crate::state::Message::UpdateProcesses(processes), ()))
+            // Update every 2000ms by default (settings.json intervals_ms.processes)
+            tokio::time::sleep(std::time::Duration::from_millis(settings::current().intervals_ms.processes)).await;
+            // Get the top user processes by CPU usage and by disk I/O
+            let processes = user_process_fetch::get_top_processes();
+            let top_io = user_process_fetch::get_top_io_processes();
+            Some((crate::state::Message::UpdateProcesses(processes, top_io), ()))
         });
         Box::pin(stream)
     }
@@ -80,8 +130,8 @@ impl Recipe for CpuCoresMonitor {
         _input: BoxStream<'static, Event>,
     ) -> BoxStream<'static, Self::Output> {
         let stream = stream::unfold((), |()| async {
-            // Update every 300ms (fastest update rate for responsive UI)
-            tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+            // Update every 300ms by default (fastest update rate for responsive UI)
+            tokio::time::sleep(std::time::Duration::from_millis(settings::current().intervals_ms.cpu_cores)).await;
             // Get current usage for all CPU cores
             let core_usages = what_cpu_check::get_core_usages().await;
             Some((crate::state::Message::UpdateCores(core_usages), ()))
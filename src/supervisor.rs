@@ -0,0 +1,406 @@
+//! Supervisor for long-running helper processes such as TempMonitor.exe.
+//!
+//! The supervisor owns the child handle and streams its stdout line by line.
+//! When the child exits, can't be started, or stops printing for longer than
+//! the stall timeout, its process tree (and only that) is killed and it is
+//! restarted after an exponential backoff. Every state change is reported so
+//! the UI can show what the helper is doing. Output is read no faster than
+//! the caller takes it, so the child is paced by its consumer.
+//!
+//! Nothing here is specific to TempMonitor.exe: any command that prints lines
+//! can be supervised, which is how external sensor commands run and how the
+//! tests run it against shell scripts.
+
+use std::ffi::OsString;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use iced_futures::futures::{future, Stream};
+
+use crate::collector::{self, Stamped};
+
+/// Lines read ahead of `on_event`. Once they are queued the reader thread
+/// blocks, the pipe fills up and the child waits on its next write, so a slow
+/// consumer paces the child rather than lines piling up in memory.
+const LINE_BUFFER: usize = 16;
+
+/// What to run and how patiently.
+#[derive(Debug, Clone)]
+pub struct SupervisorConfig {
+    pub program: OsString,
+    pub args: Vec<OsString>,
+    /// Delay before the first restart; doubled on every further failure.
+    pub initial_backoff: Duration,
+    /// Upper bound for the restart delay.
+    pub max_backoff: Duration,
+    /// Restart the child if it prints nothing for this long.
+    pub stall_timeout: Duration,
+    /// A child that ran at least this long resets the backoff.
+    pub healthy_after: Duration,
+}
+
+impl SupervisorConfig {
+    pub fn new(program: impl Into<OsString>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            stall_timeout: Duration::from_secs(10),
+            healthy_after: Duration::from_secs(60),
+        }
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<OsString>,
+    {
+        self.args = args.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn stall_timeout(mut self, stall_timeout: Duration) -> Self {
+        self.stall_timeout = stall_timeout;
+        self
+    }
+}
+
+/// Lifecycle of the supervised child, as shown to the user.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChildState {
+    /// Spawning the child.
+    Starting,
+    /// The child is running with this process id.
+    Running { pid: u32 },
+    /// The child stopped; it is restarted after `delay`.
+    Restarting {
+        attempt: u32,
+        delay: Duration,
+        reason: String,
+    },
+}
+
+impl std::fmt::Display for ChildState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChildState::Starting => write!(f, "Starting"),
+            ChildState::Running { pid } => write!(f, "Running (PID {})", pid),
+            ChildState::Restarting {
+                attempt,
+                delay,
+                reason,
+            } => write!(
+                f,
+                "{}, restart #{} in {:.1}s",
+                reason,
+                attempt,
+                delay.as_secs_f32()
+            ),
+        }
+    }
+}
+
+/// Something the supervisor wants the caller to know.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SupervisorEvent {
+    /// A line the child printed, without the line ending.
+    Line(String),
+    State(ChildState),
+}
+
+/// Restart delay for the given attempt (1-based): initial, 2x, 4x, ... up to `max`.
+pub fn backoff_delay(attempt: u32, initial: Duration, max: Duration) -> Duration {
+    let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+    initial.saturating_mul(factor).min(max)
+}
+
+/// Owns a running child and kills its whole process tree when dropped.
+struct ChildGuard(Child);
+
+impl Drop for ChildGuard {
+    fn drop(&mut self) {
+        kill_tree(&mut self.0);
+    }
+}
+
+/// Kills `child` and everything it started, but nothing else.
+fn kill_tree(child: &mut Child) {
+    let pid = child.id().to_string();
+
+    #[cfg(target_os = "windows")]
+    let _ = Command::new("taskkill")
+        .args(["/f", "/t", "/pid", &pid])
+        .output();
+
+    // The child leads its own process group (see `spawn`), so this reaches its descendants
+    #[cfg(not(target_os = "windows"))]
+    let _ = Command::new("kill")
+        .args(["-KILL", "--", &format!("-{}", pid)])
+        .output();
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+fn spawn(config: &SupervisorConfig) -> std::io::Result<Child> {
+    let mut command = Command::new(&config.program);
+    command
+        .args(&config.args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped());
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+
+    command.spawn()
+}
+
+/// Runs the child until `on_event` returns false, restarting it whenever it
+/// exits or stalls. Blocks the calling thread; run it on a dedicated one.
+pub fn supervise(config: &SupervisorConfig, mut on_event: impl FnMut(SupervisorEvent) -> bool) {
+    let mut attempt = 0;
+
+    loop {
+        if !on_event(SupervisorEvent::State(ChildState::Starting)) {
+            return;
+        }
+
+        let started = Instant::now();
+        let reason = match spawn(config) {
+            Ok(mut child) => {
+                let pid = child.id();
+                let stdout = child.stdout.take();
+                let mut guard = ChildGuard(child);
+                if !on_event(SupervisorEvent::State(ChildState::Running { pid })) {
+                    return;
+                }
+
+                // Read on a separate thread so a silent child can be detected
+                let (lines_tx, lines_rx) = mpsc::sync_channel(LINE_BUFFER);
+                if let Some(stdout) = stdout {
+                    std::thread::spawn(move || {
+                        for line in BufReader::new(stdout).lines() {
+                            let Ok(line) = line else { break };
+                            if lines_tx.send(line).is_err() {
+                                break;
+                            }
+                        }
+                    });
+                }
+
+                let reason = loop {
+                    match lines_rx.recv_timeout(config.stall_timeout) {
+                        Ok(line) => {
+                            if !on_event(SupervisorEvent::Line(line)) {
+                                return;
+                            }
+                        }
+                        Err(RecvTimeoutError::Timeout) => {
+                            break format!(
+                                "No output for {:.0}s",
+                                config.stall_timeout.as_secs_f32()
+                            );
+                        }
+                        Err(RecvTimeoutError::Disconnected) => {
+                            break match guard.0.wait() {
+                                Ok(status) => format!("Exited ({})", status),
+                                Err(e) => format!("Exited ({})", e),
+                            };
+                        }
+                    }
+                };
+                drop(guard);
+                reason
+            }
+            Err(e) => format!("Failed to start: {}", e),
+        };
+
+        if started.elapsed() >= config.healthy_after {
+            attempt = 0;
+        }
+        attempt += 1;
+        let delay = backoff_delay(attempt, config.initial_backoff, config.max_backoff);
+        if !on_event(SupervisorEvent::State(ChildState::Restarting {
+            attempt,
+            delay,
+            reason,
+        })) {
+            return;
+        }
+        std::thread::sleep(delay);
+    }
+}
+
+/// Supervises a child on a dedicated thread and turns its output into a
+/// latest-value stream (see `collector.rs`), where items with equal `key`s
+/// replace each other until they are read.
+///
+/// `config` runs on that thread first, so it may do blocking setup such as
+/// extracting the program. Every line goes to `on_line`, which emits any number
+/// of items; state changes are wrapped by `on_state`. The child's process tree
+/// is killed once the stream is dropped.
+pub fn line_channel<T, K, C, L, S>(
+    key: fn(&T) -> K,
+    config: C,
+    mut on_line: L,
+    on_state: S,
+) -> impl Stream<Item = Stamped<T>>
+where
+    T: Send + 'static,
+    K: PartialEq + Send + 'static,
+    C: FnOnce() -> SupervisorConfig + Send + 'static,
+    L: FnMut(&str, &mut dyn FnMut(T)) + Send + 'static,
+    S: Fn(ChildState) -> T + Send + 'static,
+{
+    collector::latest(key, |publisher| async move {
+        std::thread::spawn(move || {
+            let config = config();
+            supervise(&config, |event| {
+                match event {
+                    SupervisorEvent::Line(line) => on_line(&line, &mut |item| publisher.publish(item)),
+                    SupervisorEvent::State(state) => publisher.publish(on_state(state)),
+                }
+                // Stop once the stream is gone
+                !publisher.is_closed()
+            });
+        });
+        // Keep the async task alive indefinitely.
+        future::pending::<()>().await
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_delay() {
+        let initial = Duration::from_millis(500);
+        let max = Duration::from_secs(30);
+        assert_eq!(backoff_delay(1, initial, max), Duration::from_millis(500));
+        assert_eq!(backoff_delay(2, initial, max), Duration::from_secs(1));
+        assert_eq!(backoff_delay(4, initial, max), Duration::from_secs(4));
+        assert_eq!(backoff_delay(10, initial, max), max);
+        assert_eq!(backoff_delay(100, initial, max), max);
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod linux_tests {
+    use super::*;
+
+    fn stub(script: &str) -> SupervisorConfig {
+        let mut config = SupervisorConfig::new("sh").args(["-c", script]);
+        config.initial_backoff = Duration::from_millis(10);
+        config.max_backoff = Duration::from_millis(40);
+        config.stall_timeout = Duration::from_millis(300);
+        config
+    }
+
+    /// Collects events until `limit` have been seen.
+    fn collect(config: &SupervisorConfig, limit: usize) -> Vec<SupervisorEvent> {
+        let mut events = Vec::new();
+        supervise(config, |event| {
+            events.push(event);
+            events.len() < limit
+        });
+        events
+    }
+
+    #[test]
+    fn test_restarts_after_exit_with_backoff() {
+        let events = collect(&stub("echo one; echo two; exit 3"), 13);
+
+        let lines: Vec<&SupervisorEvent> = events
+            .iter()
+            .filter(|e| matches!(e, SupervisorEvent::Line(_)))
+            .collect();
+        assert_eq!(lines[0], &SupervisorEvent::Line("one".to_string()));
+        assert_eq!(lines[1], &SupervisorEvent::Line("two".to_string()));
+
+        let restarts: Vec<(u32, Duration, &String)> = events
+            .iter()
+            .filter_map(|event| match event {
+                SupervisorEvent::State(ChildState::Restarting {
+                    attempt,
+                    delay,
+                    reason,
+                }) => Some((*attempt, *delay, reason)),
+                _ => None,
+            })
+            .collect();
+        assert!(restarts.len() >= 2, "{:?}", events);
+        assert_eq!(restarts[0].0, 1);
+        assert_eq!(restarts[0].1, Duration::from_millis(10));
+        assert!(
+            restarts[0].2.contains("exit status: 3"),
+            "{}",
+            restarts[0].2
+        );
+        assert_eq!(restarts[1].1, Duration::from_millis(20));
+    }
+
+    #[test]
+    fn test_stalled_child_tree_is_killed() {
+        // The grandchild would keep the pipe open forever if only `sh` were killed
+        let events = collect(&stub("sleep 30 & echo $!; wait"), 4);
+
+        let SupervisorEvent::Line(pid) = &events[2] else {
+            panic!("expected the grandchild pid, got {:?}", events);
+        };
+        assert!(matches!(
+            &events[3],
+            SupervisorEvent::State(ChildState::Restarting { reason, .. }) if reason.starts_with("No output")
+        ));
+
+        // Gone, or a zombie waiting for init to reap it
+        std::thread::sleep(Duration::from_millis(100));
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).unwrap_or_default();
+        assert!(stat.is_empty() || stat.contains(") Z "), "{}", stat);
+    }
+
+    #[test]
+    fn test_slow_consumer_paces_the_child() {
+        let mut pid = 0;
+        let mut written = Vec::new();
+        supervise(&stub("exec yes"), |event| {
+            match event {
+                SupervisorEvent::State(ChildState::Running { pid: running }) => pid = running,
+                SupervisorEvent::Line(_) => {
+                    // Take a line now and then, far slower than `yes` prints them
+                    std::thread::sleep(Duration::from_millis(150));
+                    let io = std::fs::read_to_string(format!("/proc/{}/io", pid)).unwrap();
+                    let wchar = io
+                        .lines()
+                        .find_map(|line| line.strip_prefix("wchar: "))
+                        .unwrap();
+                    written.push(wchar.parse::<u64>().unwrap());
+                }
+                SupervisorEvent::State(_) => {}
+            }
+            written.len() < 3
+        });
+        // Blocked on a full pipe rather than writing into an ever-growing queue
+        assert_eq!(written[1], written[2], "{:?}", written);
+    }
+
+    #[test]
+    fn test_missing_program_is_retried() {
+        let mut config = stub("");
+        config.program = "/nonexistent/TempMonitor.exe".into();
+        let events = collect(&config, 4);
+
+        assert_eq!(events[0], SupervisorEvent::State(ChildState::Starting));
+        assert!(matches!(
+            &events[1],
+            SupervisorEvent::State(ChildState::Restarting { attempt: 1, reason, .. }) if reason.starts_with("Failed to start")
+        ));
+        assert_eq!(events[2], SupervisorEvent::State(ChildState::Starting));
+    }
+}
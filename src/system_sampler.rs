@@ -0,0 +1,390 @@
+//! One long-lived `sysinfo::System` shared by every CPU and process collector.
+//!
+//! `System::new_all()` enumerates every process, disk and network interface,
+//! which is far more work than reading the CPU load. The sampler starts empty
+//! and refreshes only the part a caller asks for. A part refreshed less than
+//! `MINIMUM_CPU_UPDATE_INTERVAL` ago is served from the previous refresh, so
+//! the thread and core monitors read the same sample instead of each taking
+//! their own. The time spent refreshing is counted per part (see `overhead()`).
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use sysinfo::{CpuRefreshKind, ProcessRefreshKind, System, UpdateKind, MINIMUM_CPU_UPDATE_INTERVAL};
+
+use crate::what_cpu_check::CpuInfo;
+
+/// Usage of every logical CPU and every physical core from one refresh.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CpuSample {
+    pub thread_usages: Vec<f32>,
+    /// Thread usages combined per core (see `cpu_topology.rs`).
+    pub core_usages: Vec<f32>,
+    /// Current clock of every logical CPU (0 where it can't be read).
+    pub frequencies_mhz: Vec<u64>,
+}
+
+/// One running process.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProcessSample {
+    pub pid: u32,
+    /// Parent process, if it is known and still running.
+    pub parent_pid: Option<u32>,
+    pub name: String,
+    /// Path of the executable, where the process may be inspected.
+    pub exe: Option<PathBuf>,
+    /// Share of the whole machine in percent (100 = every logical CPU busy).
+    pub cpu_usage: f32,
+    /// Resident memory in bytes.
+    pub memory_bytes: u64,
+    /// Memory only this process uses: anonymous resident memory on Linux,
+    /// private bytes on Windows.
+    pub private_bytes: Option<u64>,
+    pub threads: Option<u32>,
+    /// Bytes read from storage per second since the previous process refresh
+    /// (`read_bytes` of /proc/[pid]/io on Linux, the I/O counters on Windows).
+    pub read_bytes_per_second: f64,
+    /// Bytes written per second since the previous process refresh.
+    pub write_bytes_per_second: f64,
+    /// Bytes read since the process started.
+    pub total_read_bytes: u64,
+    /// Bytes written since the process started.
+    pub total_written_bytes: u64,
+    /// Seconds since the process started.
+    pub uptime_secs: u64,
+    /// When the process started, in seconds since the Unix epoch.
+    pub start_time: u64,
+}
+
+/// Refresh count and time spent for one part of the sampler.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PartOverhead {
+    pub refreshes: u64,
+    pub last_us: u64,
+    pub total_us: u64,
+}
+
+impl PartOverhead {
+    fn add(&mut self, elapsed: Duration) {
+        let elapsed_us = elapsed.as_micros() as u64;
+        self.refreshes += 1;
+        self.last_us = elapsed_us;
+        self.total_us += elapsed_us;
+    }
+}
+
+/// Time the sampler spent refreshing, per part.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Overhead {
+    pub cpu: PartOverhead,
+    pub processes: PartOverhead,
+}
+
+struct Sampler {
+    system: System,
+    cpu_refreshed: Option<Instant>,
+    /// CPU usage needs two refreshes; the first one only sets the baseline.
+    cpu_primed: bool,
+    processes_refreshed: Option<Instant>,
+    /// Time between the last two process refreshes, for the I/O rates.
+    processes_elapsed: Duration,
+    /// Thread count per PID, which sysinfo doesn't report on Windows.
+    #[cfg(target_os = "windows")]
+    thread_counts: std::collections::HashMap<u32, u32>,
+    overhead: Overhead,
+}
+
+impl Sampler {
+    fn new() -> Self {
+        let mut system = System::new();
+        // Lists the CPUs with their brand and sets the usage baseline
+        system.refresh_cpu();
+        Self {
+            system,
+            cpu_refreshed: Some(Instant::now()),
+            cpu_primed: false,
+            processes_refreshed: None,
+            processes_elapsed: Duration::ZERO,
+            #[cfg(target_os = "windows")]
+            thread_counts: std::collections::HashMap::new(),
+            overhead: Overhead::default(),
+        }
+    }
+
+    fn refresh_cpu(&mut self, now: Instant) {
+        if is_fresh(self.cpu_refreshed, now) {
+            return;
+        }
+        let started = Instant::now();
+        self.system
+            .refresh_cpu_specifics(CpuRefreshKind::new().with_cpu_usage().with_frequency());
+        self.overhead.cpu.add(started.elapsed());
+        self.cpu_refreshed = Some(now);
+        self.cpu_primed = true;
+    }
+
+    fn refresh_processes(&mut self, now: Instant) {
+        if is_fresh(self.processes_refreshed, now) {
+            return;
+        }
+        let started = Instant::now();
+        self.system
+            .refresh_processes_specifics(
+                ProcessRefreshKind::new()
+                    .with_cpu()
+                    .with_memory()
+                    .with_exe(UpdateKind::OnlyIfNotSet)
+                    .with_disk_usage(),
+            );
+        #[cfg(target_os = "windows")]
+        {
+            self.thread_counts = windows_thread_counts();
+        }
+        self.overhead.processes.add(started.elapsed());
+        self.processes_elapsed = self
+            .processes_refreshed
+            .map_or(Duration::ZERO, |refreshed| now.saturating_duration_since(refreshed));
+        self.processes_refreshed = Some(now);
+    }
+
+    fn process_sample(&self, process: &sysinfo::Process) -> ProcessSample {
+        // sysinfo reports 100% per fully used logical CPU
+        let threads = self.system.cpus().len().max(1) as f32;
+        let disk_usage = process.disk_usage();
+        let elapsed = self.processes_elapsed.as_secs_f64();
+        let pid = process.pid().as_u32();
+        ProcessSample {
+            pid,
+            parent_pid: process.parent().map(|parent| parent.as_u32()),
+            name: process.name().to_string(),
+            exe: process.exe().map(|exe| exe.to_path_buf()),
+            cpu_usage: process.cpu_usage() / threads,
+            memory_bytes: process.memory(),
+            #[cfg(target_os = "linux")]
+            private_bytes: std::fs::read_to_string(format!("/proc/{}/status", pid))
+                .ok()
+                .and_then(|status| parse_rss_anon(&status)),
+            #[cfg(target_os = "windows")]
+            private_bytes: Some(process.virtual_memory()),
+            // The main thread isn't listed among the tasks
+            #[cfg(target_os = "linux")]
+            threads: process.tasks().map(|tasks| tasks.len() as u32 + 1),
+            #[cfg(target_os = "windows")]
+            threads: self.thread_counts.get(&pid).copied(),
+            read_bytes_per_second: per_second(disk_usage.read_bytes, elapsed),
+            write_bytes_per_second: per_second(disk_usage.written_bytes, elapsed),
+            total_read_bytes: disk_usage.total_read_bytes,
+            total_written_bytes: disk_usage.total_written_bytes,
+            uptime_secs: process.run_time(),
+            start_time: process.start_time(),
+        }
+    }
+
+    fn thread_usages(&self) -> Vec<f32> {
+        self.system
+            .cpus()
+            .iter()
+            .map(|cpu| cpu.cpu_usage())
+            .collect()
+    }
+}
+
+/// Bytes counted over `elapsed` seconds as a rate; 0 before there is a previous refresh.
+fn per_second(bytes: u64, elapsed: f64) -> f64 {
+    if elapsed > 0.0 {
+        bytes as f64 / elapsed
+    } else {
+        0.0
+    }
+}
+
+/// True if a part refreshed at `refreshed` can still be served at `now`.
+fn is_fresh(refreshed: Option<Instant>, now: Instant) -> bool {
+    refreshed.is_some_and(|refreshed| {
+        now.saturating_duration_since(refreshed) < MINIMUM_CPU_UPDATE_INTERVAL
+    })
+}
+
+lazy_static! {
+    static ref SAMPLER: Mutex<Sampler> = Mutex::new(Sampler::new());
+}
+
+/// Model, core and thread count of the CPU, without refreshing anything.
+pub fn cpu_info() -> CpuInfo {
+    let sampler = SAMPLER.lock().unwrap();
+    let system = &sampler.system;
+    let threads = system.cpus().len();
+    CpuInfo {
+        model: system
+            .cpus()
+            .first()
+            .map(|cpu| cpu.brand().to_string())
+            .unwrap_or_default(),
+        cores: system.physical_core_count().unwrap_or(threads.max(1)),
+        threads,
+    }
+}
+
+/// Thread and core usage, refreshed if the last sample is older than
+/// `MINIMUM_CPU_UPDATE_INTERVAL`. The very first call waits for that interval,
+/// since usage is measured between two refreshes.
+pub async fn cpu() -> CpuSample {
+    let primed = SAMPLER.lock().unwrap().cpu_primed;
+    if !primed {
+        tokio::time::sleep(MINIMUM_CPU_UPDATE_INTERVAL).await;
+    }
+
+    let (thread_usages, frequencies_mhz) = {
+        let mut sampler = SAMPLER.lock().unwrap();
+        sampler.refresh_cpu(Instant::now());
+        let frequencies = sampler
+            .system
+            .cpus()
+            .iter()
+            .map(|cpu| cpu.frequency())
+            .collect();
+        (sampler.thread_usages(), frequencies)
+    };
+    CpuSample {
+        core_usages: crate::cpu_topology::current().core_usages(&thread_usages),
+        thread_usages,
+        frequencies_mhz,
+    }
+}
+
+/// Every running process with its CPU and memory use, refreshed if the last
+/// process sample is older than `MINIMUM_CPU_UPDATE_INTERVAL`.
+pub fn processes() -> Vec<ProcessSample> {
+    let mut sampler = SAMPLER.lock().unwrap();
+    sampler.refresh_processes(Instant::now());
+    sampler
+        .system
+        .processes()
+        .values()
+        // On Linux the threads of a process are listed as processes too
+        .filter(|process| process.thread_kind().is_none())
+        .map(|process| sampler.process_sample(process))
+        .collect()
+}
+
+/// Reads `RssAnon` (resident memory not backed by a file) from
+/// `/proc/[pid]/status`, in bytes.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_rss_anon(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("RssAnon:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// Thread count of every process from a Toolhelp snapshot.
+#[cfg(target_os = "windows")]
+fn windows_thread_counts() -> std::collections::HashMap<u32, u32> {
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W,
+        TH32CS_SNAPPROCESS,
+    };
+
+    let mut counts = std::collections::HashMap::new();
+    unsafe {
+        let Ok(snapshot) = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) else {
+            return counts;
+        };
+        let mut entry = PROCESSENTRY32W {
+            dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+            ..Default::default()
+        };
+        let mut found = Process32FirstW(snapshot, &mut entry).is_ok();
+        while found {
+            counts.insert(entry.th32ProcessID, entry.cntThreads);
+            found = Process32NextW(snapshot, &mut entry).is_ok();
+        }
+        let _ = CloseHandle(snapshot);
+    }
+    counts
+}
+
+/// Time spent refreshing so far.
+pub fn overhead() -> Overhead {
+    SAMPLER.lock().unwrap().overhead
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_refreshes_are_shared() {
+        let mut sampler = Sampler::new();
+        let now = Instant::now() + MINIMUM_CPU_UPDATE_INTERVAL;
+        sampler.refresh_cpu(now);
+        sampler.refresh_cpu(now + MINIMUM_CPU_UPDATE_INTERVAL / 2);
+        assert_eq!(sampler.overhead.cpu.refreshes, 1);
+        assert!(sampler.cpu_primed);
+
+        sampler.refresh_cpu(now + MINIMUM_CPU_UPDATE_INTERVAL);
+        assert_eq!(sampler.overhead.cpu.refreshes, 2);
+        assert!(sampler.overhead.cpu.total_us >= sampler.overhead.cpu.last_us);
+        // Processes are only listed when asked for
+        assert_eq!(sampler.overhead.processes.refreshes, 0);
+        assert_eq!(sampler.thread_usages().len(), sampler.system.cpus().len());
+    }
+
+    #[test]
+    fn test_parse_rss_anon() {
+        let status = "Name:\tfirefox\nVmRSS:\t  812345 kB\nRssAnon:\t  600000 kB\nRssFile:\t  200000 kB\n";
+        assert_eq!(parse_rss_anon(status), Some(600000 * 1024));
+        // Kernel threads have no memory lines at all
+        assert_eq!(parse_rss_anon("Name:\tkthreadd\n"), None);
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod linux_tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+
+    #[test]
+    fn test_reads_disk_io_of_a_writing_child() {
+        // Next to the test binary, on the disk of the build: temp_dir() may be a
+        // tmpfs, whose writes never reach storage and aren't counted
+        let file = std::env::current_exe()
+            .unwrap()
+            .with_file_name(format!("cutemonitor-io-{}", std::process::id()));
+        // Write 4 MiB through to the disk, say so, then stay around to be sampled
+        let script = format!(
+            "dd if=/dev/zero of='{}' bs=1M count=4 conv=fsync 2>/dev/null; echo written; sleep 5",
+            file.display()
+        );
+        let mut child = Command::new("sh")
+            .args(["-c", &script])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(child.stdout.take().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        assert_eq!(line.trim(), "written");
+
+        let mut sampler = Sampler::new();
+        sampler.refresh_processes(Instant::now());
+        let pid = sysinfo::Pid::from_u32(child.id());
+        let sample = sampler.process_sample(sampler.system.process(pid).unwrap());
+        let _ = child.kill();
+        let _ = child.wait();
+        let _ = std::fs::remove_file(&file);
+
+        // dd runs as a child of the shell; the shell's counters include its reaped children
+        assert!(
+            sample.total_written_bytes >= 4 * 1024 * 1024,
+            "{:?}",
+            sample
+        );
+        // Without a previous refresh there is no rate yet
+        assert_eq!(sample.write_bytes_per_second, 0.0);
+    }
+}
@@ -1,88 +1,250 @@
-use serde::Deserialize;
+// This module handles fetching and monitoring user processes
+// Besides the CPU ranking it ranks processes by disk I/O (bytes read plus written per second)
+// CPU usage comes from the shared sysinfo sampler (tick deltas between two refreshes,
+// read from /proc/[pid]/stat on Linux and the process times on Windows)
+// Processes matching settings.json ignored_processes (by default the monitor's own
+// processes and the idle/system pseudo-processes) are filtered out to show only user applications
 
-// This module handles fetching and monitoring user processes using PowerShell
-// It uses Get-Counter for real-time CPU metrics, providing accurate and efficient monitoring
-// System processes are filtered out to show only user applications
-
-use std::fs::File;
-use std::io::Write;
-use std::os::windows::process::CommandExt;
-use std::process::Command;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use std::thread;
 use std::time::Duration;
 use lazy_static::lazy_static;
 
-#[derive(Clone, Deserialize)]
-pub struct Process {
-    #[serde(rename = "Name")]
-    pub name: String,
-    #[serde(rename = "Description")]
-    pub description: Option<String>,
-    #[serde(rename = "CPU")]
-    pub cpu_usage: f64,
-}
+use crate::settings;
+use crate::system_sampler::{self, ProcessSample};
+use crate::what_cpu_check::ProcessInfo;
 
 lazy_static! {
-    static ref CURRENT_TOP_PROCESSES: Mutex<Vec<(String, String, f64)>> = Mutex::new(Vec::new());
+    static ref CURRENT_PROCESSES: Mutex<Vec<ProcessInfo>> = Mutex::new(Vec::new());
     static ref IS_LOADING: Mutex<bool> = Mutex::new(true);
+    // File descriptions by executable path; reading version info every second would be wasteful
+    static ref DESCRIPTIONS: Mutex<HashMap<PathBuf, Option<String>>> = Mutex::new(HashMap::new());
 }
 
 pub fn start_collection() {
     // Start background thread for continuous updates (no blocking initial query)
     thread::spawn(move || {
+        // The first refresh only sets the CPU time baseline, so every process would read 0%
+        system_sampler::processes();
+        thread::sleep(Duration::from_millis(1000));
         loop {
-            match fetch_processes() {
-                Ok(processes) => {
-                    let top4: Vec<(String, String, f64)> = processes.into_iter().take(4).map(|p| {
-                        let desc_str = p.description.unwrap_or_else(|| "Unknown".to_string());
-                        (p.name, desc_str, p.cpu_usage)
-                    }).collect();
-                    
-                    *CURRENT_TOP_PROCESSES.lock().unwrap() = top4;
-                    *IS_LOADING.lock().unwrap() = false;
-                }
-                Err(e) => eprintln!("Error: {}", e),
-            }
+            let processes = fetch_processes(&settings::current().ignored_processes);
+            *CURRENT_PROCESSES.lock().unwrap() = processes;
+            *IS_LOADING.lock().unwrap() = false;
             thread::sleep(Duration::from_millis(1000));
         }
     });
 }
 
-pub fn get_top_processes() -> Vec<(String, String, f64)> {
+/// Every user process, highest CPU usage first
+pub fn get_top_processes() -> Vec<ProcessInfo> {
     let is_loading = *IS_LOADING.lock().unwrap();
-    let processes = CURRENT_TOP_PROCESSES.lock().unwrap().clone();
-    
+    let processes = CURRENT_PROCESSES.lock().unwrap().clone();
+
     if is_loading && processes.is_empty() {
-        vec![
-            ("Loading...".to_string(), "Initializing process monitor".to_string(), 0.0),
-            ("".to_string(), "".to_string(), 0.0),
-            ("".to_string(), "".to_string(), 0.0),
-            ("".to_string(), "".to_string(), 0.0),
-        ]
+        vec![ProcessInfo {
+            name: "Loading...".to_string(),
+            description: "Initializing process monitor".to_string(),
+            ..Default::default()
+        }]
     } else {
         processes
     }
 }
 
-// Fetches the top user processes using PowerShell
-// Uses Get-Counter for real-time CPU metrics, providing accurate and efficient monitoring
-// Filters out system processes and returns the top 4 by CPU usage
-fn fetch_processes() -> std::result::Result<Vec<Process>, String> {
-    let command = r#"$ProgressPreference = 'SilentlyContinue'; Get-Counter '\Process(*)\% Processor Time' -ErrorAction SilentlyContinue | Select-Object -ExpandProperty CounterSamples | Where-Object { $_.InstanceName -notlike '_total' -and $_.InstanceName -notlike 'idle' -and $_.InstanceName -notlike 'system' -and $_.InstanceName -notlike '*cutemonitor*' -and $_.InstanceName -notlike '*TempMonitor*' -and $_.InstanceName -notlike '*powershell*' } | ForEach-Object { $procName = ($_.InstanceName -split '#')[0]; $desc = (Get-Process -Name $procName -ErrorAction SilentlyContinue | Select-Object -First 1).Description; [PSCustomObject]@{ Name = $procName; Description = $desc; CPU = [math]::Round($_.CookedValue / [Environment]::ProcessorCount, 2) } } | Sort-Object CPU -Descending | Select-Object -First 4 | ConvertTo-Json"#;
-    
-    let output = Command::new("powershell")
-        .arg("-Command")
-        .arg(command)
-        .creation_flags(0x08000000) // CREATE_NO_WINDOW
-        .output()
-        .map_err(|e| e.to_string())?;
-
-    if !output.stderr.is_empty() {
-        let _ = File::create("debug_err.txt").and_then(|mut f| f.write_all(&output.stderr));
-        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+/// User processes doing disk I/O, highest read plus write rate first
+pub fn get_top_io_processes() -> Vec<ProcessInfo> {
+    rank_by_io(&CURRENT_PROCESSES.lock().unwrap())
+}
+
+/// The processes reading or writing anything, busiest first
+fn rank_by_io(processes: &[ProcessInfo]) -> Vec<ProcessInfo> {
+    let mut ranked: Vec<ProcessInfo> = processes
+        .iter()
+        .filter(|process| process.io_bytes_per_second() > 0.0)
+        .cloned()
+        .collect();
+    ranked.sort_by(|a, b| b.io_bytes_per_second().total_cmp(&a.io_bytes_per_second()));
+    ranked
+}
+
+/// Display name of a process: the executable name without ".exe"
+fn display_name(name: &str) -> &str {
+    name.strip_suffix(".exe").unwrap_or(name)
+}
+
+/// Case-insensitive match of a name against a pattern where `*` matches any text
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let name = name.to_lowercase();
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let (first, rest) = parts.split_first().unwrap();
+    let Some(mut remaining) = name.strip_prefix(first) else {
+        return false;
+    };
+    // Without any `*` the whole name has to match
+    let Some((last, middle)) = rest.split_last() else {
+        return remaining.is_empty();
+    };
+    for part in middle {
+        match remaining.find(part) {
+            Some(pos) => remaining = &remaining[pos + part.len()..],
+            None => return false,
+        }
     }
-    
-    let processes: Vec<Process> = ::serde_json::from_str(&String::from_utf8_lossy(&output.stdout)).unwrap_or_default();
-    Ok(processes)
-}
\ No newline at end of file
+    remaining.ends_with(last)
+}
+
+/// True for the idle pseudo-process and names on the ignore list
+fn is_ignored(sample: &ProcessSample, ignored: &[String]) -> bool {
+    let name = display_name(&sample.name);
+    sample.pid == 0 || ignored.iter().any(|pattern| matches_pattern(pattern, name))
+}
+
+/// User processes sorted by CPU usage, highest first
+fn top_user_processes(samples: Vec<ProcessSample>, ignored: &[String]) -> Vec<ProcessSample> {
+    let mut processes: Vec<ProcessSample> =
+        samples.into_iter().filter(|sample| !is_ignored(sample, ignored)).collect();
+    processes.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage));
+    processes
+}
+
+// Fetches the user processes from the shared sampler, sorted by CPU usage
+// CPU usage is the share of the whole machine, like the Get-Counter value divided by the processor count
+fn fetch_processes(ignored: &[String]) -> Vec<ProcessInfo> {
+    top_user_processes(system_sampler::processes(), ignored)
+        .into_iter()
+        .map(|sample| ProcessInfo {
+            pid: sample.pid,
+            parent_pid: sample.parent_pid,
+            name: display_name(&sample.name).to_string(),
+            description: sample
+                .exe
+                .as_deref()
+                .and_then(cached_description)
+                .unwrap_or_else(|| "Unknown".to_string()),
+            cpu_usage: sample.cpu_usage,
+            memory_bytes: sample.memory_bytes,
+            private_bytes: sample.private_bytes,
+            threads: sample.threads,
+            read_bytes_per_second: sample.read_bytes_per_second,
+            write_bytes_per_second: sample.write_bytes_per_second,
+            total_read_bytes: sample.total_read_bytes,
+            total_written_bytes: sample.total_written_bytes,
+            uptime_secs: sample.uptime_secs,
+            start_time: sample.start_time,
+        })
+        .collect()
+}
+
+fn cached_description(exe: &Path) -> Option<String> {
+    DESCRIPTIONS
+        .lock()
+        .unwrap()
+        .entry(exe.to_path_buf())
+        .or_insert_with(|| file_description(exe))
+        .clone()
+}
+
+/// The FileDescription of the executable's version resource (what Task Manager shows)
+#[cfg(target_os = "windows")]
+fn file_description(exe: &Path) -> Option<String> {
+    use std::ffi::c_void;
+    use windows::core::{w, HSTRING};
+    use windows::Win32::Storage::FileSystem::{GetFileVersionInfoSizeW, GetFileVersionInfoW, VerQueryValueW};
+
+    let path = HSTRING::from(exe.to_string_lossy().as_ref());
+    unsafe {
+        let size = GetFileVersionInfoSizeW(&path, None);
+        if size == 0 {
+            return None;
+        }
+        let mut data = vec![0u8; size as usize];
+        GetFileVersionInfoW(&path, 0, size, data.as_mut_ptr() as *mut c_void).ok()?;
+
+        // The strings are stored per language and code page; take the first pair
+        let mut translation: *mut c_void = std::ptr::null_mut();
+        let mut len = 0u32;
+        if !VerQueryValueW(data.as_ptr() as *const c_void, w!("\\VarFileInfo\\Translation"), &mut translation, &mut len).as_bool()
+            || len < 4
+        {
+            return None;
+        }
+        let [language, code_page] = *(translation as *const [u16; 2]);
+        let key = HSTRING::from(format!("\\StringFileInfo\\{:04x}{:04x}\\FileDescription", language, code_page));
+
+        let mut value: *mut c_void = std::ptr::null_mut();
+        let mut chars = 0u32;
+        if !VerQueryValueW(data.as_ptr() as *const c_void, &key, &mut value, &mut chars).as_bool() || chars == 0 {
+            return None;
+        }
+        let text = String::from_utf16_lossy(std::slice::from_raw_parts(value as *const u16, chars as usize));
+        let text = text.trim_end_matches('\0').trim();
+        (!text.is_empty()).then(|| text.to_string())
+    }
+}
+
+/// Linux executables carry no description
+#[cfg(target_os = "linux")]
+fn file_description(_exe: &Path) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(pid: u32, name: &str, cpu_usage: f32) -> ProcessSample {
+        ProcessSample {
+            pid,
+            name: name.to_string(),
+            cpu_usage,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("*TempMonitor*", "tempmonitor"));
+        assert!(matches_pattern("steam*", "steamwebhelper"));
+        assert!(matches_pattern("*helper", "steamwebhelper"));
+        assert!(matches_pattern("s*m*r", "steamwebhelper"));
+        assert!(!matches_pattern("system", "systemd"));
+        assert!(!matches_pattern("*helper*x", "steamwebhelper"));
+        // The end may not reuse text matched in the middle
+        assert!(!matches_pattern("*ab*ba", "aba"));
+    }
+
+    #[test]
+    fn test_rank_by_io() {
+        let io = |pid: u32, read: f64, write: f64| ProcessInfo {
+            pid,
+            read_bytes_per_second: read,
+            write_bytes_per_second: write,
+            ..Default::default()
+        };
+        let processes = vec![io(1, 0.0, 0.0), io(2, 4096.0, 0.0), io(3, 1024.0, 8192.0), io(4, 0.0, 512.0)];
+        let pids: Vec<u32> = rank_by_io(&processes).iter().map(|process| process.pid).collect();
+        assert_eq!(pids, vec![3, 2, 4]);
+    }
+
+    #[test]
+    fn test_excludes_ignored_processes() {
+        let samples = vec![
+            sample(0, "System Idle Process", 90.0),
+            sample(4, "System", 3.0),
+            sample(10, "cutemonitor.exe", 5.0),
+            sample(11, "TempMonitor.exe", 4.0),
+            sample(12, "powershell.exe", 2.0),
+            sample(13, "chrome.exe", 1.5),
+            sample(14, "firefox", 7.25),
+            sample(15, "systemd", 0.5),
+        ];
+        let ignored = crate::settings::Settings::default().ignored_processes;
+        let top = top_user_processes(samples, &ignored);
+        let names: Vec<&str> = top.iter().map(|process| display_name(&process.name)).collect();
+        assert_eq!(names, vec!["firefox", "chrome", "systemd"]);
+    }
+}
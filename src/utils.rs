@@ -6,6 +6,9 @@ use windows::Win32::Security::{
 };
 use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
 use windows::Win32::System::Registry::*;
+use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+use windows::Win32::UI::Shell::ShellExecuteW;
+use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
 
 /// Checks if the current process is running with administrator privileges
 /// This is required because hardware monitoring libraries need elevated permissions
@@ -43,20 +46,71 @@ pub fn is_admin() -> bool {
     }
 }
 
-pub fn save_window_position(x: i32, y: i32) {
+/// Starts this exe again with the same arguments through the UAC prompt
+/// The manifest runs the exe as the invoking user, so the window asks for admin rights here
+/// Returns false if the elevated copy couldn't be started (e.g. the user declined the prompt)
+pub fn relaunch_as_admin() -> bool {
+    let Ok(exe) = std::env::current_exe() else {
+        return false;
+    };
+    let arguments: Vec<String> = std::env::args().skip(1).map(|arg| quote_argument(&arg)).collect();
+    let exe = windows::core::HSTRING::from(exe.to_string_lossy().as_ref());
+    let arguments = windows::core::HSTRING::from(arguments.join(" "));
     unsafe {
-        let mut key: HKEY = HKEY::default();
-        let subkey = windows::core::PCWSTR::from_raw("Software\\Cutemonitor\0".encode_utf16().collect::<Vec<_>>().as_ptr());
-        if RegCreateKeyExW(HKEY_CURRENT_USER, subkey, 0, windows::core::PCWSTR::null(), REG_OPTION_NON_VOLATILE, KEY_WRITE, None, &mut key, None).is_ok() {
-            let value_name = windows::core::PCWSTR::from_raw("WindowPosition\0".encode_utf16().collect::<Vec<_>>().as_ptr());
-            let data_utf16: Vec<u16> = format!("{},{}", x, y).encode_utf16().chain(std::iter::once(0)).collect();
-            let data_slice = std::slice::from_raw_parts(data_utf16.as_ptr() as *const u8, data_utf16.len() * 2);
-            let _ = RegSetValueExW(key, value_name, 0, REG_SZ, Some(data_slice));
-            let _ = RegCloseKey(key);
+        // Values above 32 mean success
+        let result = ShellExecuteW(
+            windows::Win32::Foundation::HWND::default(),
+            windows::core::w!("runas"), // Ask for elevation
+            &exe,
+            &arguments,
+            windows::core::PCWSTR::null(),
+            SW_SHOWNORMAL,
+        );
+        result.0 > 32
+    }
+}
+
+/// Quotes an argument so the C runtime's command-line parsing gives it back unchanged
+fn quote_argument(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        return arg.to_string();
+    }
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            // Backslashes before a quote are escaped, and so is the quote
+            '"' => {
+                quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+                quoted.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                quoted.push_str(&"\\".repeat(backslashes));
+                quoted.push(c);
+                backslashes = 0;
+            }
         }
     }
+    // Backslashes before the closing quote are escaped too
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
 }
 
+/// Attaches to the console of the process that launched us (e.g. a terminal)
+/// The binary uses the "windows" subsystem, so without this nothing printed to
+/// stdout/stderr would be visible when run from a command prompt
+/// Failure (e.g. launched from Explorer, no parent console) is ignored
+pub fn attach_parent_console() {
+    unsafe {
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+/// Reads the window position older versions saved in the registry
+/// Only used to migrate it into settings.json on first start
 pub fn load_window_position() -> Option<(i32, i32)> {
     unsafe {
         let mut key: HKEY = HKEY::default();
@@ -82,4 +136,18 @@ pub fn load_window_position() -> Option<(i32, i32)> {
         }
     }
     None
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_argument() {
+        assert_eq!(quote_argument("--replay"), "--replay");
+        assert_eq!(quote_argument(""), "\"\"");
+        assert_eq!(quote_argument("C:\\My Files\\run.jsonl"), "\"C:\\My Files\\run.jsonl\"");
+        assert_eq!(quote_argument("a \"b\""), "\"a \\\"b\\\"\"");
+        assert_eq!(quote_argument("C:\\dir name\\"), "\"C:\\dir name\\\\\"");
+    }
+}
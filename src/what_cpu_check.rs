@@ -1,25 +1,53 @@
 // This module handles CPU information detection and monitoring
 // It provides functions to get CPU specs, detect virtual machines, and monitor usage
 
-use sysinfo::System;
+use serde::{Deserialize, Serialize};
 use windows::Win32::System::Registry::{RegCloseKey, RegOpenKeyExW, HKEY_LOCAL_MACHINE, KEY_READ};
 
 
 
-/// Information about a running process and its CPU usage
-#[derive(Debug, Clone)]
+/// Information about a running process, one row of the process table
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct ProcessInfo {
+    /// Process ID
+    pub pid: u32,
+    /// Process ID of the parent, for grouping by process tree
+    pub parent_pid: Option<u32>,
     /// The name of the process (usually the executable name)
     pub name: String,
     /// The description of the process (from file properties)
-    #[allow(dead_code)]
     pub description: String,
     /// Current CPU usage percentage for this process
-    #[allow(dead_code)]
     pub cpu_usage: f32,
+    /// Resident memory (working set) in bytes
+    pub memory_bytes: u64,
+    /// Memory not shared with other processes in bytes, where known
+    pub private_bytes: Option<u64>,
+    /// Number of threads, where known
+    pub threads: Option<u32>,
+    /// Bytes read from storage per second
+    pub read_bytes_per_second: f64,
+    /// Bytes written to storage per second
+    pub write_bytes_per_second: f64,
+    /// Bytes read since the process started
+    pub total_read_bytes: u64,
+    /// Bytes written since the process started
+    pub total_written_bytes: u64,
+    /// Seconds since the process started
+    pub uptime_secs: u64,
+    /// When the process started (seconds since the Unix epoch), which tells a reused PID apart
+    pub start_time: u64,
+}
+
+impl ProcessInfo {
+    /// Bytes read and written per second
+    pub fn io_bytes_per_second(&self) -> f64 {
+        self.read_bytes_per_second + self.write_bytes_per_second
+    }
 }
 
 /// Basic CPU information structure
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CpuInfo {
     /// CPU model/brand string (e.g., "AMD Ryzen 5 5600X")
     pub model: String,
@@ -32,36 +60,18 @@ pub struct CpuInfo {
 /// Gets basic information about the system's CPU
 /// This includes the model name, physical core count, and thread count
 pub fn get_cpu_info() -> CpuInfo {
-    let mut sys = System::new_all();
-    sys.refresh_all();
-
-    // Get information from the first CPU (they're usually identical)
-    let cpu = sys.cpus().first().unwrap();
-    let model = cpu.brand().to_string();
-    let cores = sys.physical_core_count().unwrap_or(1);
-    let threads = sys.cpus().len();
-
-    CpuInfo {
-        model,
-        cores,
-        threads,
-    }
+    crate::system_sampler::cpu_info()
 }
 
 /// Checks if the system is running in a virtual machine
 /// This affects which CPU logo to display in the UI
 /// Returns true if running in a VM, false for bare metal
-#[allow(dead_code)]
 pub fn is_virtual_machine() -> bool {
     // Check CPU brand for common virtualization signatures
-    let mut sys = System::new();
-    sys.refresh_all();
-    if let Some(cpu) = sys.cpus().first() {
-        let brand = cpu.brand().to_lowercase();
-        // QEMU and KVM are common open-source virtualization platforms
-        if brand.contains("qemu") || brand.contains("kvm") {
-            return true;
-        }
+    let brand = get_cpu_info().model.to_lowercase();
+    // QEMU and KVM are common open-source virtualization platforms
+    if brand.contains("qemu") || brand.contains("kvm") {
+        return true;
     }
 
     // Check Windows registry for Hyper-V (Microsoft's virtualization platform)
@@ -78,29 +88,31 @@ pub fn is_virtual_machine() -> bool {
     false // Not running in a virtual machine
 }
 
+/// Usage of every physical core, the mean of its SMT siblings (see `cpu_topology.rs`)
+/// Served from the shared sampler, so it matches the thread usages of the same refresh
 pub async fn get_core_usages() -> Vec<f32> {
-    let mut sys = System::new_all();
-    sys.refresh_cpu();
-    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-    sys.refresh_cpu();
-    let cores = sys.physical_core_count().unwrap_or(1);
-    let usages = (0..cores)
-        .map(|i| sys.cpus()[i].cpu_usage())
-        .collect::<Vec<f32>>();
-    usages
+    crate::system_sampler::cpu().await.core_usages
 }
 
+/// Usage of every logical CPU from the shared sampler
 pub async fn get_thread_usages() -> Vec<f32> {
-    let mut sys = System::new_all();
-    sys.refresh_cpu();
-    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-    sys.refresh_cpu();
-    let usages = sys
-        .cpus()
-        .iter()
-        .map(|cpu| cpu.cpu_usage())
-        .collect::<Vec<f32>>();
-    usages
+    crate::system_sampler::cpu().await.thread_usages
+}
+
+/// Current clock of every logical CPU in MHz from the shared sampler
+pub async fn get_thread_frequencies() -> Vec<u64> {
+    crate::system_sampler::cpu().await.frequencies_mhz
+}
+
+/// Current clocks together with the mean thread usage of the same sampler refresh
+pub async fn get_thread_frequencies_and_usage() -> (Vec<u64>, f32) {
+    let sample = crate::system_sampler::cpu().await;
+    let usage = if sample.thread_usages.is_empty() {
+        0.0
+    } else {
+        sample.thread_usages.iter().sum::<f32>() / sample.thread_usages.len() as f32
+    };
+    (sample.frequencies_mhz, usage)
 }
 
 